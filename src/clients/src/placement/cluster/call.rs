@@ -0,0 +1,177 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::{
+    placement::{retry_call, PlacementCenterService},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use prost::Message as _;
+use protocol::cluster::{
+    ClusterStatusReply, ClusterStatusRequest, HeartbeatRequest, ListNodesReply, ListNodesRequest,
+    MemberStatusReply, MemberStatusRequest, RegisterNodeRequest, UnRegisterNodeRequest,
+};
+use protocol::common::CommonReply;
+use std::sync::Arc;
+
+pub async fn placement_register_node(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: RegisterNodeRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = RegisterNodeRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::RegisterNode,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_un_register_node(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: UnRegisterNodeRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = UnRegisterNodeRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::UnRegisterNode,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_list_nodes(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ListNodesRequest,
+) -> Result<ListNodesReply, RobustMQError> {
+    let request_data = ListNodesRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::ListNodes,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ListNodesReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_heartbeat(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: HeartbeatRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = HeartbeatRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::Heartbeat,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_cluster_status(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ClusterStatusRequest,
+) -> Result<ClusterStatusReply, RobustMQError> {
+    let request_data = ClusterStatusRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::ClusterStatus,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ClusterStatusReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_member_status(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: MemberStatusRequest,
+) -> Result<MemberStatusReply, RobustMQError> {
+    let request_data = MemberStatusRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Cluster,
+        PlacementCenterInterface::MemberStatus,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match MemberStatusReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
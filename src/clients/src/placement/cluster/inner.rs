@@ -0,0 +1,125 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::ClusterServiceManager;
+use common_base::errors::RobustMQError;
+use mobc::Connection;
+use prost::Message;
+use protocol::cluster::{
+    ClusterStatusReply, ClusterStatusRequest, HeartbeatRequest, ListNodesReply, ListNodesRequest,
+    MemberStatusReply, MemberStatusRequest, RegisterNodeRequest, UnRegisterNodeRequest,
+};
+use protocol::common::CommonReply;
+
+pub(crate) async fn inner_register_node(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match RegisterNodeRequest::decode(request.as_ref()) {
+        Ok(request) => match client.register_node(request).await {
+            Ok(result) => {
+                return Ok(CommonReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_un_register_node(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match UnRegisterNodeRequest::decode(request.as_ref()) {
+        Ok(request) => match client.un_register_node(request).await {
+            Ok(result) => {
+                return Ok(CommonReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_list_nodes(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ListNodesRequest::decode(request.as_ref()) {
+        Ok(request) => match client.list_nodes(request).await {
+            Ok(result) => {
+                return Ok(ListNodesReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_heartbeat(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match HeartbeatRequest::decode(request.as_ref()) {
+        Ok(request) => match client.heartbeat(request).await {
+            Ok(result) => {
+                return Ok(CommonReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_cluster_status(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ClusterStatusRequest::decode(request.as_ref()) {
+        Ok(request) => match client.cluster_status(request).await {
+            Ok(result) => {
+                return Ok(ClusterStatusReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_member_status(
+    mut client: Connection<ClusterServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match MemberStatusRequest::decode(request.as_ref()) {
+        Ok(request) => match client.member_status(request).await {
+            Ok(result) => {
+                return Ok(MemberStatusReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
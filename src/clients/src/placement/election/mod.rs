@@ -0,0 +1,110 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::poll::ClientPool;
+use common_base::errors::RobustMQError;
+use inner::{inner_campaign, inner_resign};
+use mobc::{Connection, Manager};
+use protocol::election::election_service_client::ElectionServiceClient;
+use std::sync::Arc;
+use tonic::transport::Channel;
+
+pub mod call;
+mod inner;
+
+pub(crate) async fn election_interface_call(
+    interface: PlacementCenterInterface,
+    client_poll: Arc<ClientPool>,
+    addr: String,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match election_client(client_poll.clone(), addr.clone()).await {
+        Ok(client) => {
+            let result = match interface {
+                PlacementCenterInterface::Campaign => inner_campaign(client, request.clone()).await,
+                PlacementCenterInterface::Resign => inner_resign(client, request.clone()).await,
+                _ => {
+                    return Err(RobustMQError::CommmonError(format!(
+                        "election service does not support service interfaces [{:?}]",
+                        interface
+                    )))
+                }
+            };
+            match result {
+                Ok(data) => return Ok(data),
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+async fn election_client(
+    client_poll: Arc<ClientPool>,
+    addr: String,
+) -> Result<Connection<ElectionServiceManager>, RobustMQError> {
+    match client_poll
+        .placement_center_election_services_client(addr)
+        .await
+    {
+        Ok(client) => {
+            return Ok(client);
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ElectionServiceManager {
+    pub addr: String,
+}
+
+impl ElectionServiceManager {
+    pub fn new(addr: String) -> Self {
+        Self { addr }
+    }
+}
+
+#[tonic::async_trait]
+impl Manager for ElectionServiceManager {
+    type Connection = ElectionServiceClient<Channel>;
+    type Error = RobustMQError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let addr = format!("http://{}", self.addr.clone());
+        match ElectionServiceClient::connect(addr.clone()).await {
+            Ok(client) => {
+                return Ok(client);
+            }
+            Err(err) => {
+                return Err(RobustMQError::CommmonError(format!(
+                    "{},{}",
+                    err.to_string(),
+                    addr
+                )))
+            }
+        };
+    }
+
+    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        Ok(conn)
+    }
+}
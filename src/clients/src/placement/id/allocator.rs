@@ -0,0 +1,147 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::call::placement_allocate_id;
+use crate::poll::ClientPool;
+use common_base::errors::RobustMQError;
+use log::error;
+use protocol::id::AllocateIdRequest;
+use std::{collections::VecDeque, ops::Range, sync::Arc};
+use tokio::sync::Mutex;
+
+#[derive(Default)]
+struct CachedIds {
+    blocks: VecDeque<Range<u64>>,
+    refilling: bool,
+}
+
+impl CachedIds {
+    fn remaining(&self) -> u64 {
+        return self
+            .blocks
+            .iter()
+            .map(|block| block.end - block.start)
+            .sum();
+    }
+
+    fn take(&mut self) -> Option<u64> {
+        while let Some(block) = self.blocks.front_mut() {
+            if let Some(id) = block.next() {
+                return Some(id);
+            }
+            self.blocks.pop_front();
+        }
+        return None;
+    }
+}
+
+/// Hands out the IDs of one sequence from blocks taken from the placement center. The
+/// next block is fetched in the background once half of a block is left, so callers
+/// rarely wait for the placement center. IDs of a dropped allocator are never reused.
+#[derive(Clone)]
+pub struct IdAllocator {
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    name: String,
+    block_size: u64,
+    cached: Arc<Mutex<CachedIds>>,
+    // Only one block is fetched at a time, so blocks are cached in the order they were
+    // allocated and the IDs of an allocator keep increasing.
+    fetch: Arc<Mutex<()>>,
+}
+
+impl IdAllocator {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        addrs: Vec<String>,
+        name: String,
+        block_size: u64,
+    ) -> Self {
+        return IdAllocator {
+            client_poll,
+            addrs,
+            name,
+            block_size: block_size.max(1),
+            cached: Arc::new(Mutex::new(CachedIds::default())),
+            fetch: Arc::new(Mutex::new(())),
+        };
+    }
+
+    pub async fn next_id(&self) -> Result<u64, RobustMQError> {
+        loop {
+            let mut cached = self.cached.lock().await;
+            if let Some(id) = cached.take() {
+                if cached.remaining() <= self.block_size / 2 && !cached.refilling {
+                    cached.refilling = true;
+                    self.refill();
+                }
+                return Ok(id);
+            }
+            drop(cached);
+
+            // Nothing cached, wait for a block. A refill in flight may bring one first.
+            let _fetch = self.fetch.lock().await;
+            let mut cached = self.cached.lock().await;
+            if cached.remaining() > 0 {
+                continue;
+            }
+            drop(cached);
+            let block = self.allocate_block().await?;
+            cached = self.cached.lock().await;
+            cached.blocks.push_back(block);
+        }
+    }
+
+    fn refill(&self) {
+        let allocator = self.clone();
+        tokio::spawn(async move {
+            let _fetch = allocator.fetch.lock().await;
+            let needed = allocator.cached.lock().await.remaining() <= allocator.block_size / 2;
+            let block = if needed {
+                Some(allocator.allocate_block().await)
+            } else {
+                None
+            };
+
+            let mut cached = allocator.cached.lock().await;
+            cached.refilling = false;
+            match block {
+                Some(Ok(block)) => cached.blocks.push_back(block),
+                Some(Err(e)) => {
+                    error!(
+                        "Failed to refill the IDs of sequence {}: {}",
+                        allocator.name, e
+                    );
+                }
+                None => {}
+            }
+        });
+    }
+
+    async fn allocate_block(&self) -> Result<Range<u64>, RobustMQError> {
+        let request = AllocateIdRequest {
+            name: self.name.clone(),
+            count: self.block_size,
+        };
+        let reply =
+            placement_allocate_id(self.client_poll.clone(), self.addrs.clone(), request).await?;
+        if reply.count == 0 {
+            return Err(RobustMQError::CommmonError(format!(
+                "Sequence {} returned an empty block",
+                self.name
+            )));
+        }
+        return Ok(reply.start..reply.start + reply.count);
+    }
+}
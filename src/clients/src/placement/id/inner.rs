@@ -0,0 +1,36 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::IdServiceManager;
+use common_base::errors::RobustMQError;
+use mobc::Connection;
+use prost::Message;
+use protocol::id::{AllocateIdReply, AllocateIdRequest};
+
+pub(crate) async fn inner_allocate(
+    mut client: Connection<IdServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match AllocateIdRequest::decode(request.as_ref()) {
+        Ok(request) => match client.allocate(request).await {
+            Ok(result) => {
+                return Ok(AllocateIdReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
@@ -20,7 +20,8 @@ use crate::{
 use common_base::errors::RobustMQError;
 use prost::Message as _;
 use protocol::kv::{
-    CommonReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetRequest,
+    CommonReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, ImportRequest,
+    IncrReply, IncrRequest, ReadIndexReply, ReadIndexRequest, ScanReply, ScanRequest, SetRequest,
 };
 use std::sync::Arc;
 
@@ -123,3 +124,103 @@ pub async fn placement_exists(
         }
     }
 }
+
+pub async fn placement_scan(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ScanRequest,
+) -> Result<ScanReply, RobustMQError> {
+    let request_data = ScanRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Kv,
+        PlacementCenterInterface::Scan,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ScanReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_read_index(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ReadIndexRequest,
+) -> Result<ReadIndexReply, RobustMQError> {
+    let request_data = ReadIndexRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Kv,
+        PlacementCenterInterface::ReadIndex,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ReadIndexReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_incr(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: IncrRequest,
+) -> Result<IncrReply, RobustMQError> {
+    let request_data = IncrRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Kv,
+        PlacementCenterInterface::Incr,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match IncrReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_import(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ImportRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = ImportRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Kv,
+        PlacementCenterInterface::Import,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
@@ -15,7 +15,10 @@
 use common_base::errors::RobustMQError;
 use mobc::Connection;
 use prost::Message;
-use protocol::kv::{CommonReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, SetRequest};
+use protocol::kv::{
+    CommonReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply, GetRequest, ImportRequest,
+    IncrReply, IncrRequest, ReadIndexReply, ReadIndexRequest, ScanReply, ScanRequest, SetRequest,
+};
 use super::KvServiceManager;
 
 pub(crate) async fn inner_get(
@@ -85,3 +88,71 @@ pub(crate) async fn inner_exists(
         }
     }
 }
+
+pub(crate) async fn inner_scan(
+    mut client: Connection<KvServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ScanRequest::decode(request.as_ref()) {
+        Ok(request) => match client.scan(request).await {
+            Ok(result) => {
+                return Ok(ScanReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_read_index(
+    mut client: Connection<KvServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ReadIndexRequest::decode(request.as_ref()) {
+        Ok(request) => match client.read_index(request).await {
+            Ok(result) => {
+                return Ok(ReadIndexReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_incr(
+    mut client: Connection<KvServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match IncrRequest::decode(request.as_ref()) {
+        Ok(request) => match client.incr(request).await {
+            Ok(result) => {
+                return Ok(IncrReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_import(
+    mut client: Connection<KvServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ImportRequest::decode(request.as_ref()) {
+        Ok(request) => match client.import(request).await {
+            Ok(result) => {
+                return Ok(CommonReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
@@ -13,7 +13,10 @@
 // limitations under the License.
 
 use crate::poll::ClientPool;
-use self::inner::{inner_delete, inner_exists, inner_get, inner_set};
+use self::inner::{
+    inner_delete, inner_exists, inner_get, inner_import, inner_incr, inner_read_index, inner_scan,
+    inner_set,
+};
 use super::PlacementCenterInterface;
 use common_base::errors::RobustMQError;
 use mobc::{Connection, Manager};
@@ -51,6 +54,12 @@ pub(crate) async fn kv_interface_call(
                 PlacementCenterInterface::Delete => inner_delete(client, request.clone()).await,
                 PlacementCenterInterface::Get => inner_get(client, request.clone()).await,
                 PlacementCenterInterface::Exists => inner_exists(client, request.clone()).await,
+                PlacementCenterInterface::Scan => inner_scan(client, request.clone()).await,
+                PlacementCenterInterface::ReadIndex => {
+                    inner_read_index(client, request.clone()).await
+                }
+                PlacementCenterInterface::Incr => inner_incr(client, request.clone()).await,
+                PlacementCenterInterface::Import => inner_import(client, request.clone()).await,
                 _ => return Err(RobustMQError::CommmonError(format!(
                     "kv service does not support service interfaces [{:?}]",
                     interface
@@ -0,0 +1,74 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::{
+    placement::{retry_call, PlacementCenterService},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use prost::Message as _;
+use protocol::common::CommonReply;
+use protocol::lock::{LockReply, LockRequest, UnlockRequest};
+use std::sync::Arc;
+
+pub async fn placement_lock(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: LockRequest,
+) -> Result<LockReply, RobustMQError> {
+    let request_data = LockRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Lock,
+        PlacementCenterInterface::Lock,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match LockReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_unlock(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: UnlockRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = UnlockRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Lock,
+        PlacementCenterInterface::Unlock,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
@@ -14,16 +14,30 @@
 
 use crate::{poll::ClientPool, retry_sleep_time, retry_times};
 use common_base::errors::RobustMQError;
+use cluster::cluster_interface_call;
+use election::election_interface_call;
+use id::id_interface_call;
 use kv::kv_interface_call;
+use lock::lock_interface_call;
 use log::error;
+use mqtt::mqtt_interface_call;
+use offset::offset_interface_call;
 use openraft::openraft_interface_call;
 use std::{sync::Arc, time::Duration};
 use tokio::time::sleep;
+use topic::topic_interface_call;
 
 #[derive(Clone, Debug)]
 pub enum PlacementCenterService {
     Kv,
     OpenRaft,
+    Cluster,
+    Topic,
+    Election,
+    Lock,
+    Id,
+    Mqtt,
+    Offset,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -33,15 +47,63 @@ pub enum PlacementCenterInterface {
     Get,
     Delete,
     Exists,
+    Scan,
+    ReadIndex,
+    Incr,
+    Import,
 
     // Open Raft
     Vote,
     Append,
     Snapshot,
+
+    // Cluster
+    RegisterNode,
+    UnRegisterNode,
+    ListNodes,
+    Heartbeat,
+    ClusterStatus,
+    MemberStatus,
+
+    // Topic
+    CreateTopic,
+    DescribeTopic,
+    ReassignPartition,
+
+    // Election
+    Campaign,
+    Resign,
+
+    // Lock
+    Lock,
+    Unlock,
+
+    // Id
+    AllocateId,
+
+    // Mqtt
+    SaveSession,
+    DeleteSession,
+    GetSession,
+    ListSessions,
+    Subscribe,
+    Unsubscribe,
+    SaveLastWill,
+
+    // Offset
+    CommitOffset,
+    FetchOffset,
 }
 
+pub mod cluster;
+pub mod election;
+pub mod id;
 pub mod kv;
+pub mod lock;
+pub mod mqtt;
+pub mod offset;
 pub mod openraft;
+pub mod topic;
 
 async fn retry_call(
     service: PlacementCenterService,
@@ -74,6 +136,76 @@ async fn retry_call(
                 )
                 .await
             }
+
+            PlacementCenterService::Cluster => {
+                cluster_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Topic => {
+                topic_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Election => {
+                election_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Lock => {
+                lock_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Id => {
+                id_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Mqtt => {
+                mqtt_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
+
+            PlacementCenterService::Offset => {
+                offset_interface_call(
+                    interface.clone(),
+                    client_poll.clone(),
+                    addr.clone(),
+                    request.clone(),
+                )
+                .await
+            }
         };
 
         match result {
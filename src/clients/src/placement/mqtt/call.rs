@@ -0,0 +1,203 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::{
+    placement::{retry_call, PlacementCenterService},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use prost::Message as _;
+use protocol::common::CommonReply;
+use protocol::mqtt::{
+    DeleteSessionRequest, GetSessionReply, GetSessionRequest, ListSessionsReply,
+    ListSessionsRequest, SaveLastWillRequest, SaveSessionRequest, SubscribeRequest,
+    UnsubscribeRequest,
+};
+use std::sync::Arc;
+
+pub async fn placement_save_session(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: SaveSessionRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = SaveSessionRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::SaveSession,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_delete_session(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: DeleteSessionRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = DeleteSessionRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::DeleteSession,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_get_session(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: GetSessionRequest,
+) -> Result<GetSessionReply, RobustMQError> {
+    let request_data = GetSessionRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::GetSession,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match GetSessionReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_list_sessions(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ListSessionsRequest,
+) -> Result<ListSessionsReply, RobustMQError> {
+    let request_data = ListSessionsRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::ListSessions,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ListSessionsReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_subscribe(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: SubscribeRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = SubscribeRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::Subscribe,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_unsubscribe(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: UnsubscribeRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = UnsubscribeRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::Unsubscribe,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_save_last_will(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: SaveLastWillRequest,
+) -> Result<CommonReply, RobustMQError> {
+    let request_data = SaveLastWillRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Mqtt,
+        PlacementCenterInterface::SaveLastWill,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CommonReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
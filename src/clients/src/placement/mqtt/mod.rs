@@ -0,0 +1,132 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::poll::ClientPool;
+use common_base::errors::RobustMQError;
+use inner::{
+    inner_delete_session, inner_get_session, inner_list_sessions, inner_save_last_will,
+    inner_save_session, inner_subscribe, inner_unsubscribe,
+};
+use mobc::{Connection, Manager};
+use protocol::mqtt::mqtt_service_client::MqttServiceClient;
+use std::sync::Arc;
+use tonic::transport::Channel;
+
+pub mod call;
+mod inner;
+
+pub(crate) async fn mqtt_interface_call(
+    interface: PlacementCenterInterface,
+    client_poll: Arc<ClientPool>,
+    addr: String,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match mqtt_client(client_poll.clone(), addr.clone()).await {
+        Ok(client) => {
+            let result = match interface {
+                PlacementCenterInterface::SaveSession => {
+                    inner_save_session(client, request.clone()).await
+                }
+                PlacementCenterInterface::DeleteSession => {
+                    inner_delete_session(client, request.clone()).await
+                }
+                PlacementCenterInterface::GetSession => {
+                    inner_get_session(client, request.clone()).await
+                }
+                PlacementCenterInterface::ListSessions => {
+                    inner_list_sessions(client, request.clone()).await
+                }
+                PlacementCenterInterface::Subscribe => {
+                    inner_subscribe(client, request.clone()).await
+                }
+                PlacementCenterInterface::Unsubscribe => {
+                    inner_unsubscribe(client, request.clone()).await
+                }
+                PlacementCenterInterface::SaveLastWill => {
+                    inner_save_last_will(client, request.clone()).await
+                }
+                _ => {
+                    return Err(RobustMQError::CommmonError(format!(
+                        "mqtt service does not support service interfaces [{:?}]",
+                        interface
+                    )))
+                }
+            };
+            match result {
+                Ok(data) => return Ok(data),
+                Err(e) => {
+                    return Err(e);
+                }
+            }
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+async fn mqtt_client(
+    client_poll: Arc<ClientPool>,
+    addr: String,
+) -> Result<Connection<MqttServiceManager>, RobustMQError> {
+    match client_poll
+        .placement_center_mqtt_services_client(addr)
+        .await
+    {
+        Ok(client) => {
+            return Ok(client);
+        }
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MqttServiceManager {
+    pub addr: String,
+}
+
+impl MqttServiceManager {
+    pub fn new(addr: String) -> Self {
+        Self { addr }
+    }
+}
+
+#[tonic::async_trait]
+impl Manager for MqttServiceManager {
+    type Connection = MqttServiceClient<Channel>;
+    type Error = RobustMQError;
+
+    async fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        let addr = format!("http://{}", self.addr.clone());
+        match MqttServiceClient::connect(addr.clone()).await {
+            Ok(client) => {
+                return Ok(client);
+            }
+            Err(err) => {
+                return Err(RobustMQError::CommmonError(format!(
+                    "{},{}",
+                    err.to_string(),
+                    addr
+                )))
+            }
+        };
+    }
+
+    async fn check(&self, conn: Self::Connection) -> Result<Self::Connection, Self::Error> {
+        Ok(conn)
+    }
+}
@@ -0,0 +1,101 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::PlacementCenterInterface;
+use crate::{
+    placement::{retry_call, PlacementCenterService},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use prost::Message as _;
+use protocol::topic::{
+    CreateTopicReply, CreateTopicRequest, DescribeTopicReply, DescribeTopicRequest,
+    ReassignPartitionReply, ReassignPartitionRequest,
+};
+use std::sync::Arc;
+
+pub async fn placement_create_topic(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: CreateTopicRequest,
+) -> Result<CreateTopicReply, RobustMQError> {
+    let request_data = CreateTopicRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Topic,
+        PlacementCenterInterface::CreateTopic,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match CreateTopicReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_describe_topic(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: DescribeTopicRequest,
+) -> Result<DescribeTopicReply, RobustMQError> {
+    let request_data = DescribeTopicRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Topic,
+        PlacementCenterInterface::DescribeTopic,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match DescribeTopicReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
+
+pub async fn placement_reassign_partition(
+    client_poll: Arc<ClientPool>,
+    addrs: Vec<String>,
+    request: ReassignPartitionRequest,
+) -> Result<ReassignPartitionReply, RobustMQError> {
+    let request_data = ReassignPartitionRequest::encode_to_vec(&request);
+    match retry_call(
+        PlacementCenterService::Topic,
+        PlacementCenterInterface::ReassignPartition,
+        client_poll,
+        addrs,
+        request_data,
+    )
+    .await
+    {
+        Ok(data) => match ReassignPartitionReply::decode(data.as_ref()) {
+            Ok(da) => return Ok(da),
+            Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+        },
+        Err(e) => {
+            return Err(e);
+        }
+    }
+}
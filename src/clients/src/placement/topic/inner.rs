@@ -0,0 +1,73 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use super::TopicServiceManager;
+use common_base::errors::RobustMQError;
+use mobc::Connection;
+use prost::Message;
+use protocol::topic::{
+    CreateTopicReply, CreateTopicRequest, DescribeTopicReply, DescribeTopicRequest,
+    ReassignPartitionReply, ReassignPartitionRequest,
+};
+
+pub(crate) async fn inner_create_topic(
+    mut client: Connection<TopicServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match CreateTopicRequest::decode(request.as_ref()) {
+        Ok(request) => match client.create_topic(request).await {
+            Ok(result) => {
+                return Ok(CreateTopicReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_describe_topic(
+    mut client: Connection<TopicServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match DescribeTopicRequest::decode(request.as_ref()) {
+        Ok(request) => match client.describe_topic(request).await {
+            Ok(result) => {
+                return Ok(DescribeTopicReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
+
+pub(crate) async fn inner_reassign_partition(
+    mut client: Connection<TopicServiceManager>,
+    request: Vec<u8>,
+) -> Result<Vec<u8>, RobustMQError> {
+    match ReassignPartitionRequest::decode(request.as_ref()) {
+        Ok(request) => match client.reassign_partition(request).await {
+            Ok(result) => {
+                return Ok(ReassignPartitionReply::encode_to_vec(&result.into_inner()));
+            }
+            Err(e) => return Err(RobustMQError::GrpcServerStatus(e)),
+        },
+        Err(e) => {
+            return Err(RobustMQError::CommmonError(e.to_string()));
+        }
+    }
+}
@@ -12,7 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::placement::{kv::KvServiceManager, openraft::OpenRaftServiceManager};
+use crate::placement::{
+    cluster::ClusterServiceManager, election::ElectionServiceManager, id::IdServiceManager,
+    kv::KvServiceManager, lock::LockServiceManager, mqtt::MqttServiceManager,
+    offset::OffsetServiceManager, openraft::OpenRaftServiceManager, topic::TopicServiceManager,
+};
 use common_base::errors::RobustMQError;
 use dashmap::DashMap;
 use mobc::{Connection, Pool};
@@ -23,6 +27,13 @@ pub struct ClientPool {
     // placement center
     placement_center_kv_service_pools: DashMap<String, Pool<KvServiceManager>>,
     placement_center_openraft_service_pools: DashMap<String, Pool<OpenRaftServiceManager>>,
+    placement_center_cluster_service_pools: DashMap<String, Pool<ClusterServiceManager>>,
+    placement_center_topic_service_pools: DashMap<String, Pool<TopicServiceManager>>,
+    placement_center_election_service_pools: DashMap<String, Pool<ElectionServiceManager>>,
+    placement_center_lock_service_pools: DashMap<String, Pool<LockServiceManager>>,
+    placement_center_id_service_pools: DashMap<String, Pool<IdServiceManager>>,
+    placement_center_mqtt_service_pools: DashMap<String, Pool<MqttServiceManager>>,
+    placement_center_offset_service_pools: DashMap<String, Pool<OffsetServiceManager>>,
 }
 
 impl ClientPool {
@@ -31,6 +42,13 @@ impl ClientPool {
             max_open_connection,
             placement_center_kv_service_pools: DashMap::with_capacity(2),
             placement_center_openraft_service_pools: DashMap::with_capacity(2),
+            placement_center_cluster_service_pools: DashMap::with_capacity(2),
+            placement_center_topic_service_pools: DashMap::with_capacity(2),
+            placement_center_election_service_pools: DashMap::with_capacity(2),
+            placement_center_lock_service_pools: DashMap::with_capacity(2),
+            placement_center_id_service_pools: DashMap::with_capacity(2),
+            placement_center_mqtt_service_pools: DashMap::with_capacity(2),
+            placement_center_offset_service_pools: DashMap::with_capacity(2),
         }
     }
 
@@ -107,4 +125,258 @@ impl ClientPool {
             "connection pool is not initialized".to_string(),
         ));
     }
+
+    pub async fn placement_center_cluster_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<ClusterServiceManager>, RobustMQError> {
+        let module = "ClusterServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self
+            .placement_center_cluster_service_pools
+            .contains_key(&key)
+        {
+            let manager = ClusterServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_cluster_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_cluster_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_topic_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<TopicServiceManager>, RobustMQError> {
+        let module = "TopicServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self.placement_center_topic_service_pools.contains_key(&key) {
+            let manager = TopicServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_topic_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_topic_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_election_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<ElectionServiceManager>, RobustMQError> {
+        let module = "ElectionServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self
+            .placement_center_election_service_pools
+            .contains_key(&key)
+        {
+            let manager = ElectionServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_election_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_election_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_lock_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<LockServiceManager>, RobustMQError> {
+        let module = "LockServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self.placement_center_lock_service_pools.contains_key(&key) {
+            let manager = LockServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_lock_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_lock_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_id_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<IdServiceManager>, RobustMQError> {
+        let module = "IdServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self.placement_center_id_service_pools.contains_key(&key) {
+            let manager = IdServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_id_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_id_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_mqtt_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<MqttServiceManager>, RobustMQError> {
+        let module = "MqttServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self.placement_center_mqtt_service_pools.contains_key(&key) {
+            let manager = MqttServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_mqtt_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_mqtt_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
+
+    pub async fn placement_center_offset_services_client(
+        &self,
+        addr: String,
+    ) -> Result<Connection<OffsetServiceManager>, RobustMQError> {
+        let module = "OffsetServices".to_string();
+        let key = format!("{}_{}_{}", "PlacementCenter", module, addr);
+        if !self
+            .placement_center_offset_service_pools
+            .contains_key(&key)
+        {
+            let manager = OffsetServiceManager::new(addr.clone());
+            let pool = Pool::builder()
+                .max_open(self.max_open_connection)
+                .build(manager);
+            self.placement_center_offset_service_pools
+                .insert(key.clone(), pool);
+        }
+
+        if let Some(poll) = self.placement_center_offset_service_pools.get(&key) {
+            match poll.get().await {
+                Ok(conn) => {
+                    return Ok(conn);
+                }
+                Err(e) => {
+                    return Err(RobustMQError::NoAvailableGrpcConnection(
+                        module,
+                        e.to_string(),
+                    ));
+                }
+            };
+        }
+
+        return Err(RobustMQError::NoAvailableGrpcConnection(
+            module,
+            "connection pool is not initialized".to_string(),
+        ));
+    }
 }
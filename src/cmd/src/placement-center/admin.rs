@@ -0,0 +1,212 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::command;
+use clap::Parser;
+use clap::Subcommand;
+use clients::placement::kv::call::placement_import;
+use clients::poll::ClientPool;
+use common_base::config::placement_center::init_placement_center_conf_by_path;
+use common_base::config::placement_center::placement_center_conf;
+use placement_center::openraft::raft_node::Node;
+use placement_center::openraft::store::recover_log_store;
+use placement_center::storage::backup::restore_backup;
+use placement_center::storage::backup::ENGINE_STORAGE_DIR;
+use placement_center::storage::export::{export_kv, read_export, read_stopped_state};
+use placement_center::storage::keys::is_reserved_key;
+use protocol::kv::{ImportRequest, KeyValue};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::sleep;
+
+pub const DEFAULT_PLACEMENT_CENTER_CONFIG: &str = "config/placement-center.toml";
+
+#[derive(Parser, Debug)]
+#[command(author="robustmq-geek", version="0.0.1", about=" RobustMQ: Placement Center administration tool.", long_about = None)]
+#[command(next_line_help = true)]
+struct ArgsParams {
+    /// Path of the configuration file of the node being operated on
+    #[arg(short, long, default_value_t=String::from(DEFAULT_PLACEMENT_CENTER_CONFIG))]
+    conf: String,
+
+    #[command(subcommand)]
+    action: AdminAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum AdminAction {
+    /// Restore the data directory of a stopped node from a backup directory
+    Restore {
+        #[arg(long)]
+        backup_dir: String,
+
+        /// Rewrite the restored data into a new single-node cluster made of this node
+        #[arg(long, default_value_t = false)]
+        seed_single_node: bool,
+    },
+
+    /// Export the KV data of a stopped node to a line-delimited JSON file
+    Export {
+        #[arg(long)]
+        path: String,
+    },
+
+    /// Replay an export file into a running cluster, one Raft entry per batch
+    Import {
+        #[arg(long)]
+        path: String,
+
+        /// Grpc address of the node to write to, defaults to the node in the configuration file
+        #[arg(long)]
+        server: Option<String>,
+
+        #[arg(long, default_value_t = 100)]
+        batch_size: usize,
+
+        #[arg(long, default_value_t = 100)]
+        interval_ms: u64,
+    },
+
+    /// Truncate the Raft log of a stopped node back to its last valid entry, so the
+    /// rest is re-replicated from the leader on the next start
+    RecoverLogs,
+}
+
+#[tokio::main]
+async fn main() {
+    let args = ArgsParams::parse();
+    init_placement_center_conf_by_path(&args.conf);
+    let conf = placement_center_conf();
+
+    match args.action {
+        AdminAction::Restore {
+            backup_dir,
+            seed_single_node,
+        } => {
+            let seed = if seed_single_node {
+                Some(local_node())
+            } else {
+                None
+            };
+            match restore_backup(&backup_dir, conf, seed).await {
+                Ok(meta) => {
+                    println!(
+                        "Restore succeeded, last applied log id: {:?}, membership: {:?}",
+                        meta.last_applied_log_id, meta.last_membership
+                    );
+                }
+                Err(e) => {
+                    panic!("Restore failed, error message: {}", e.to_string());
+                }
+            }
+        }
+        AdminAction::Export { path } => {
+            let (kvs, revision) = match read_stopped_state(conf).await {
+                Ok(data) => data,
+                Err(e) => {
+                    panic!(
+                        "Failed to read the state machine, error message: {}",
+                        e.to_string()
+                    );
+                }
+            };
+            match export_kv(&kvs, revision, &conf.cluster_name, &path) {
+                Ok(header) => {
+                    println!(
+                        "Exported {} keys at revision {} to {}",
+                        header.record_num, header.revision, path
+                    );
+                }
+                Err(e) => {
+                    panic!("Export failed, error message: {}", e.to_string());
+                }
+            }
+        }
+        AdminAction::Import {
+            path,
+            server,
+            batch_size,
+            interval_ms,
+        } => {
+            let (header, records) = match read_export(&path) {
+                Ok(data) => data,
+                Err(e) => {
+                    panic!(
+                        "Failed to read export file, error message: {}",
+                        e.to_string()
+                    );
+                }
+            };
+            // The KV service refuses the keys of the internal services.
+            let (records, reserved): (Vec<_>, Vec<_>) = records
+                .into_iter()
+                .partition(|record| !is_reserved_key(&record.key));
+            let server = server.unwrap_or(format!("{}:{}", conf.addr, conf.grpc_port));
+            let client_poll = Arc::new(ClientPool::new(3));
+            for batch in records.chunks(batch_size.max(1)) {
+                let request = ImportRequest {
+                    kvs: batch
+                        .iter()
+                        .map(|record| KeyValue {
+                            key: record.key.clone(),
+                            value: record.value.clone(),
+                        })
+                        .collect(),
+                };
+                if let Err(e) =
+                    placement_import(client_poll.clone(), vec![server.clone()], request).await
+                {
+                    panic!(
+                        "Failed to import the batch starting at key {}, error message: {}",
+                        batch[0].key, e
+                    );
+                }
+                sleep(Duration::from_millis(interval_ms)).await;
+            }
+            println!(
+                "Imported {} keys of revision {} into {}, skipped {} internal keys",
+                records.len(),
+                header.revision,
+                server,
+                reserved.len()
+            );
+        }
+        AdminAction::RecoverLogs => {
+            let path = format!("{}/{}", conf.data_path, ENGINE_STORAGE_DIR);
+            match recover_log_store(&path, &conf.rocksdb).await {
+                Ok(Some(index)) => {
+                    println!("Corrupted log entry found, log was truncated at index {}", index);
+                }
+                Ok(None) => {
+                    println!("All log entries are valid, nothing was truncated");
+                }
+                Err(e) => {
+                    panic!("Log recovery failed, error message: {}", e.to_string());
+                }
+            }
+        }
+    }
+}
+
+fn local_node() -> Node {
+    let conf = placement_center_conf();
+    let rpc_addr = match conf.local_node() {
+        Some(node) => node.addr,
+        None => format!("{}:{}", conf.addr, conf.grpc_port),
+    };
+    return Node {
+        node_id: conf.node_id,
+        rpc_addr,
+    };
+}
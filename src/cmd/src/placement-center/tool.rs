@@ -0,0 +1,142 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use clap::command;
+use clap::Parser;
+use clap::Subcommand;
+use placement_center::storage::inspect::DataDirInspector;
+
+#[derive(Parser, Debug)]
+#[command(author="robustmq-geek", version="0.0.1", about=" RobustMQ: Placement Center offline data inspection tool.", long_about = None)]
+#[command(next_line_help = true)]
+struct ArgsParams {
+    /// Data directory of the node, the data_path of its configuration file
+    #[arg(short, long)]
+    data_path: String,
+
+    #[command(subcommand)]
+    action: ToolAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum ToolAction {
+    /// Show vote, committed and last purged log id, and the range of stored logs
+    Raft,
+
+    /// List the log entries with an index in [start, end]
+    Logs {
+        #[arg(long, default_value_t = 0)]
+        start: u64,
+
+        #[arg(long, default_value_t = u64::MAX)]
+        end: u64,
+    },
+
+    /// Show the meta of the stored snapshot
+    Snapshot,
+
+    /// List KV keys starting with a prefix
+    Keys {
+        #[arg(long, default_value_t = String::new())]
+        prefix: String,
+
+        /// Read the keys from the stored snapshot only, without the committed entries after it
+        #[arg(long, default_value_t = false)]
+        snapshot: bool,
+    },
+}
+
+fn main() {
+    let args = ArgsParams::parse();
+    let inspector = match DataDirInspector::open(&args.data_path) {
+        Ok(data) => data,
+        Err(e) => {
+            panic!(
+                "Failed to open data directory {}, error message: {}",
+                args.data_path,
+                e.to_string()
+            );
+        }
+    };
+
+    match args.action {
+        ToolAction::Raft => match inspector.raft_store_state() {
+            Ok(state) => {
+                println!("cluster_id: {:?}", state.cluster_id);
+                println!("vote: {:?}", state.vote);
+                println!("committed: {:?}", state.committed);
+                println!("last_purged: {:?}", state.last_purged);
+                println!(
+                    "logs: {:?} ..= {:?}",
+                    state.first_log_index, state.last_log_index
+                );
+            }
+            Err(e) => {
+                panic!(
+                    "Failed to read raft store, error message: {}",
+                    e.to_string()
+                );
+            }
+        },
+        ToolAction::Logs { start, end } => match inspector.log_entries(start, end) {
+            Ok(entries) => {
+                for raw in entries {
+                    match raw.entry {
+                        Ok(entry) => {
+                            println!("{} {} {:?}", raw.index, entry.log_id, entry.payload);
+                        }
+                        Err(e) => {
+                            println!("{} <undecodable entry: {}>", raw.index, e);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                panic!("Failed to read raft logs, error message: {}", e.to_string());
+            }
+        },
+        ToolAction::Snapshot => match inspector.snapshot() {
+            Ok(Some(snapshot)) => {
+                println!("snapshot_id: {}", snapshot.meta.snapshot_id);
+                println!("last_log_id: {:?}", snapshot.meta.last_log_id);
+                println!("last_membership: {:?}", snapshot.meta.last_membership);
+                println!("data_size: {}", snapshot.data_size);
+                println!("key_num: {}", snapshot.key_num);
+            }
+            Ok(None) => {
+                println!("No snapshot is stored");
+            }
+            Err(e) => {
+                panic!("Failed to read snapshot, error message: {}", e.to_string());
+            }
+        },
+        ToolAction::Keys { prefix, snapshot } => {
+            let keys = if snapshot {
+                inspector.snapshot_keys(&prefix)
+            } else {
+                inspector.state_keys(&prefix)
+            };
+            match keys {
+                Ok(keys) => {
+                    for key in keys {
+                        println!("{}", key);
+                    }
+                }
+                Err(e) => {
+                    panic!("Failed to list keys, error message: {}", e.to_string());
+                }
+            }
+        }
+    }
+}
@@ -29,22 +29,114 @@
  */
 
 use crate::tools::read_file;
+use rocksdb::{DBCompactionStyle, DBCompressionType};
 use serde::Deserialize;
-use toml::Table;
+use toml::{Table, Value};
+use std::collections::HashMap;
 use std::sync::OnceLock;
 
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct PlacementCenterConfig {
     pub cluster_name: String,
+    /// Pin the node to a cluster id. A data directory that belongs to another cluster
+    /// makes the node refuse to start. Unset, the id is taken from the data directory.
+    #[serde(default)]
+    pub cluster_id: Option<String>,
     pub addr: String,
     #[serde(default = "default_node_id")]
     pub node_id: u64,
     #[serde(default = "default_grpc_port")]
     pub grpc_port: usize,
+    /// Nodes of the cluster keyed by node id. A value is either the Raft address of a
+    /// voter, or a table `{ addr = "...", learner = true }` for a non-voting learner that
+    /// replicates the data and serves reads but takes no part in quorum.
     pub nodes: Table,
     pub http_port: usize,
     pub data_path: String,
     pub log: Log,
+    #[serde(default)]
+    pub rocksdb: RocksDB,
+    #[serde(default)]
+    pub group_commit: GroupCommit,
+    #[serde(default)]
+    pub raft: RaftConfig,
+    #[serde(default)]
+    pub heartbeat: Heartbeat,
+    #[serde(default)]
+    pub offset_retention: OffsetRetention,
+    #[serde(default)]
+    pub topic: TopicLimits,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfiguredNode {
+    pub node_id: u64,
+    pub addr: String,
+    pub learner: bool,
+}
+
+impl PlacementCenterConfig {
+    /// Parse the `nodes` table, ordered by node id.
+    pub fn configured_nodes(&self) -> Result<Vec<ConfiguredNode>, String> {
+        let mut nodes = Vec::new();
+        for (node_id, value) in self.nodes.iter() {
+            let node_id: u64 = match node_id.trim().parse() {
+                Ok(id) => id,
+                Err(_) => return Err(format!("nodes: invalid node id {}", node_id)),
+            };
+            let node = match value {
+                Value::String(addr) => ConfiguredNode {
+                    node_id,
+                    addr: addr.clone(),
+                    learner: false,
+                },
+                Value::Table(table) => {
+                    let addr = match table.get("addr").and_then(|addr| addr.as_str()) {
+                        Some(addr) => addr.to_string(),
+                        None => return Err(format!("nodes: node {} has no addr", node_id)),
+                    };
+                    let learner = match table.get("learner") {
+                        None => false,
+                        Some(learner) => match learner.as_bool() {
+                            Some(learner) => learner,
+                            None => {
+                                return Err(format!(
+                                    "nodes: learner of node {} must be a boolean",
+                                    node_id
+                                ))
+                            }
+                        },
+                    };
+                    ConfiguredNode {
+                        node_id,
+                        addr,
+                        learner,
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "nodes: node {} must be an address or a table",
+                        node_id
+                    ))
+                }
+            };
+            nodes.push(node);
+        }
+        if !nodes.iter().any(|node| !node.learner) {
+            return Err("nodes: at least one node must be a voter".to_string());
+        }
+        nodes.sort_by_key(|node| node.node_id);
+        return Ok(nodes);
+    }
+
+    /// The entry of this node in `nodes`, if configured.
+    pub fn local_node(&self) -> Option<ConfiguredNode> {
+        return self
+            .configured_nodes()
+            .ok()?
+            .into_iter()
+            .find(|node| node.node_id == self.node_id);
+    }
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -53,6 +145,352 @@ pub struct Log {
     pub log_path: String,
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RocksDBProfile {
+    /// Small memtables and block cache for laptops and small VMs.
+    Dev,
+    #[default]
+    Production,
+}
+
+/// Column families whose data must never be dropped by compaction.
+const DURABLE_COLUMN_FAMILIES: [&str; 3] = ["_raft_logs", "_raft_store", "cluster"];
+
+/// Options of the `[rocksdb]` section. Unset values fall back to the selected profile.
+/// `block_cache_size` is the size of one cache shared by every database of the process.
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RocksDB {
+    #[serde(default)]
+    pub profile: RocksDBProfile,
+    pub block_cache_size: Option<usize>,
+    /// One of none, snappy, zlib, bz2, lz4, lz4hc, zstd.
+    pub compression_type: Option<String>,
+    pub use_fsync: Option<bool>,
+    pub bytes_per_sync: Option<u64>,
+    pub max_open_files: Option<i32>,
+    /// Per column family overrides, keyed by column family name.
+    #[serde(default)]
+    pub column_family: HashMap<String, RocksDBColumnFamily>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct RocksDBColumnFamily {
+    pub write_buffer_size: Option<usize>,
+    pub max_write_buffer_number: Option<i32>,
+    pub min_write_buffer_number_to_merge: Option<i32>,
+    pub target_file_size_base: Option<u64>,
+    pub level_zero_slowdown_writes_trigger: Option<i32>,
+    pub level_zero_stop_writes_trigger: Option<i32>,
+    /// One of level, universal, fifo.
+    pub compaction_style: Option<String>,
+    pub disable_auto_compactions: Option<bool>,
+}
+
+impl RocksDBProfile {
+    fn db_defaults(&self) -> RocksDB {
+        let (block_cache_size, bytes_per_sync) = match self {
+            RocksDBProfile::Dev => (32 * 1024 * 1024, 1024 * 1024),
+            RocksDBProfile::Production => (512 * 1024 * 1024, 8 * 1024 * 1024),
+        };
+        return RocksDB {
+            profile: *self,
+            block_cache_size: Some(block_cache_size),
+            compression_type: Some("lz4".to_string()),
+            use_fsync: Some(false),
+            bytes_per_sync: Some(bytes_per_sync),
+            max_open_files: Some(1000),
+            column_family: HashMap::new(),
+        };
+    }
+
+    fn column_family_defaults(&self) -> RocksDBColumnFamily {
+        let (write_buffer_size, max_write_buffer_number, min_merge, target_file_size_base) =
+            match self {
+                RocksDBProfile::Dev => (16 * 1024 * 1024, 2, 1, 32 * 1024 * 1024),
+                RocksDBProfile::Production => (128 * 1024 * 1024, 4, 2, 256 * 1024 * 1024),
+            };
+        return RocksDBColumnFamily {
+            write_buffer_size: Some(write_buffer_size),
+            max_write_buffer_number: Some(max_write_buffer_number),
+            min_write_buffer_number_to_merge: Some(min_merge),
+            target_file_size_base: Some(target_file_size_base),
+            level_zero_slowdown_writes_trigger: Some(20),
+            level_zero_stop_writes_trigger: Some(36),
+            compaction_style: Some("level".to_string()),
+            disable_auto_compactions: Some(false),
+        };
+    }
+}
+
+impl RocksDB {
+    /// Database wide options with every value filled in from the profile.
+    pub fn resolve(&self) -> RocksDB {
+        let defaults = self.profile.db_defaults();
+        return RocksDB {
+            profile: self.profile,
+            block_cache_size: self.block_cache_size.or(defaults.block_cache_size),
+            compression_type: self.compression_type.clone().or(defaults.compression_type),
+            use_fsync: self.use_fsync.or(defaults.use_fsync),
+            bytes_per_sync: self.bytes_per_sync.or(defaults.bytes_per_sync),
+            max_open_files: self.max_open_files.or(defaults.max_open_files),
+            column_family: self.column_family.clone(),
+        };
+    }
+
+    /// Options of one column family with every value filled in from the profile.
+    pub fn column_family(&self, name: &str) -> RocksDBColumnFamily {
+        let defaults = self.profile.column_family_defaults();
+        let conf = match self.column_family.get(name) {
+            Some(conf) => conf.clone(),
+            None => return defaults,
+        };
+        return RocksDBColumnFamily {
+            write_buffer_size: conf.write_buffer_size.or(defaults.write_buffer_size),
+            max_write_buffer_number: conf
+                .max_write_buffer_number
+                .or(defaults.max_write_buffer_number),
+            min_write_buffer_number_to_merge: conf
+                .min_write_buffer_number_to_merge
+                .or(defaults.min_write_buffer_number_to_merge),
+            target_file_size_base: conf
+                .target_file_size_base
+                .or(defaults.target_file_size_base),
+            level_zero_slowdown_writes_trigger: conf
+                .level_zero_slowdown_writes_trigger
+                .or(defaults.level_zero_slowdown_writes_trigger),
+            level_zero_stop_writes_trigger: conf
+                .level_zero_stop_writes_trigger
+                .or(defaults.level_zero_stop_writes_trigger),
+            compaction_style: conf.compaction_style.or(defaults.compaction_style),
+            disable_auto_compactions: conf
+                .disable_auto_compactions
+                .or(defaults.disable_auto_compactions),
+        };
+    }
+
+    /// Compression of every column family, parsed from `compression_type`.
+    pub fn compression(&self) -> Result<DBCompressionType, String> {
+        let name = self.resolve().compression_type.unwrap();
+        match name.to_lowercase().as_str() {
+            "none" => return Ok(DBCompressionType::None),
+            "snappy" => return Ok(DBCompressionType::Snappy),
+            "zlib" => return Ok(DBCompressionType::Zlib),
+            "bz2" => return Ok(DBCompressionType::Bz2),
+            "lz4" => return Ok(DBCompressionType::Lz4),
+            "lz4hc" => return Ok(DBCompressionType::Lz4hc),
+            "zstd" => return Ok(DBCompressionType::Zstd),
+            _ => {
+                return Err(format!(
+                    "rocksdb.compression_type ({}) must be one of none, snappy, zlib, bz2, lz4, lz4hc, zstd",
+                    name
+                ))
+            }
+        }
+    }
+
+    /// Compaction style of the column family `name`, parsed from its `compaction_style`.
+    pub fn compaction_style(&self, name: &str) -> Result<DBCompactionStyle, String> {
+        let style = self.column_family(name).compaction_style.unwrap();
+        match style.to_lowercase().as_str() {
+            "level" => return Ok(DBCompactionStyle::Level),
+            "universal" => return Ok(DBCompactionStyle::Universal),
+            "fifo" => return Ok(DBCompactionStyle::Fifo),
+            _ => {
+                return Err(format!(
+                    "rocksdb.column_family.{}.compaction_style ({}) must be one of level, universal, fifo",
+                    name, style
+                ))
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.compression()?;
+        // Column families without an override use the profile default, which is valid.
+        for name in self.column_family.keys() {
+            let style = self.compaction_style(name)?;
+            // FIFO compaction drops the oldest files once the size limit is reached.
+            if style == DBCompactionStyle::Fifo && DURABLE_COLUMN_FAMILIES.contains(&name.as_str())
+            {
+                return Err(format!(
+                    "rocksdb.column_family.{}.compaction_style must not be fifo, the column family holds Raft state",
+                    name
+                ));
+            }
+        }
+        return Ok(());
+    }
+}
+
+/// Options of the `[group_commit]` section. Writes arriving within `max_wait_ms` of the
+/// first pending write are committed together as one Raft entry, up to `max_batch_size` writes.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct GroupCommit {
+    #[serde(default = "default_group_commit_max_batch_size")]
+    pub max_batch_size: usize,
+    #[serde(default = "default_group_commit_max_wait_ms")]
+    pub max_wait_ms: u64,
+}
+
+impl Default for GroupCommit {
+    fn default() -> Self {
+        return GroupCommit {
+            max_batch_size: default_group_commit_max_batch_size(),
+            max_wait_ms: default_group_commit_max_wait_ms(),
+        };
+    }
+}
+
+pub fn default_group_commit_max_batch_size() -> usize {
+    256
+}
+
+pub fn default_group_commit_max_wait_ms() -> u64 {
+    2
+}
+
+/// Liveness of the registered broker nodes. The leader declares a node dead and removes
+/// it when no heartbeat arrived for `timeout_ms`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct Heartbeat {
+    #[serde(default = "default_heartbeat_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_heartbeat_check_interval_ms")]
+    pub check_interval_ms: u64,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        return Heartbeat {
+            timeout_ms: default_heartbeat_timeout_ms(),
+            check_interval_ms: default_heartbeat_check_interval_ms(),
+        };
+    }
+}
+
+pub fn default_heartbeat_timeout_ms() -> u64 {
+    30000
+}
+
+pub fn default_heartbeat_check_interval_ms() -> u64 {
+    1000
+}
+
+/// Committed consumer offsets of a group are removed once the group committed nothing
+/// for `retention_sec`.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct OffsetRetention {
+    #[serde(default = "default_offset_retention_sec")]
+    pub retention_sec: u64,
+    #[serde(default = "default_offset_retention_check_interval_ms")]
+    pub check_interval_ms: u64,
+}
+
+impl Default for OffsetRetention {
+    fn default() -> Self {
+        return OffsetRetention {
+            retention_sec: default_offset_retention_sec(),
+            check_interval_ms: default_offset_retention_check_interval_ms(),
+        };
+    }
+}
+
+pub fn default_offset_retention_sec() -> u64 {
+    7 * 24 * 3600
+}
+
+pub fn default_offset_retention_check_interval_ms() -> u64 {
+    60000
+}
+
+/// Upper bounds of a topic created through the topic service. The leader builds the
+/// assignment of every partition in memory, an unbounded request could exhaust it.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TopicLimits {
+    #[serde(default = "default_topic_max_partition_num")]
+    pub max_partition_num: u32,
+    #[serde(default = "default_topic_max_replication_factor")]
+    pub max_replication_factor: u32,
+}
+
+impl Default for TopicLimits {
+    fn default() -> Self {
+        return TopicLimits {
+            max_partition_num: default_topic_max_partition_num(),
+            max_replication_factor: default_topic_max_replication_factor(),
+        };
+    }
+}
+
+pub fn default_topic_max_partition_num() -> u32 {
+    1024
+}
+
+pub fn default_topic_max_replication_factor() -> u32 {
+    5
+}
+
+/// Options of the `[raft]` section, applied to both consensus engines. The openraft engine
+/// has no switches for pre-vote, check-quorum or lease reads, so `pre_vote`, `check_quorum`
+/// and `leader_lease` are refused instead of being silently ignored.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RaftConfig {
+    #[serde(default = "default_raft_heartbeat_interval_ms")]
+    pub heartbeat_interval_ms: u64,
+    /// A follower that does not hear from the leader for a random time between min and
+    /// max starts an election.
+    #[serde(default = "default_raft_election_timeout_min_ms")]
+    pub election_timeout_min_ms: u64,
+    #[serde(default = "default_raft_election_timeout_max_ms")]
+    pub election_timeout_max_ms: u64,
+}
+
+impl Default for RaftConfig {
+    fn default() -> Self {
+        return RaftConfig {
+            heartbeat_interval_ms: default_raft_heartbeat_interval_ms(),
+            election_timeout_min_ms: default_raft_election_timeout_min_ms(),
+            election_timeout_max_ms: default_raft_election_timeout_max_ms(),
+        };
+    }
+}
+
+impl RaftConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.heartbeat_interval_ms == 0 {
+            return Err("raft.heartbeat_interval_ms must be greater than 0".to_string());
+        }
+        // A follower must be able to miss a heartbeat without starting an election.
+        if self.election_timeout_min_ms < 2 * self.heartbeat_interval_ms {
+            return Err(format!(
+                "raft.election_timeout_min_ms ({}) must be at least twice raft.heartbeat_interval_ms ({})",
+                self.election_timeout_min_ms, self.heartbeat_interval_ms
+            ));
+        }
+        if self.election_timeout_max_ms <= self.election_timeout_min_ms {
+            return Err(format!(
+                "raft.election_timeout_max_ms ({}) must be greater than raft.election_timeout_min_ms ({})",
+                self.election_timeout_max_ms, self.election_timeout_min_ms
+            ));
+        }
+        return Ok(());
+    }
+}
+
+pub fn default_raft_heartbeat_interval_ms() -> u64 {
+    250
+}
+
+pub fn default_raft_election_timeout_min_ms() -> u64 {
+    1000
+}
+
+pub fn default_raft_election_timeout_max_ms() -> u64 {
+    2000
+}
+
 pub fn default_node_id() -> u64 {
     1
 }
@@ -72,6 +510,15 @@ pub fn init_placement_center_conf_by_path(config_path: &String) -> &'static Plac
             }
         };
         let pc_config: PlacementCenterConfig = toml::from_str(&content).unwrap();
+        if let Err(e) = pc_config.raft.validate() {
+            panic!("Invalid placement center configuration: {}", e);
+        }
+        if let Err(e) = pc_config.rocksdb.validate() {
+            panic!("Invalid placement center configuration: {}", e);
+        }
+        if let Err(e) = pc_config.configured_nodes() {
+            panic!("Invalid placement center configuration: {}", e);
+        }
         return pc_config;
     })
 }
@@ -91,8 +538,11 @@ pub fn placement_center_conf() -> &'static PlacementCenterConfig {
 #[cfg(test)]
 mod tests {
     use crate::config::placement_center::{
-        init_placement_center_conf_by_path, placement_center_conf,
+        init_placement_center_conf_by_path, placement_center_conf, ConfiguredNode, GroupCommit,
+        Heartbeat, OffsetRetention, PlacementCenterConfig, RaftConfig, RocksDB, RocksDBProfile,
+        TopicLimits,
     };
+    use rocksdb::{DBCompactionStyle, DBCompressionType};
 
     #[test]
     fn config_init_test() {
@@ -106,4 +556,163 @@ mod tests {
         assert_eq!(config.node_id, 1);
         assert_eq!(config.grpc_port, 1228);
     }
+
+    #[test]
+    fn rocksdb_profile_test() {
+        let content = r#"
+            cluster_name = "placement-test"
+            addr = "127.0.0.1"
+            nodes = { 1 = "127.0.0.1:1228" }
+            http_port = 8971
+            data_path = "/tmp/placement-center"
+
+            [log]
+            log_config = "./config/log4rs.yaml"
+            log_path = "./logs"
+
+            [rocksdb]
+            profile = "dev"
+            compression_type = "zstd"
+
+            [rocksdb.column_family._raft_logs]
+            write_buffer_size = 67108864
+        "#;
+        let config: PlacementCenterConfig = toml::from_str(content).unwrap();
+        assert_eq!(config.rocksdb.profile, RocksDBProfile::Dev);
+
+        let db = config.rocksdb.resolve();
+        assert_eq!(db.compression_type, Some("zstd".to_string()));
+        assert_eq!(db.block_cache_size, Some(32 * 1024 * 1024));
+
+        let logs = config.rocksdb.column_family("_raft_logs");
+        assert_eq!(logs.write_buffer_size, Some(64 * 1024 * 1024));
+        assert_eq!(logs.max_write_buffer_number, Some(2));
+
+        let cluster = config.rocksdb.column_family("cluster");
+        assert_eq!(cluster.write_buffer_size, Some(16 * 1024 * 1024));
+
+        assert_eq!(config.group_commit, GroupCommit::default());
+        assert_eq!(config.heartbeat, Heartbeat::default());
+        assert_eq!(config.offset_retention, OffsetRetention::default());
+        assert_eq!(config.topic, TopicLimits::default());
+    }
+
+    #[test]
+    fn rocksdb_validate_test() {
+        let config = RocksDB::default();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.compression(), Ok(DBCompressionType::Lz4));
+
+        let config: RocksDB = toml::from_str(
+            r#"
+            compression_type = "ZSTD"
+
+            [column_family._raft_logs]
+            compaction_style = "universal"
+        "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.compression(), Ok(DBCompressionType::Zstd));
+        assert_eq!(
+            config.compaction_style("_raft_logs"),
+            Ok(DBCompactionStyle::Universal)
+        );
+
+        let config: RocksDB = toml::from_str(r#"compression_type = "gzip""#).unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("rocksdb.compression_type"));
+
+        let config: RocksDB = toml::from_str(
+            r#"
+            [column_family.cluster]
+            compaction_style = "tiered"
+        "#,
+        )
+        .unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("rocksdb.column_family.cluster.compaction_style"));
+
+        for name in ["_raft_logs", "_raft_store", "cluster"] {
+            let content = format!("[column_family.{}]\ncompaction_style = \"fifo\"", name);
+            let config: RocksDB = toml::from_str(&content).unwrap();
+            assert!(config.validate().unwrap_err().contains(name));
+        }
+        let config: RocksDB = toml::from_str(
+            r#"
+            [column_family.other]
+            compaction_style = "fifo"
+        "#,
+        )
+        .unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn raft_config_test() {
+        let config = RaftConfig::default();
+        assert!(config.validate().is_ok());
+
+        let config: RaftConfig = toml::from_str("heartbeat_interval_ms = 100").unwrap();
+        assert_eq!(config.election_timeout_min_ms, 1000);
+        assert!(config.validate().is_ok());
+
+        let mut config = RaftConfig::default();
+        config.election_timeout_min_ms = config.heartbeat_interval_ms;
+        assert!(config.validate().is_err());
+
+        let mut config = RaftConfig::default();
+        config.election_timeout_max_ms = config.election_timeout_min_ms;
+        assert!(config.validate().is_err());
+
+        for key in ["pre_vote", "check_quorum", "leader_lease"] {
+            let content = format!("{} = true", key);
+            let err = toml::from_str::<RaftConfig>(&content).unwrap_err();
+            assert!(err.to_string().contains(key));
+        }
+    }
+
+    #[test]
+    fn configured_nodes_test() {
+        let mut config = PlacementCenterConfig::default();
+        config.nodes = toml::from_str(
+            r#"
+            2 = { addr = "127.0.0.1:1229", learner = true }
+            1 = "127.0.0.1:1228"
+            3 = { addr = "127.0.0.1:1230" }
+        "#,
+        )
+        .unwrap();
+        let nodes = config.configured_nodes().unwrap();
+        assert_eq!(
+            nodes,
+            vec![
+                ConfiguredNode {
+                    node_id: 1,
+                    addr: "127.0.0.1:1228".to_string(),
+                    learner: false,
+                },
+                ConfiguredNode {
+                    node_id: 2,
+                    addr: "127.0.0.1:1229".to_string(),
+                    learner: true,
+                },
+                ConfiguredNode {
+                    node_id: 3,
+                    addr: "127.0.0.1:1230".to_string(),
+                    learner: false,
+                },
+            ]
+        );
+        config.node_id = 2;
+        assert!(config.local_node().unwrap().learner);
+
+        config.nodes =
+            toml::from_str(r#"1 = { addr = "127.0.0.1:1228", learner = true }"#).unwrap();
+        assert!(config.configured_nodes().is_err());
+        config.nodes = toml::from_str(r#"1 = { learner = false }"#).unwrap();
+        assert!(config.configured_nodes().is_err());
+        config.nodes = toml::from_str(r#"a = "127.0.0.1:1228""#).unwrap();
+        assert!(config.configured_nodes().is_err());
+    }
 }
@@ -18,6 +18,7 @@ use std::{
 };
 
 use crate::errors::RobustMQError;
+use uuid::Uuid;
 
 pub fn create_fold(fold: &String) -> Result<(), RobustMQError> {
     if !Path::new(fold).exists() {
@@ -41,9 +42,27 @@ pub fn read_file(path: &String) -> Result<String, RobustMQError> {
     return Ok(fs::read_to_string(&path)?);
 }
 
+pub fn copy_dir(src: &Path, dst: &Path) -> Result<(), RobustMQError> {
+    fs::create_dir_all(dst)?;
+    for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        let target = dst.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), target)?;
+        }
+    }
+    return Ok(());
+}
+
 pub fn now_second() -> u64 {
     return SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-}
\ No newline at end of file
+}
+
+pub fn unique_id() -> String {
+    return Uuid::new_v4().to_string();
+}
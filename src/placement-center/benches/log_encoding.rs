@@ -0,0 +1,92 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Append and read throughput of the `_raft_logs` encodings.
+//!
+//! cargo bench -p placement-center --bench log_encoding
+
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use openraft::{CommittedLeaderId, Entry, EntryPayload, LogId};
+use placement_center::openraft::{
+    route::AppRequestData,
+    store::codec::{
+        decode_entry, encode_value_with_format, FORMAT_BINCODE, FORMAT_JSON, FORMAT_LEGACY_JSON,
+    },
+    typeconfig::TypeConfig,
+};
+use rocksdb::{IteratorMode, Options, DB};
+
+const ENTRY_NUM: u64 = 100_000;
+const VALUE_SIZE: usize = 128;
+
+fn entry(index: u64) -> Entry<TypeConfig> {
+    return Entry {
+        log_id: LogId::new(CommittedLeaderId::new(1, 1), index),
+        payload: EntryPayload::Normal(AppRequestData::Set {
+            key: format!("/bench/key/{}", index),
+            value: "v".repeat(VALUE_SIZE),
+        }),
+    };
+}
+
+fn bench_format(name: &str, format: u8, root: &String) {
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    let db = DB::open(&opts, format!("{}/{}", root, name)).unwrap();
+
+    let entries: Vec<Entry<TypeConfig>> = (1..=ENTRY_NUM).map(entry).collect();
+
+    let start = Instant::now();
+    let mut bytes = 0;
+    for ent in entries.iter() {
+        let buf = encode_value_with_format(ent, format).unwrap();
+        bytes += buf.len();
+        db.put(ent.log_id.index.to_be_bytes(), buf).unwrap();
+    }
+    let append_secs = start.elapsed().as_secs_f64();
+
+    let start = Instant::now();
+    let mut read_num = 0;
+    for res in db.iterator(IteratorMode::Start) {
+        let (key, value) = res.unwrap();
+        let index = u64::from_be_bytes(key.as_ref().try_into().unwrap());
+        decode_entry(index, &value).unwrap();
+        read_num += 1;
+    }
+    let read_secs = start.elapsed().as_secs_f64();
+    assert_eq!(read_num, ENTRY_NUM);
+
+    println!(
+        "{:<12} append {:>10.0} entries/s   read {:>10.0} entries/s   {:>5} bytes/entry",
+        name,
+        ENTRY_NUM as f64 / append_secs,
+        ENTRY_NUM as f64 / read_secs,
+        bytes as u64 / ENTRY_NUM
+    );
+}
+
+fn main() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let root = format!("/tmp/bench_log_encoding/{}", now);
+
+    bench_format("legacy-json", FORMAT_LEGACY_JSON, &root);
+    bench_format("json", FORMAT_JSON, &root);
+    bench_format("bincode", FORMAT_BINCODE, &root);
+
+    let _ = std::fs::remove_dir_all(&root);
+}
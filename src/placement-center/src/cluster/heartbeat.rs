@@ -0,0 +1,172 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use common_base::config::placement_center::Heartbeat;
+use log::{error, info};
+use openraft::Raft;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::{
+    openraft::{
+        group_commit::GroupCommitter,
+        route::{cluster::list_all_nodes, kvs::StateKvs, AppRequestData},
+        typeconfig::TypeConfig,
+    },
+    raft::node::{BrokerNode, NodeState},
+    storage::keys::key_node,
+};
+
+/// Last heartbeat of every broker node, kept by the leader in memory only. A new leader
+/// starts without history and gives every node a full timeout from its first check.
+pub struct HeartbeatTracker {
+    last_heartbeat: Mutex<HashMap<String, Instant>>,
+}
+
+impl HeartbeatTracker {
+    pub fn new() -> Self {
+        return HeartbeatTracker {
+            last_heartbeat: Mutex::new(HashMap::new()),
+        };
+    }
+
+    pub fn report(&self, node: &BrokerNode, now: Instant) {
+        self.last_heartbeat
+            .lock()
+            .unwrap()
+            .insert(node_key(node), now);
+    }
+
+    pub fn clear(&self) {
+        self.last_heartbeat.lock().unwrap().clear();
+    }
+
+    /// Registered nodes whose last heartbeat is older than `timeout`. Nodes that are no
+    /// longer registered are forgotten.
+    pub fn expired(
+        &self,
+        nodes: &[BrokerNode],
+        now: Instant,
+        timeout: Duration,
+    ) -> Vec<BrokerNode> {
+        let mut last_heartbeat = self.last_heartbeat.lock().unwrap();
+        let registered: HashMap<String, &BrokerNode> =
+            nodes.iter().map(|node| (node_key(node), node)).collect();
+        last_heartbeat.retain(|key, _| registered.contains_key(key));
+
+        let mut expired = Vec::new();
+        for (key, node) in registered {
+            let last = *last_heartbeat.entry(key).or_insert(now);
+            if now.duration_since(last) > timeout {
+                expired.push(node.clone());
+            }
+        }
+        return expired;
+    }
+}
+
+fn node_key(node: &BrokerNode) -> String {
+    return key_node(&node.cluster_type, &node.cluster_name, node.node_id);
+}
+
+/// Declare broker nodes dead once their heartbeats time out. Runs on every node, only
+/// the leader acts.
+pub async fn start_heartbeat_check(
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    tracker: Arc<HeartbeatTracker>,
+    config: Heartbeat,
+) {
+    let timeout = Duration::from_millis(config.timeout_ms);
+    loop {
+        sleep(Duration::from_millis(config.check_interval_ms)).await;
+
+        let metrics = raft_node.metrics().borrow().clone();
+        if metrics.current_leader != Some(metrics.id) {
+            tracker.clear();
+            continue;
+        }
+
+        let nodes = list_all_nodes(&*kvs.read().await);
+        for node in tracker.expired(&nodes, Instant::now(), timeout) {
+            let data = AppRequestData::UpdateNodeState {
+                cluster_type: node.cluster_type.clone(),
+                cluster_name: node.cluster_name.clone(),
+                node_id: node.node_id,
+                state: NodeState::Stop,
+            };
+            match group_committer.write(data).await {
+                Ok(_) => {
+                    info!(
+                        "Broker node {} of cluster {} missed its heartbeats and was removed",
+                        node.node_id, node.cluster_name
+                    );
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to remove broker node {} of cluster {}: {}",
+                        node.node_id, node.cluster_name, e
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::HeartbeatTracker;
+    use crate::raft::node::BrokerNode;
+
+    fn node(node_id: u64) -> BrokerNode {
+        return BrokerNode {
+            cluster_type: "MQTTBrokerServer".to_string(),
+            cluster_name: "mqtt".to_string(),
+            node_id,
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn expired_nodes() {
+        let tracker = HeartbeatTracker::new();
+        let timeout = Duration::from_secs(10);
+        let start = Instant::now();
+        let nodes = vec![node(1), node(2)];
+
+        // Nodes seen for the first time get a full timeout.
+        assert!(tracker.expired(&nodes, start, timeout).is_empty());
+
+        tracker.report(&nodes[0], start + Duration::from_secs(8));
+        let expired = tracker.expired(&nodes, start + Duration::from_secs(11), timeout);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].node_id, 2);
+
+        // An unregistered node is forgotten, registering again restarts its timeout.
+        assert!(tracker
+            .expired(&nodes[..1], start + Duration::from_secs(12), timeout)
+            .is_empty());
+        assert!(tracker
+            .expired(&nodes, start + Duration::from_secs(15), timeout)
+            .is_empty());
+    }
+}
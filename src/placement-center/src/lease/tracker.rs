@@ -0,0 +1,206 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::{error, info};
+use openraft::Raft;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::{
+    openraft::{
+        group_commit::GroupCommitter,
+        route::{election::list_elections, kvs::StateKvs, lock::list_locks, AppRequestData},
+        typeconfig::TypeConfig,
+    },
+    storage::keys::{key_election, key_lock},
+};
+
+const LEASE_CHECK_INTERVAL_MS: u64 = 100;
+
+/// A lease held in the replicated state: the key of the record it belongs to, the token
+/// of the holder and how long the holder keeps it without renewing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lease {
+    pub key: String,
+    pub token: u64,
+    pub ttl_ms: u64,
+}
+
+/// Deadline of every lease, kept by the leader in memory only. A new leader starts
+/// without history and gives every lease a full TTL from its first check.
+pub struct LeaseTracker {
+    deadlines: Mutex<HashMap<String, (u64, Instant)>>,
+}
+
+impl LeaseTracker {
+    pub fn new() -> Self {
+        return LeaseTracker {
+            deadlines: Mutex::new(HashMap::new()),
+        };
+    }
+
+    pub fn renew(&self, key: &String, token: u64, deadline: Instant) {
+        self.deadlines
+            .lock()
+            .unwrap()
+            .insert(key.clone(), (token, deadline));
+    }
+
+    pub fn clear(&self) {
+        self.deadlines.lock().unwrap().clear();
+    }
+
+    /// Leases whose TTL ran out. Leases that ended or changed holder are forgotten.
+    pub fn expired(&self, leases: &[Lease], now: Instant) -> Vec<Lease> {
+        let mut deadlines = self.deadlines.lock().unwrap();
+        let current: HashMap<&String, &Lease> =
+            leases.iter().map(|lease| (&lease.key, lease)).collect();
+        deadlines.retain(|key, (token, _)| match current.get(key) {
+            Some(lease) => lease.token == *token,
+            None => false,
+        });
+
+        let mut expired = Vec::new();
+        for lease in leases {
+            let ttl = Duration::from_millis(lease.ttl_ms);
+            let (_, deadline) = *deadlines
+                .entry(lease.key.clone())
+                .or_insert((lease.token, now + ttl));
+            if now > deadline {
+                expired.push(lease.clone());
+            }
+        }
+        return expired;
+    }
+}
+
+/// Every lease currently held, with the request that expires it.
+fn held_leases(kvs: &BTreeMap<String, String>) -> Vec<(Lease, AppRequestData)> {
+    let mut leases = Vec::new();
+    for election in list_elections(kvs) {
+        if let Some(leader) = election.leader {
+            let lease = Lease {
+                key: key_election(&election.name),
+                token: leader.epoch,
+                ttl_ms: leader.lease_ms,
+            };
+            let expire = AppRequestData::ExpireLeader {
+                name: election.name,
+                epoch: leader.epoch,
+            };
+            leases.push((lease, expire));
+        }
+    }
+    for lock in list_locks(kvs) {
+        if let Some(holder) = lock.holder {
+            let lease = Lease {
+                key: key_lock(&lock.name),
+                token: holder.token,
+                ttl_ms: holder.ttl_ms,
+            };
+            let expire = AppRequestData::ExpireLock {
+                name: lock.name,
+                token: holder.token,
+            };
+            leases.push((lease, expire));
+        }
+    }
+    return leases;
+}
+
+/// Expire the election leaderships and locks whose lease ran out. Runs on every node,
+/// only the leader acts.
+pub async fn start_lease_check(
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    tracker: Arc<LeaseTracker>,
+) {
+    loop {
+        sleep(Duration::from_millis(LEASE_CHECK_INTERVAL_MS)).await;
+
+        let metrics = raft_node.metrics().borrow().clone();
+        if metrics.current_leader != Some(metrics.id) {
+            tracker.clear();
+            continue;
+        }
+
+        let (leases, mut expire): (Vec<Lease>, HashMap<String, AppRequestData>) = {
+            let held = held_leases(&*kvs.read().await);
+            let leases = held.iter().map(|(lease, _)| lease.clone()).collect();
+            let expire = held
+                .into_iter()
+                .map(|(lease, expire)| (lease.key, expire))
+                .collect();
+            (leases, expire)
+        };
+        for lease in tracker.expired(&leases, Instant::now()) {
+            let data = match expire.remove(&lease.key) {
+                Some(data) => data,
+                None => continue,
+            };
+            match group_committer.write(data).await {
+                Ok(_) => {
+                    info!("Lease of {} with token {} expired", lease.key, lease.token);
+                }
+                Err(e) => {
+                    error!("Failed to expire the lease of {}: {}", lease.key, e);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Lease, LeaseTracker};
+
+    fn lease(token: u64) -> Lease {
+        return Lease {
+            key: "/election/shard-1".to_string(),
+            token,
+            ttl_ms: 1000,
+        };
+    }
+
+    #[test]
+    fn expired_leases() {
+        let tracker = LeaseTracker::new();
+        let start = Instant::now();
+        let key = "/election/shard-1".to_string();
+
+        // Unseen leases get a full TTL.
+        assert!(tracker.expired(&[lease(1)], start).is_empty());
+        tracker.renew(&key, 1, start + Duration::from_millis(1500));
+        assert!(tracker
+            .expired(&[lease(1)], start + Duration::from_millis(1200))
+            .is_empty());
+        assert_eq!(
+            tracker.expired(&[lease(1)], start + Duration::from_millis(1600)),
+            vec![lease(1)]
+        );
+
+        // A new holder does not inherit the deadline of the previous one.
+        assert!(tracker
+            .expired(&[lease(2)], start + Duration::from_millis(1700))
+            .is_empty());
+    }
+}
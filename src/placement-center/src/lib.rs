@@ -15,9 +15,16 @@
 use std::sync::{Arc, RwLock};
 
 use clients::poll::ClientPool;
+use cluster::heartbeat::{start_heartbeat_check, HeartbeatTracker};
 use common_base::config::placement_center::placement_center_conf;
+use lease::tracker::{start_lease_check, LeaseTracker};
 use log::info;
-use openraft::raft_node::{create_raft_node, start_openraft_node};
+use offset::retention::start_offset_retention;
+use openraft::{
+    group_commit::GroupCommitter,
+    raft_node::{create_raft_node, start_openraft_node},
+    store::{CF_RAFT_LOGS, CF_RAFT_STORE},
+};
 use raft::{
     apply::{RaftMachineApply, RaftMessage},
     machine::RaftMachine,
@@ -29,13 +36,22 @@ use server::{
     grpc::server::start_grpc_server,
     http::server::{start_http_server, HttpServerState},
 };
-use storage::{raft::RaftMachineStorage, rocksdb::RocksDBEngine};
+use storage::{
+    backup::{ENGINE_STORAGE_DIR, STORAGE_ROCKSDB_DIR},
+    metrics::StorageMetricsRegistry,
+    raft::RaftMachineStorage,
+    rocksdb::{column_family_list, RocksDBEngine},
+};
 use tokio::{
     signal,
     sync::{broadcast, mpsc},
 };
 
+pub mod cluster;
+pub mod lease;
+pub mod offset;
 pub mod openraft;
+pub mod placement;
 pub mod raft;
 pub mod requests;
 pub mod server;
@@ -68,17 +84,88 @@ pub async fn start_server(stop_sx: broadcast::Sender<bool>) {
 
     let client_poll = Arc::new(ClientPool::new(3));
 
-    let (openraft_node, kvs) = create_raft_node(client_poll.clone()).await;
+    let (openraft_node, kvs, engine_db, cluster_identity, watchers) =
+        create_raft_node(client_poll.clone()).await;
+
+    let group_committer = GroupCommitter::new(openraft_node.clone(), &config.group_commit);
+
+    let heartbeat_tracker = Arc::new(HeartbeatTracker::new());
+    let tmp_openraft_node = openraft_node.clone();
+    let tmp_group_committer = group_committer.clone();
+    let tmp_kvs = kvs.clone();
+    let tmp_heartbeat_tracker = heartbeat_tracker.clone();
+    tokio::spawn(async move {
+        start_heartbeat_check(
+            tmp_openraft_node,
+            tmp_group_committer,
+            tmp_kvs,
+            tmp_heartbeat_tracker,
+            config.heartbeat.clone(),
+        )
+        .await;
+    });
+
+    let lease_tracker = Arc::new(LeaseTracker::new());
+    let tmp_openraft_node = openraft_node.clone();
+    let tmp_group_committer = group_committer.clone();
+    let tmp_kvs = kvs.clone();
+    let tmp_lease_tracker = lease_tracker.clone();
+    tokio::spawn(async move {
+        start_lease_check(
+            tmp_openraft_node,
+            tmp_group_committer,
+            tmp_kvs,
+            tmp_lease_tracker,
+        )
+        .await;
+    });
+
+    let tmp_openraft_node = openraft_node.clone();
+    let tmp_group_committer = group_committer.clone();
+    let tmp_kvs = kvs.clone();
+    tokio::spawn(async move {
+        start_offset_retention(
+            tmp_openraft_node,
+            tmp_group_committer,
+            tmp_kvs,
+            config.offset_retention.clone(),
+        )
+        .await;
+    });
+
+    let storage_metrics = Arc::new(StorageMetricsRegistry::new());
+    storage_metrics.register(
+        STORAGE_ROCKSDB_DIR,
+        rocksdb_engine_handler.db.clone(),
+        column_family_list(),
+    );
+    storage_metrics.register(
+        ENGINE_STORAGE_DIR,
+        engine_db.clone(),
+        vec![CF_RAFT_STORE.to_string(), CF_RAFT_LOGS.to_string()],
+    );
 
     let raw_stop_sx = stop_sx.clone();
     let tmp_openraft_node = openraft_node.clone();
+    let tmp_kvs = kvs.clone();
+    let tmp_cluster_identity = cluster_identity.clone();
+    let tmp_placement_cache = placement_cache.clone();
+    let tmp_storage_metrics = storage_metrics.clone();
+    let tmp_group_committer = group_committer.clone();
+    let tmp_client_poll = client_poll.clone();
     tokio::spawn(async move {
         start_grpc_server(
-            client_poll,
+            tmp_client_poll,
             tmp_openraft_node,
+            tmp_cluster_identity,
+            tmp_group_committer,
+            tmp_kvs,
+            heartbeat_tracker,
+            lease_tracker,
+            watchers,
+            tmp_placement_cache,
+            tmp_storage_metrics,
             placement_center_storage,
-            rocksdb_engine_handler,
-            placement_cache,
             raw_stop_sx,
         )
         .await;
@@ -86,12 +173,21 @@ pub async fn start_server(stop_sx: broadcast::Sender<bool>) {
 
     let tmp_openraft_node = openraft_node.clone();
     tokio::spawn(async move {
-        start_openraft_node(openraft_node).await;
+        start_openraft_node(openraft_node, cluster_identity).await;
     });
 
     let raw_stop_sx = stop_sx.clone();
     tokio::spawn(async move {
-        let state = HttpServerState::new(tmp_openraft_node, kvs);
+        let state = HttpServerState::new(
+            tmp_openraft_node,
+            client_poll,
+            group_committer,
+            kvs,
+            rocksdb_engine_handler,
+            engine_db,
+            placement_cache,
+            storage_metrics,
+        );
         start_http_server(state, raw_stop_sx).await;
     });
 
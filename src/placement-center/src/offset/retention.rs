@@ -0,0 +1,65 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{sync::Arc, time::Duration};
+
+use common_base::{config::placement_center::OffsetRetention, tools::now_second};
+use log::{error, info};
+use openraft::Raft;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::openraft::{
+    group_commit::GroupCommitter,
+    route::{kvs::StateKvs, offset::idle_groups, AppRequestData},
+    typeconfig::TypeConfig,
+};
+
+/// Remove the committed offsets of consumer groups that have been idle for longer than
+/// the retention. Runs on every node, only the leader acts.
+pub async fn start_offset_retention(
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    config: OffsetRetention,
+) {
+    loop {
+        sleep(Duration::from_millis(config.check_interval_ms)).await;
+
+        let metrics = raft_node.metrics().borrow().clone();
+        if metrics.current_leader != Some(metrics.id) {
+            continue;
+        }
+
+        let before = now_second().saturating_sub(config.retention_sec);
+        let groups = idle_groups(&*kvs.read().await, before);
+        if groups.is_empty() {
+            continue;
+        }
+        let data = AppRequestData::ExpireOffsets {
+            groups: groups.clone(),
+            before,
+        };
+        match group_committer.write(data).await {
+            Ok(_) => {
+                info!("Offsets of idle consumer groups {:?} expired", groups);
+            }
+            Err(e) => {
+                error!(
+                    "Failed to expire the offsets of idle consumer groups: {}",
+                    e
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use clients::{placement::kv::call::placement_read_index, poll::ClientPool};
+use common_base::errors::RobustMQError;
+use openraft::Raft;
+use protocol::kv::{ReadIndexRequest, StalenessBound};
+
+use super::{store::log_store::CommitWatcher, typeconfig::TypeConfig};
+
+/// How long a read waits for the local state machine to catch up before it is forwarded
+/// to the leader instead.
+const READ_BARRIER_TIMEOUT_MS: u64 = 1000;
+
+/// The read index of the leader and the time it was requested. Once the local state
+/// machine has applied that index, the local state is at most `at.elapsed()` old.
+#[derive(Debug, Clone, Copy)]
+pub struct SyncPoint {
+    pub read_index: Option<u64>,
+    pub at: Instant,
+}
+
+/// Decides whether a read with a staleness bound can be answered from the local state of
+/// a follower or learner.
+pub struct BoundedReader {
+    raft_node: Raft<TypeConfig>,
+    client_poll: Arc<ClientPool>,
+    commit: CommitWatcher,
+    /// How long a read index of the leader vouches for the local commit index.
+    election_timeout_ms: u64,
+    last_sync: Mutex<Option<SyncPoint>>,
+}
+
+impl BoundedReader {
+    pub fn new(
+        raft_node: Raft<TypeConfig>,
+        client_poll: Arc<ClientPool>,
+        commit: CommitWatcher,
+        election_timeout_ms: u64,
+    ) -> Self {
+        return BoundedReader {
+            raft_node,
+            client_poll,
+            commit,
+            election_timeout_ms,
+            last_sync: Mutex::new(None),
+        };
+    }
+
+    /// Returns true once the local state satisfies the bound, waiting for a read barrier
+    /// if it is close. Returns false when the read should be forwarded to the leader.
+    pub async fn ensure_fresh(&self, bound: &StalenessBound) -> Result<bool, RobustMQError> {
+        // The commit index learned through replication tells how many entries the local
+        // state misses, but it stops moving once the node is cut off from the leader. It
+        // only counts next to a read index of the leader taken within an election timeout,
+        // a node that cannot get one refuses the read.
+        if let Some(max_entries) = bound.max_entries {
+            let sync = self.sync_point(self.election_timeout_ms).await?;
+            let committed = self.commit.committed().max(sync.read_index);
+            if let Some(target) = entries_target(committed, max_entries) {
+                if !self.wait_applied(target).await {
+                    return Ok(false);
+                }
+            }
+        }
+
+        // The leader is only asked again once the last sync is older than the bound.
+        if let Some(max_ms) = bound.max_ms {
+            let sync = self.sync_point(max_ms).await?;
+            if let Some(read_index) = sync.read_index {
+                if !self.wait_applied(read_index).await {
+                    return Ok(false);
+                }
+            }
+        }
+        return Ok(true);
+    }
+
+    async fn sync_point(&self, max_ms: u64) -> Result<SyncPoint, RobustMQError> {
+        let last_sync = *self.last_sync.lock().unwrap();
+        if let Some(sync) = last_sync {
+            if is_recent(&sync, max_ms, Instant::now()) {
+                return Ok(sync);
+            }
+        }
+
+        let at = Instant::now();
+        let read_index = self.read_index().await?;
+        let sync = SyncPoint { read_index, at };
+        *self.last_sync.lock().unwrap() = Some(sync);
+        return Ok(sync);
+    }
+
+    /// Wait for the local state machine to apply `target`, false when it does not in time.
+    async fn wait_applied(&self, target: u64) -> bool {
+        if is_applied(self.applied_index(), target) {
+            return true;
+        }
+        let timeout = Duration::from_millis(READ_BARRIER_TIMEOUT_MS);
+        return self
+            .raft_node
+            .wait(Some(timeout))
+            .applied_index_at_least(Some(target), "bounded-staleness read")
+            .await
+            .is_ok();
+    }
+
+    /// The read index of the leader: every write committed before the call is at or
+    /// below it.
+    pub async fn read_index(&self) -> Result<Option<u64>, RobustMQError> {
+        let metrics = self.raft_node.metrics().borrow().clone();
+        if metrics.current_leader == Some(metrics.id) {
+            return local_read_index(&self.raft_node).await;
+        }
+
+        let leader_addr = match leader_addr(&self.raft_node) {
+            Some(addr) => addr,
+            None => {
+                return Err(RobustMQError::CommmonError(
+                    "No leader is known, the read index is not available".to_string(),
+                ));
+            }
+        };
+        let reply = placement_read_index(
+            self.client_poll.clone(),
+            vec![leader_addr],
+            ReadIndexRequest::default(),
+        )
+        .await?;
+        return Ok(reply.read_index);
+    }
+
+    fn applied_index(&self) -> Option<u64> {
+        return self
+            .raft_node
+            .metrics()
+            .borrow()
+            .last_applied
+            .map(|log_id| log_id.index);
+    }
+}
+
+/// Read index of this node as the leader, confirmed with a quorum.
+pub async fn local_read_index(raft_node: &Raft<TypeConfig>) -> Result<Option<u64>, RobustMQError> {
+    match raft_node.ensure_linearizable().await {
+        Ok(log_id) => return Ok(log_id.map(|log_id| log_id.index)),
+        Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+    }
+}
+
+/// Address of the leader when it is another node.
+pub fn leader_addr(raft_node: &Raft<TypeConfig>) -> Option<String> {
+    let metrics = raft_node.metrics().borrow().clone();
+    match metrics.current_leader {
+        Some(leader_id) if leader_id != metrics.id => {
+            return metrics
+                .membership_config
+                .membership()
+                .get_node(&leader_id)
+                .map(|node| node.rpc_addr.clone());
+        }
+        _ => return None,
+    }
+}
+
+/// The index the local state machine must have applied to be at most `max_entries`
+/// behind `committed`, None when any local state is.
+pub fn entries_target(committed: Option<u64>, max_entries: u64) -> Option<u64> {
+    return committed?.checked_sub(max_entries);
+}
+
+pub fn is_recent(sync: &SyncPoint, max_ms: u64, now: Instant) -> bool {
+    return now.duration_since(sync.at) <= Duration::from_millis(max_ms);
+}
+
+fn is_applied(applied: Option<u64>, target: u64) -> bool {
+    return applied.map(|applied| applied >= target).unwrap_or(false);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{entries_target, is_recent, SyncPoint};
+
+    #[test]
+    fn entries_target_test() {
+        assert_eq!(entries_target(Some(10), 3), Some(7));
+        assert_eq!(entries_target(Some(10), 0), Some(10));
+        // Fewer entries than the bound were ever committed.
+        assert_eq!(entries_target(Some(10), 20), None);
+        assert_eq!(entries_target(None, 3), None);
+    }
+
+    #[test]
+    fn is_recent_test() {
+        let at = Instant::now();
+        let sync = SyncPoint {
+            read_index: Some(10),
+            at,
+        };
+        let now = at + Duration::from_millis(50);
+        assert!(is_recent(&sync, 100, now));
+        assert!(is_recent(&sync, 50, now));
+        assert!(!is_recent(&sync, 20, now));
+    }
+}
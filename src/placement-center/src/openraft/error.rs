@@ -21,3 +21,11 @@ pub fn to_error<E: std::error::Error + 'static + Clone>(
 ) -> RPCError<TypeConfig, E> {
     RPCError::Unreachable(Unreachable::new(&e))
 }
+
+/// An error the peer may recover from right away, such as a timed out request. Unlike
+/// `to_error` it does not make Raft back off from the peer.
+pub fn to_network_error<E: std::error::Error + 'static + Clone>(
+    e: RobustMQError,
+) -> RPCError<TypeConfig, E> {
+    RPCError::Network(NetworkError::new(&e))
+}
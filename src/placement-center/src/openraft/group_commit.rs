@@ -0,0 +1,226 @@
+use std::{future::Future, time::Duration};
+
+use common_base::{config::placement_center::GroupCommit, errors::RobustMQError};
+use log::error;
+use openraft::Raft;
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{timeout_at, Instant},
+};
+
+use super::{
+    route::{AppRequestData, AppResponseData},
+    typeconfig::TypeConfig,
+};
+
+const GROUP_COMMIT_CHANNEL_SIZE: usize = 4096;
+
+type CommitResult = Result<AppResponseData, RobustMQError>;
+
+type PendingWrite = (AppRequestData, oneshot::Sender<CommitResult>);
+
+/// Group commit in front of `client_write`. Writes that arrive within `max_wait_ms` of the
+/// first pending one are proposed together as a single `AppRequestData::Batch` entry, so
+/// they share one log append and fsync. While a batch is being committed new writes keep
+/// queueing up and form the next batch.
+#[derive(Clone)]
+pub struct GroupCommitter {
+    submit_sx: mpsc::Sender<PendingWrite>,
+}
+
+impl GroupCommitter {
+    pub fn new(raft_node: Raft<TypeConfig>, config: &GroupCommit) -> Self {
+        return GroupCommitter::start(config, move |data| {
+            let raft_node = raft_node.clone();
+            async move {
+                match raft_node.client_write(data).await {
+                    Ok(resp) => Ok(resp.data),
+                    Err(e) => Err(RobustMQError::CommmonError(e.to_string())),
+                }
+            }
+        });
+    }
+
+    /// Start the batching task with `commit` proposing one request to Raft.
+    pub fn start<F, Fut>(config: &GroupCommit, commit: F) -> Self
+    where
+        F: Fn(AppRequestData) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = CommitResult> + Send + 'static,
+    {
+        let (submit_sx, mut submit_rx) = mpsc::channel::<PendingWrite>(GROUP_COMMIT_CHANNEL_SIZE);
+        let max_batch_size = config.max_batch_size.max(1);
+        let max_wait = Duration::from_millis(config.max_wait_ms);
+
+        tokio::spawn(async move {
+            while let Some(first) = submit_rx.recv().await {
+                let deadline = Instant::now() + max_wait;
+                let mut pending = vec![first];
+                while pending.len() < max_batch_size {
+                    match timeout_at(deadline, submit_rx.recv()).await {
+                        Ok(Some(write)) => pending.push(write),
+                        // The window elapsed or every sender is gone.
+                        _ => break,
+                    }
+                }
+                commit_pending(&commit, pending).await;
+            }
+        });
+
+        return GroupCommitter { submit_sx };
+    }
+
+    /// Commit one write and wait for its own result.
+    pub async fn write(&self, data: AppRequestData) -> CommitResult {
+        let (reply_sx, reply_rx) = oneshot::channel();
+        if self.submit_sx.send((data, reply_sx)).await.is_err() {
+            return Err(RobustMQError::CommmonError(
+                "Group commit task has stopped".to_string(),
+            ));
+        }
+        match reply_rx.await {
+            Ok(result) => return result,
+            Err(_) => {
+                return Err(RobustMQError::CommmonError(
+                    "Group commit task dropped the write".to_string(),
+                ));
+            }
+        }
+    }
+}
+
+async fn commit_pending<F, Fut>(commit: &F, mut pending: Vec<PendingWrite>)
+where
+    F: Fn(AppRequestData) -> Fut,
+    Fut: Future<Output = CommitResult>,
+{
+    // A lone write has nothing to share its entry with, propose it as is.
+    if pending.len() == 1 {
+        let (data, reply_sx) = pending.remove(0);
+        let _ = reply_sx.send(commit(data).await);
+        return;
+    }
+
+    let (requests, reply_list): (Vec<AppRequestData>, Vec<oneshot::Sender<CommitResult>>) =
+        pending.into_iter().unzip();
+    match commit(AppRequestData::Batch { requests }).await {
+        Ok(resp) => {
+            let mut results = resp.batch.into_iter();
+            for reply_sx in reply_list {
+                let _ = reply_sx.send(Ok(results.next().unwrap_or_default()));
+            }
+        }
+        Err(e) => {
+            error!("Group commit of {} writes failed: {}", reply_list.len(), e);
+            for reply_sx in reply_list {
+                let _ = reply_sx.send(Err(RobustMQError::CommmonError(e.to_string())));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::GroupCommitter;
+    use crate::openraft::route::{AppRequestData, AppResponseData};
+    use common_base::config::placement_center::GroupCommit;
+
+    fn committer(config: GroupCommit, proposals: Arc<Mutex<Vec<usize>>>) -> GroupCommitter {
+        return GroupCommitter::start(&config, move |data| {
+            let proposals = proposals.clone();
+            async move {
+                match data {
+                    AppRequestData::Batch { requests } => {
+                        proposals.lock().unwrap().push(requests.len());
+                        let batch = requests
+                            .into_iter()
+                            .map(|req| match req {
+                                AppRequestData::Set { value, .. } => AppResponseData {
+                                    value: Some(value),
+                                    ..Default::default()
+                                },
+                                _ => AppResponseData::default(),
+                            })
+                            .collect();
+                        Ok(AppResponseData { value: None, batch })
+                    }
+                    AppRequestData::Set { value, .. } => {
+                        proposals.lock().unwrap().push(1);
+                        Ok(AppResponseData {
+                            value: Some(value),
+                            ..Default::default()
+                        })
+                    }
+                    _ => {
+                        proposals.lock().unwrap().push(1);
+                        Ok(AppResponseData::default())
+                    }
+                }
+            }
+        });
+    }
+
+    fn set(i: usize) -> AppRequestData {
+        return AppRequestData::Set {
+            key: format!("k{}", i),
+            value: format!("v{}", i),
+        };
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes_share_one_entry() {
+        let proposals = Arc::new(Mutex::new(Vec::new()));
+        let config = GroupCommit {
+            max_batch_size: 16,
+            max_wait_ms: 200,
+        };
+        let committer = committer(config, proposals.clone());
+
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let committer = committer.clone();
+            handles.push(tokio::spawn(async move { committer.write(set(i)).await }));
+        }
+        for (i, handle) in handles.into_iter().enumerate() {
+            let resp = handle.await.unwrap().unwrap();
+            assert_eq!(resp.value, Some(format!("v{}", i)));
+        }
+        assert_eq!(*proposals.lock().unwrap(), vec![10]);
+    }
+
+    #[tokio::test]
+    async fn batch_size_limit() {
+        let proposals = Arc::new(Mutex::new(Vec::new()));
+        let config = GroupCommit {
+            max_batch_size: 4,
+            max_wait_ms: 200,
+        };
+        let committer = committer(config, proposals.clone());
+
+        let mut handles = Vec::new();
+        for i in 0..10 {
+            let committer = committer.clone();
+            handles.push(tokio::spawn(async move { committer.write(set(i)).await }));
+        }
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+        let proposals = proposals.lock().unwrap().clone();
+        assert!(proposals.iter().all(|size| *size <= 4));
+        assert_eq!(proposals.iter().sum::<usize>(), 10);
+    }
+
+    #[tokio::test]
+    async fn single_write_is_not_wrapped() {
+        let proposals = Arc::new(Mutex::new(Vec::new()));
+        let config = GroupCommit {
+            max_batch_size: 16,
+            max_wait_ms: 0,
+        };
+        let committer = committer(config, proposals.clone());
+        let resp = committer.write(set(1)).await.unwrap();
+        assert_eq!(resp.value, Some("v1".to_string()));
+        assert!(resp.batch.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::Duration,
+};
+
+use log::{error, info};
+use openraft::{LogId, Raft};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use super::{
+    raft_node::{Node, NodeId},
+    typeconfig::TypeConfig,
+};
+
+const LEARNER_JOIN_INTERVAL_SEC: u64 = 3;
+
+/// Add the learners configured in `nodes` to the membership. Runs on every voter, only
+/// the current leader acts, so the learners still join after a leader change. Returns
+/// once every learner is part of the membership.
+pub async fn join_learners(raft_node: Raft<TypeConfig>, learners: Vec<Node>) {
+    loop {
+        sleep(Duration::from_secs(LEARNER_JOIN_INTERVAL_SEC)).await;
+
+        let metrics = raft_node.metrics().borrow().clone();
+        let membership = metrics.membership_config.membership().clone();
+        let missing: Vec<&Node> = learners
+            .iter()
+            .filter(|learner| membership.get_node(&learner.node_id).is_none())
+            .collect();
+        if missing.is_empty() {
+            info!("All configured learners are part of the membership");
+            return;
+        }
+        if metrics.current_leader != Some(metrics.id) {
+            continue;
+        }
+
+        for learner in missing {
+            // Do not block on the learner catching up, a slow learner must not hold back
+            // the others.
+            match raft_node
+                .add_learner(learner.node_id, learner.clone(), false)
+                .await
+            {
+                Ok(_) => {
+                    info!("Learner node {} was added", learner.node_id);
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to add learner node {}, error message: {}",
+                        learner.node_id,
+                        e.to_string()
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplicationLag {
+    pub node_id: NodeId,
+    pub learner: bool,
+    /// Last log index known to be replicated to the node.
+    pub matched_index: Option<u64>,
+    /// Number of log entries the node is behind the leader.
+    pub lag: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationStatus {
+    pub node_id: NodeId,
+    pub learner: bool,
+    pub current_leader: Option<NodeId>,
+    pub last_log_index: Option<u64>,
+    pub last_applied_index: Option<u64>,
+    /// Lag of every other node, only known on the leader.
+    pub replication: Vec<ReplicationLag>,
+}
+
+pub fn replication_status(raft_node: &Raft<TypeConfig>) -> ReplicationStatus {
+    let metrics = raft_node.metrics().borrow().clone();
+    let learners: BTreeSet<NodeId> = metrics
+        .membership_config
+        .membership()
+        .learner_ids()
+        .collect();
+    let replication = match &metrics.replication {
+        Some(replication) => replication_lag(metrics.last_log_index, replication, &learners),
+        None => Vec::new(),
+    };
+    return ReplicationStatus {
+        node_id: metrics.id,
+        learner: learners.contains(&metrics.id),
+        current_leader: metrics.current_leader,
+        last_log_index: metrics.last_log_index,
+        last_applied_index: metrics.last_applied.map(|log_id| log_id.index),
+        replication,
+    };
+}
+
+pub fn replication_lag(
+    last_log_index: Option<u64>,
+    replication: &BTreeMap<NodeId, Option<LogId<NodeId>>>,
+    learners: &BTreeSet<NodeId>,
+) -> Vec<ReplicationLag> {
+    // Log indexes start at 0, a node that matched nothing misses every entry.
+    let entries = last_log_index.map(|index| index + 1).unwrap_or(0);
+    return replication
+        .iter()
+        .map(|(node_id, matched)| {
+            let matched_index = matched.map(|log_id| log_id.index);
+            let replicated = matched_index.map(|index| index + 1).unwrap_or(0);
+            ReplicationLag {
+                node_id: *node_id,
+                learner: learners.contains(node_id),
+                matched_index,
+                lag: entries.saturating_sub(replicated),
+            }
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use openraft::{CommittedLeaderId, LogId};
+
+    use super::{replication_lag, ReplicationLag};
+
+    #[test]
+    fn replication_lag_test() {
+        let log_id = |index| Some(LogId::new(CommittedLeaderId::new(1, 1), index));
+        let replication = BTreeMap::from([(1, log_id(10)), (2, log_id(7)), (4, None)]);
+        let learners = BTreeSet::from([4]);
+
+        let lag = replication_lag(Some(10), &replication, &learners);
+        assert_eq!(
+            lag,
+            vec![
+                ReplicationLag {
+                    node_id: 1,
+                    learner: false,
+                    matched_index: Some(10),
+                    lag: 0,
+                },
+                ReplicationLag {
+                    node_id: 2,
+                    learner: false,
+                    matched_index: Some(7),
+                    lag: 3,
+                },
+                ReplicationLag {
+                    node_id: 4,
+                    learner: true,
+                    matched_index: None,
+                    lag: 11,
+                },
+            ]
+        );
+    }
+}
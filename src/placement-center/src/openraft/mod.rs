@@ -3,4 +3,9 @@ pub mod raft_node;
 pub mod route;
 pub mod store;
 pub mod typeconfig;
-pub mod error;
\ No newline at end of file
+pub mod error;
+pub mod group_commit;
+pub mod learner;
+
+pub mod bounded_read;
+pub mod status;
@@ -1,4 +1,3 @@
-use bincode::{deserialize, serialize};
 use clients::{placement::openraft::OpenRaftServiceManager, poll::ClientPool};
 use common_base::errors::RobustMQError;
 use mobc::Connection;
@@ -11,21 +10,45 @@ use openraft::{
     },
     RaftNetwork,
 };
-use protocol::openraft::{AppendRequest, SnapshotRequest};
+use protocol::openraft as pb;
 use std::sync::Arc;
+use tokio::time::timeout;
 
-use crate::openraft::{error::to_error, raft_node::NodeId, typeconfig::TypeConfig};
+use super::{
+    convert::{
+        append_request_to_proto, append_response_from_proto, decode_legacy, encode_legacy,
+        snapshot_request_to_proto, snapshot_response_from_proto, vote_request_to_proto,
+        vote_response_from_proto,
+    },
+    identity::ClusterIdentity,
+    stream::AppendStreams,
+};
+use crate::openraft::{
+    error::{to_error, to_network_error},
+    raft_node::NodeId,
+    typeconfig::TypeConfig,
+};
 
 pub struct NetworkConnection {
     addr: String,
     client_poll: Arc<ClientPool>,
+    append_streams: AppendStreams,
+    identity: Arc<ClusterIdentity>,
     target: NodeId,
 }
 impl NetworkConnection {
-    pub fn new(addr: String, client_poll: Arc<ClientPool>, target: NodeId) -> Self {
+    pub fn new(
+        addr: String,
+        client_poll: Arc<ClientPool>,
+        append_streams: AppendStreams,
+        identity: Arc<ClusterIdentity>,
+        target: NodeId,
+    ) -> Self {
         return NetworkConnection {
             addr,
             client_poll,
+            append_streams,
+            identity,
             target,
         };
     }
@@ -36,38 +59,121 @@ impl NetworkConnection {
             .placement_center_openraft_services_client(self.addr.clone())
             .await?);
     }
+
+    /// Whether the peer was built before the typed wire format. Finding out opens the
+    /// append stream, which later appends reuse.
+    async fn is_legacy(&self) -> Result<bool, RobustMQError> {
+        let stream = self.append_streams.get(&self.addr, self.target).await?;
+        return Ok(stream.is_none());
+    }
+
+    // Requests to a node built before the typed wire format carry the bincode encoded
+    // openraft type in field 1, and so do its replies. Such a node has no cluster id.
+
+    async fn legacy_append(
+        &mut self,
+        req: &AppendEntriesRequest<TypeConfig>,
+        option: &RPCOption,
+    ) -> Result<AppendEntriesResponse<TypeConfig>, RobustMQError> {
+        let request = pb::AppendRequest {
+            legacy_value: encode_legacy(req)?,
+            ..Default::default()
+        };
+        let mut c = self.c().await?;
+        let reply = match timeout(option.hard_ttl(), c.append(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(RobustMQError::CommmonError(e.to_string())),
+            Err(_) => return Err(timeout_error(&self.addr, option)),
+        };
+        return decode_legacy(&reply.legacy_value);
+    }
+
+    async fn legacy_snapshot(
+        &mut self,
+        req: &InstallSnapshotRequest<TypeConfig>,
+        option: &RPCOption,
+    ) -> Result<InstallSnapshotResponse<TypeConfig>, RobustMQError> {
+        let request = pb::SnapshotRequest {
+            legacy_value: encode_legacy(req)?,
+            ..Default::default()
+        };
+        let mut c = self.c().await?;
+        let reply = match timeout(option.hard_ttl(), c.snapshot(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(RobustMQError::CommmonError(e.to_string())),
+            Err(_) => return Err(timeout_error(&self.addr, option)),
+        };
+        return decode_legacy(&reply.legacy_value);
+    }
+
+    async fn legacy_vote(
+        &mut self,
+        req: &VoteRequest<TypeConfig>,
+        option: &RPCOption,
+    ) -> Result<VoteResponse<TypeConfig>, RobustMQError> {
+        let request = pb::VoteRequest {
+            legacy_value: encode_legacy(req)?,
+            ..Default::default()
+        };
+        let mut c = self.c().await?;
+        let reply = match timeout(option.hard_ttl(), c.vote(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(RobustMQError::CommmonError(e.to_string())),
+            Err(_) => return Err(timeout_error(&self.addr, option)),
+        };
+        return decode_legacy(&reply.legacy_value);
+    }
+}
+
+fn timeout_error(addr: &String, option: &RPCOption) -> RobustMQError {
+    return RobustMQError::CommmonError(format!(
+        "Request to {} timed out after {:?}",
+        addr,
+        option.hard_ttl()
+    ));
 }
 
 #[allow(clippy::blocks_in_conditions)]
 impl RaftNetwork<TypeConfig> for NetworkConnection {
-    
     async fn append_entries(
         &mut self,
         req: AppendEntriesRequest<TypeConfig>,
-        _option: RPCOption,
+        option: RPCOption,
     ) -> Result<AppendEntriesResponse<TypeConfig>, RPCError<TypeConfig, RaftError<TypeConfig>>>
     {
-
-        let mut c = match self.c().await {
-            Ok(conn) => conn,
+        // Entries go over the long-lived append stream of the peer instead of a unary call
+        // on a pooled connection.
+        let stream = match self.append_streams.get(&self.addr, self.target).await {
+            Ok(Some(stream)) => stream,
+            Ok(None) => match self.legacy_append(&req, &option).await {
+                Ok(resp) => return Ok(resp),
+                Err(e) => return Err(to_error(e)),
+            },
             Err(e) => return Err(to_error(e)),
         };
 
-        let value = match serialize(&req) {
+        let request = match append_request_to_proto(&req) {
             Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(e) => return Err(to_error(e)),
         };
 
-        let request = AppendRequest { value };
-
-        let reply = match c.append(request).await {
-            Ok(reply) => reply.into_inner(),
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+        let reply = match timeout(option.hard_ttl(), stream.append(request)).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(e)) => {
+                self.append_streams.remove(&self.addr);
+                return Err(to_error(e));
+            }
+            Err(_) => {
+                // The timed out request gave up its reply slot. The peer may be stuck on the
+                // stream, so the next request opens a new one.
+                self.append_streams.remove(&self.addr);
+                return Err(to_network_error(timeout_error(&self.addr, &option)));
+            }
         };
 
-        let result = match deserialize(&reply.value) {
+        let result = match append_response_from_proto(reply) {
             Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(e) => return Err(to_error(e)),
         };
 
         return Ok(result);
@@ -76,32 +182,40 @@ impl RaftNetwork<TypeConfig> for NetworkConnection {
     async fn install_snapshot(
         &mut self,
         req: InstallSnapshotRequest<TypeConfig>,
-        _option: RPCOption,
+        option: RPCOption,
     ) -> Result<
         InstallSnapshotResponse<TypeConfig>,
         RPCError<TypeConfig, RaftError<TypeConfig, InstallSnapshotError>>,
     > {
         tracing::debug!(req = debug(&req), "install_snapshot");
+        match self.is_legacy().await {
+            Ok(false) => {}
+            Ok(true) => match self.legacy_snapshot(&req, &option).await {
+                Ok(resp) => return Ok(resp),
+                Err(e) => return Err(to_error(e)),
+            },
+            Err(e) => return Err(to_error(e)),
+        }
 
         let mut c = match self.c().await {
             Ok(conn) => conn,
             Err(e) => return Err(to_error(e)),
         };
 
-        let value = match serialize(&req) {
-            Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
-        };
+        let mut request = snapshot_request_to_proto(&req);
+        request.header = Some(self.identity.header(self.target));
 
-        let request = SnapshotRequest { value };
-
-        let reply = match c.snapshot(request).await {
-            Ok(reply) => reply.into_inner(),
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+        let reply = match timeout(option.hard_ttl(), c.snapshot(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(_) => return Err(to_network_error(timeout_error(&self.addr, &option))),
         };
-        let result = match deserialize(&reply.value) {
+        if let Err(e) = self.identity.check_peer(self.target, &reply.cluster_id) {
+            return Err(to_error(e));
+        }
+        let result = match snapshot_response_from_proto(reply) {
             Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(e) => return Err(to_error(e)),
         };
 
         return Ok(result);
@@ -110,30 +224,120 @@ impl RaftNetwork<TypeConfig> for NetworkConnection {
     async fn vote(
         &mut self,
         req: VoteRequest<TypeConfig>,
-        _option: RPCOption,
+        option: RPCOption,
     ) -> Result<VoteResponse<TypeConfig>, RPCError<TypeConfig, RaftError<TypeConfig>>> {
         tracing::debug!(req = debug(&req), "vote");
+        match self.is_legacy().await {
+            Ok(false) => {}
+            Ok(true) => match self.legacy_vote(&req, &option).await {
+                Ok(resp) => return Ok(resp),
+                Err(e) => return Err(to_error(e)),
+            },
+            Err(e) => return Err(to_error(e)),
+        }
         let mut c = match self.c().await {
             Ok(conn) => conn,
             Err(e) => return Err(to_error(e)),
         };
 
-        let value = match serialize(&req) {
-            Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
-        };
+        let mut request = vote_request_to_proto(&req);
+        request.header = Some(self.identity.header(self.target));
 
-        let request = protocol::openraft::VoteRequest { value };
-
-        let reply = match c.vote(request).await {
-            Ok(reply) => reply.into_inner(),
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+        let reply = match timeout(option.hard_ttl(), c.vote(request)).await {
+            Ok(Ok(reply)) => reply.into_inner(),
+            Ok(Err(e)) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(_) => return Err(to_network_error(timeout_error(&self.addr, &option))),
         };
-        let result = match deserialize(&reply.value) {
+        if let Err(e) = self.identity.check_peer(self.target, &reply.cluster_id) {
+            return Err(to_error(e));
+        }
+        let result = match vote_response_from_proto(reply) {
             Ok(data) => data,
-            Err(e) => return Err(to_error(RobustMQError::CommmonError(e.to_string()))),
+            Err(e) => return Err(to_error(e)),
         };
 
         return Ok(result);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use clients::poll::ClientPool;
+    use openraft::{
+        error::RPCError,
+        network::RPCOption,
+        raft::{AppendEntriesRequest, AppendEntriesResponse},
+        CommittedLeaderId, LogId, RaftNetwork, Vote,
+    };
+
+    use super::NetworkConnection;
+    use crate::openraft::{
+        network::stream::{
+            tests::{start_peer, test_identity, CLOSE_STREAM, NO_REPLY},
+            AppendStreams,
+        },
+        typeconfig::TypeConfig,
+    };
+
+    fn request(index: u64) -> AppendEntriesRequest<TypeConfig> {
+        return AppendEntriesRequest {
+            vote: Vote::new_committed(1, 1),
+            prev_log_id: Some(LogId::new(CommittedLeaderId::new(1, 1), index)),
+            entries: Vec::new(),
+            leader_commit: None,
+        };
+    }
+
+    fn option() -> RPCOption {
+        return RPCOption::new(Duration::from_millis(200));
+    }
+
+    fn matched(resp: AppendEntriesResponse<TypeConfig>) -> u64 {
+        match resp {
+            AppendEntriesResponse::PartialSuccess(Some(log_id)) => return log_id.index,
+            other => panic!("unexpected append response {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn append_entries_replaces_a_failed_stream() {
+        let (addr, _) = start_peer().await;
+        let identity = test_identity().await;
+        let streams = AppendStreams::new(identity.clone());
+        let mut connection = NetworkConnection::new(
+            addr.clone(),
+            Arc::new(ClientPool::new(3)),
+            streams.clone(),
+            identity,
+            2,
+        );
+
+        let resp = connection.append_entries(request(1), option()).await;
+        assert_eq!(matched(resp.unwrap()), 1);
+        let first = streams.get(&addr, 2).await.unwrap().unwrap();
+
+        // A timeout is a network error, Raft retries right away on a new stream.
+        let err = connection
+            .append_entries(request(NO_REPLY), option())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RPCError::Network(_)));
+        let resp = connection.append_entries(request(2), option()).await;
+        assert_eq!(matched(resp.unwrap()), 2);
+        let second = streams.get(&addr, 2).await.unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+
+        // A stream broken by the peer fails the request and is reconnected on the next.
+        let err = connection
+            .append_entries(request(CLOSE_STREAM), option())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, RPCError::Unreachable(_)));
+        assert!(second.is_closed());
+        let resp = connection.append_entries(request(3), option()).await;
+        assert_eq!(matched(resp.unwrap()), 3);
+    }
+}
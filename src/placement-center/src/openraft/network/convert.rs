@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use common_base::errors::RobustMQError;
+use openraft::{
+    raft::{
+        AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest,
+        InstallSnapshotResponse, VoteRequest, VoteResponse,
+    },
+    CommittedLeaderId, Entry, EntryPayload, LeaderId, LogId, Membership, SnapshotMeta,
+    StoredMembership, Vote,
+};
+use protocol::openraft as pb;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::openraft::{
+    raft_node::{Node, NodeId},
+    route::{AppRequestData, APP_REQUEST_VERSION},
+    typeconfig::TypeConfig,
+};
+
+// Conversions between the openraft types and their protobuf messages. Conversions from
+// protobuf fail when a required field is missing, they never panic on peer input.
+
+/// A request of a node built before the typed fields carries the bincode encoded openraft
+/// request in field 1 and no header.
+pub fn is_legacy_request(legacy_value: &[u8], header: Option<&pb::RaftHeader>) -> bool {
+    return header.is_none() && !legacy_value.is_empty();
+}
+
+pub fn encode_legacy<T: Serialize>(value: &T) -> Result<Vec<u8>, RobustMQError> {
+    match bincode::serialize(value) {
+        Ok(data) => return Ok(data),
+        Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+    }
+}
+
+pub fn decode_legacy<T: DeserializeOwned>(value: &[u8]) -> Result<T, RobustMQError> {
+    match bincode::deserialize(value) {
+        Ok(data) => return Ok(data),
+        Err(e) => return Err(RobustMQError::CommmonError(e.to_string())),
+    }
+}
+
+pub fn vote_to_proto(vote: &Vote<NodeId>) -> pb::Vote {
+    return pb::Vote {
+        term: vote.leader_id.term,
+        voted_for: vote.leader_id.voted_for,
+        committed: vote.committed,
+    };
+}
+
+pub fn vote_from_proto(vote: Option<pb::Vote>) -> Result<Vote<NodeId>, RobustMQError> {
+    let vote = required(vote, "vote")?;
+    return Ok(Vote {
+        leader_id: LeaderId {
+            term: vote.term,
+            voted_for: vote.voted_for,
+        },
+        committed: vote.committed,
+    });
+}
+
+pub fn log_id_to_proto(log_id: &Option<LogId<NodeId>>) -> Option<pb::LogId> {
+    return log_id.map(|log_id| pb::LogId {
+        term: log_id.leader_id.term,
+        node_id: log_id.leader_id.node_id,
+        index: log_id.index,
+    });
+}
+
+pub fn log_id_from_proto(log_id: Option<pb::LogId>) -> Option<LogId<NodeId>> {
+    return log_id.map(|log_id| {
+        LogId::new(
+            CommittedLeaderId::new(log_id.term, log_id.node_id),
+            log_id.index,
+        )
+    });
+}
+
+pub fn membership_to_proto(membership: &Membership<TypeConfig>) -> pb::Membership {
+    let configs = membership
+        .get_joint_config()
+        .iter()
+        .map(|config| pb::NodeIdSet {
+            node_ids: config.iter().copied().collect(),
+        })
+        .collect();
+    let nodes = membership
+        .nodes()
+        .map(|(node_id, node)| {
+            (
+                *node_id,
+                pb::Node {
+                    node_id: node.node_id,
+                    rpc_addr: node.rpc_addr.clone(),
+                },
+            )
+        })
+        .collect();
+    return pb::Membership { configs, nodes };
+}
+
+pub fn membership_from_proto(
+    membership: Option<pb::Membership>,
+) -> Result<Membership<TypeConfig>, RobustMQError> {
+    let membership = required(membership, "membership")?;
+    let configs: Vec<BTreeSet<NodeId>> = membership
+        .configs
+        .into_iter()
+        .map(|config| config.node_ids.into_iter().collect())
+        .collect();
+    if configs.is_empty() {
+        return Err(RobustMQError::ParameterCannotBeNull(
+            "membership.configs".to_string(),
+        ));
+    }
+    let nodes: BTreeMap<NodeId, Node> = membership
+        .nodes
+        .into_iter()
+        .map(|(node_id, node)| {
+            (
+                node_id,
+                Node {
+                    node_id: node.node_id,
+                    rpc_addr: node.rpc_addr,
+                },
+            )
+        })
+        .collect();
+    return Ok(Membership::new(configs, nodes));
+}
+
+pub fn entry_to_proto(entry: &Entry<TypeConfig>) -> Result<pb::Entry, RobustMQError> {
+    let payload = match &entry.payload {
+        EntryPayload::Blank => pb::entry::Payload::Blank(pb::EntryBlank {}),
+        EntryPayload::Normal(data) => pb::entry::Payload::Normal(pb::AppData {
+            version: APP_REQUEST_VERSION,
+            data: serde_json::to_vec(data)?,
+        }),
+        EntryPayload::Membership(membership) => {
+            pb::entry::Payload::Membership(membership_to_proto(membership))
+        }
+    };
+    return Ok(pb::Entry {
+        log_id: log_id_to_proto(&Some(entry.log_id)),
+        payload: Some(payload),
+    });
+}
+
+pub fn entry_from_proto(entry: pb::Entry) -> Result<Entry<TypeConfig>, RobustMQError> {
+    let log_id = required(log_id_from_proto(entry.log_id), "entry.log_id")?;
+    let payload = match required(entry.payload, "entry.payload")? {
+        pb::entry::Payload::Blank(_) => EntryPayload::Blank,
+        pb::entry::Payload::Normal(data) => EntryPayload::Normal(app_data_from_proto(data)?),
+        pb::entry::Payload::Membership(membership) => {
+            EntryPayload::Membership(membership_from_proto(Some(membership))?)
+        }
+    };
+    return Ok(Entry { log_id, payload });
+}
+
+/// Requests only ever gain variants. One a node cannot decode while the writer had a newer
+/// request version is most likely a variant added since, the node needs an upgrade.
+fn app_data_from_proto(data: pb::AppData) -> Result<AppRequestData, RobustMQError> {
+    match serde_json::from_slice::<AppRequestData>(&data.data) {
+        Ok(req) => return Ok(req),
+        Err(e) if data.version > APP_REQUEST_VERSION => {
+            return Err(RobustMQError::CommmonError(format!(
+                "Entry written with request version {}, this node supports up to {} and must be upgraded: {}",
+                data.version, APP_REQUEST_VERSION, e
+            )));
+        }
+        Err(e) => return Err(e.into()),
+    }
+}
+
+pub fn snapshot_meta_to_proto(meta: &SnapshotMeta<TypeConfig>) -> pb::SnapshotMeta {
+    return pb::SnapshotMeta {
+        last_log_id: log_id_to_proto(&meta.last_log_id),
+        last_membership: Some(pb::StoredMembership {
+            log_id: log_id_to_proto(meta.last_membership.log_id()),
+            membership: Some(membership_to_proto(meta.last_membership.membership())),
+        }),
+        snapshot_id: meta.snapshot_id.clone(),
+    };
+}
+
+pub fn snapshot_meta_from_proto(
+    meta: Option<pb::SnapshotMeta>,
+) -> Result<SnapshotMeta<TypeConfig>, RobustMQError> {
+    let meta = required(meta, "snapshot meta")?;
+    let last_membership = required(meta.last_membership, "snapshot meta.last_membership")?;
+    return Ok(SnapshotMeta {
+        last_log_id: log_id_from_proto(meta.last_log_id),
+        last_membership: StoredMembership::new(
+            log_id_from_proto(last_membership.log_id),
+            membership_from_proto(last_membership.membership)?,
+        ),
+        snapshot_id: meta.snapshot_id,
+    });
+}
+
+pub fn vote_request_to_proto(req: &VoteRequest<TypeConfig>) -> pb::VoteRequest {
+    return pb::VoteRequest {
+        legacy_value: Vec::new(),
+        vote: Some(vote_to_proto(&req.vote)),
+        last_log_id: log_id_to_proto(&req.last_log_id),
+        header: None,
+    };
+}
+
+pub fn vote_request_from_proto(
+    req: pb::VoteRequest,
+) -> Result<VoteRequest<TypeConfig>, RobustMQError> {
+    return Ok(VoteRequest {
+        vote: vote_from_proto(req.vote)?,
+        last_log_id: log_id_from_proto(req.last_log_id),
+    });
+}
+
+pub fn vote_response_to_proto(resp: &VoteResponse<TypeConfig>) -> pb::VoteReply {
+    return pb::VoteReply {
+        legacy_value: Vec::new(),
+        vote: Some(vote_to_proto(&resp.vote)),
+        vote_granted: resp.vote_granted,
+        last_log_id: log_id_to_proto(&resp.last_log_id),
+        cluster_id: String::new(),
+    };
+}
+
+pub fn vote_response_from_proto(
+    reply: pb::VoteReply,
+) -> Result<VoteResponse<TypeConfig>, RobustMQError> {
+    return Ok(VoteResponse {
+        vote: vote_from_proto(reply.vote)?,
+        vote_granted: reply.vote_granted,
+        last_log_id: log_id_from_proto(reply.last_log_id),
+    });
+}
+
+pub fn append_request_to_proto(
+    req: &AppendEntriesRequest<TypeConfig>,
+) -> Result<pb::AppendRequest, RobustMQError> {
+    let mut entries = Vec::with_capacity(req.entries.len());
+    for entry in req.entries.iter() {
+        entries.push(entry_to_proto(entry)?);
+    }
+    return Ok(pb::AppendRequest {
+        legacy_value: Vec::new(),
+        vote: Some(vote_to_proto(&req.vote)),
+        prev_log_id: log_id_to_proto(&req.prev_log_id),
+        entries,
+        leader_commit: log_id_to_proto(&req.leader_commit),
+        header: None,
+    });
+}
+
+pub fn append_request_from_proto(
+    req: pb::AppendRequest,
+) -> Result<AppendEntriesRequest<TypeConfig>, RobustMQError> {
+    let mut entries = Vec::with_capacity(req.entries.len());
+    for entry in req.entries {
+        entries.push(entry_from_proto(entry)?);
+    }
+    return Ok(AppendEntriesRequest {
+        vote: vote_from_proto(req.vote)?,
+        prev_log_id: log_id_from_proto(req.prev_log_id),
+        entries,
+        leader_commit: log_id_from_proto(req.leader_commit),
+    });
+}
+
+pub fn append_response_to_proto(resp: &AppendEntriesResponse<TypeConfig>) -> pb::AppendReply {
+    let mut reply = pb::AppendReply::default();
+    match resp {
+        AppendEntriesResponse::Success => {
+            reply.set_result(pb::AppendResult::Success);
+        }
+        AppendEntriesResponse::PartialSuccess(matching) => {
+            reply.set_result(pb::AppendResult::PartialSuccess);
+            reply.matching = log_id_to_proto(matching);
+        }
+        AppendEntriesResponse::Conflict => {
+            reply.set_result(pb::AppendResult::Conflict);
+        }
+        AppendEntriesResponse::HigherVote(vote) => {
+            reply.set_result(pb::AppendResult::HigherVote);
+            reply.higher_vote = Some(vote_to_proto(vote));
+        }
+    }
+    return reply;
+}
+
+pub fn append_response_from_proto(
+    reply: pb::AppendReply,
+) -> Result<AppendEntriesResponse<TypeConfig>, RobustMQError> {
+    let result = match pb::AppendResult::try_from(reply.result) {
+        Ok(result) => result,
+        Err(_) => {
+            return Err(RobustMQError::CommmonError(format!(
+                "unknown append result {}",
+                reply.result
+            )));
+        }
+    };
+    match result {
+        pb::AppendResult::Success => return Ok(AppendEntriesResponse::Success),
+        pb::AppendResult::PartialSuccess => {
+            return Ok(AppendEntriesResponse::PartialSuccess(log_id_from_proto(
+                reply.matching,
+            )));
+        }
+        pb::AppendResult::Conflict => return Ok(AppendEntriesResponse::Conflict),
+        pb::AppendResult::HigherVote => {
+            return Ok(AppendEntriesResponse::HigherVote(vote_from_proto(
+                reply.higher_vote,
+            )?));
+        }
+    }
+}
+
+pub fn snapshot_request_to_proto(req: &InstallSnapshotRequest<TypeConfig>) -> pb::SnapshotRequest {
+    return pb::SnapshotRequest {
+        legacy_value: Vec::new(),
+        vote: Some(vote_to_proto(&req.vote)),
+        meta: Some(snapshot_meta_to_proto(&req.meta)),
+        offset: req.offset,
+        data: req.data.clone(),
+        done: req.done,
+        header: None,
+    };
+}
+
+pub fn snapshot_request_from_proto(
+    req: pb::SnapshotRequest,
+) -> Result<InstallSnapshotRequest<TypeConfig>, RobustMQError> {
+    return Ok(InstallSnapshotRequest {
+        vote: vote_from_proto(req.vote)?,
+        meta: snapshot_meta_from_proto(req.meta)?,
+        offset: req.offset,
+        data: req.data,
+        done: req.done,
+    });
+}
+
+pub fn snapshot_response_to_proto(resp: &InstallSnapshotResponse<TypeConfig>) -> pb::SnapshotReply {
+    return pb::SnapshotReply {
+        legacy_value: Vec::new(),
+        vote: Some(vote_to_proto(&resp.vote)),
+        cluster_id: String::new(),
+    };
+}
+
+pub fn snapshot_response_from_proto(
+    reply: pb::SnapshotReply,
+) -> Result<InstallSnapshotResponse<TypeConfig>, RobustMQError> {
+    return Ok(InstallSnapshotResponse {
+        vote: vote_from_proto(reply.vote)?,
+    });
+}
+
+fn required<T>(value: Option<T>, name: &str) -> Result<T, RobustMQError> {
+    match value {
+        Some(value) => return Ok(value),
+        None => return Err(RobustMQError::ParameterCannotBeNull(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{
+        append_request_from_proto, append_request_to_proto, append_response_from_proto,
+        append_response_to_proto, decode_legacy, encode_legacy, entry_from_proto,
+        is_legacy_request, snapshot_request_from_proto, snapshot_request_to_proto,
+        vote_request_from_proto, vote_request_to_proto,
+    };
+    use crate::openraft::{
+        raft_node::Node,
+        route::{AppRequestData, APP_REQUEST_VERSION},
+        typeconfig::TypeConfig,
+    };
+    use openraft::{
+        raft::{
+            AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest, VoteRequest,
+            VoteResponse,
+        },
+        CommittedLeaderId, Entry, EntryPayload, LogId, Membership, SnapshotMeta, StoredMembership,
+        Vote,
+    };
+    use prost::Message;
+    use protocol::openraft as pb;
+
+    fn log_id(term: u64, index: u64) -> LogId<u64> {
+        return LogId::new(CommittedLeaderId::new(term, 1), index);
+    }
+
+    fn membership() -> Membership<TypeConfig> {
+        let mut nodes = BTreeMap::new();
+        for node_id in 1..=3 {
+            nodes.insert(
+                node_id,
+                Node {
+                    node_id,
+                    rpc_addr: format!("127.0.0.1:{}", 1227 + node_id),
+                },
+            );
+        }
+        return Membership::new(vec![BTreeSet::from([1, 2, 3])], nodes);
+    }
+
+    #[test]
+    fn vote_request_round_trip() {
+        let req = VoteRequest::<TypeConfig> {
+            vote: Vote::new(3, 2),
+            last_log_id: Some(log_id(2, 10)),
+        };
+        let decoded = vote_request_from_proto(vote_request_to_proto(&req)).unwrap();
+        assert_eq!(decoded, req);
+    }
+
+    #[test]
+    fn append_request_round_trip() {
+        let req = AppendEntriesRequest::<TypeConfig> {
+            vote: Vote::new_committed(3, 1),
+            prev_log_id: Some(log_id(3, 4)),
+            entries: vec![
+                Entry {
+                    log_id: log_id(3, 5),
+                    payload: EntryPayload::Blank,
+                },
+                Entry {
+                    log_id: log_id(3, 6),
+                    payload: EntryPayload::Normal(AppRequestData::Set {
+                        key: "k1".to_string(),
+                        value: "v1".to_string(),
+                    }),
+                },
+                Entry {
+                    log_id: log_id(3, 7),
+                    payload: EntryPayload::Membership(membership()),
+                },
+            ],
+            leader_commit: Some(log_id(3, 5)),
+        };
+        let decoded = append_request_from_proto(append_request_to_proto(&req).unwrap()).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", req));
+    }
+
+    #[test]
+    fn append_response_round_trip() {
+        let responses = vec![
+            AppendEntriesResponse::<TypeConfig>::Success,
+            AppendEntriesResponse::PartialSuccess(Some(log_id(2, 8))),
+            AppendEntriesResponse::Conflict,
+            AppendEntriesResponse::HigherVote(Vote::new(5, 3)),
+        ];
+        for resp in responses {
+            let decoded = append_response_from_proto(append_response_to_proto(&resp)).unwrap();
+            assert_eq!(decoded, resp);
+        }
+    }
+
+    #[test]
+    fn snapshot_request_round_trip() {
+        let req = InstallSnapshotRequest::<TypeConfig> {
+            vote: Vote::new_committed(2, 1),
+            meta: SnapshotMeta {
+                last_log_id: Some(log_id(2, 20)),
+                last_membership: StoredMembership::new(Some(log_id(1, 1)), membership()),
+                snapshot_id: "2-20-1".to_string(),
+            },
+            offset: 0,
+            data: b"{}".to_vec(),
+            done: true,
+        };
+        let decoded = snapshot_request_from_proto(snapshot_request_to_proto(&req)).unwrap();
+        assert_eq!(format!("{:?}", decoded), format!("{:?}", req));
+    }
+
+    #[test]
+    fn missing_fields_are_rejected() {
+        assert!(vote_request_from_proto(pb::VoteRequest::default()).is_err());
+        assert!(append_request_from_proto(pb::AppendRequest::default()).is_err());
+        assert!(snapshot_request_from_proto(pb::SnapshotRequest::default()).is_err());
+
+        let mut reply = pb::AppendReply::default();
+        reply.result = 42;
+        assert!(append_response_from_proto(reply).is_err());
+    }
+
+    /// The message as a node built before the typed fields writes it: the bincode encoded
+    /// openraft type in field 1 and nothing else.
+    fn legacy_message<T: serde::Serialize>(value: &T) -> Vec<u8> {
+        let value = bincode::serialize(value).unwrap();
+        let mut buf = vec![0x0a];
+        prost::encoding::encode_varint(value.len() as u64, &mut buf);
+        buf.extend_from_slice(&value);
+        return buf;
+    }
+
+    #[test]
+    fn legacy_messages_are_decoded() {
+        let req = VoteRequest::<TypeConfig> {
+            vote: Vote::new(3, 2),
+            last_log_id: Some(log_id(2, 10)),
+        };
+        let decoded = pb::VoteRequest::decode(legacy_message(&req).as_slice()).unwrap();
+        assert!(decoded.vote.is_none());
+        assert!(is_legacy_request(
+            &decoded.legacy_value,
+            decoded.header.as_ref()
+        ));
+        let legacy: VoteRequest<TypeConfig> = decode_legacy(&decoded.legacy_value).unwrap();
+        assert_eq!(legacy, req);
+
+        let req = AppendEntriesRequest::<TypeConfig> {
+            vote: Vote::new_committed(3, 1),
+            prev_log_id: Some(log_id(3, 4)),
+            entries: vec![Entry {
+                log_id: log_id(3, 5),
+                payload: EntryPayload::Normal(AppRequestData::Set {
+                    key: "k1".to_string(),
+                    value: "v1".to_string(),
+                }),
+            }],
+            leader_commit: Some(log_id(3, 4)),
+        };
+        let decoded = pb::AppendRequest::decode(legacy_message(&req).as_slice()).unwrap();
+        assert!(decoded.entries.is_empty());
+        assert!(is_legacy_request(
+            &decoded.legacy_value,
+            decoded.header.as_ref()
+        ));
+        let legacy: AppendEntriesRequest<TypeConfig> =
+            decode_legacy(&decoded.legacy_value).unwrap();
+        assert_eq!(format!("{:?}", legacy), format!("{:?}", req));
+
+        // A reply of an older node leaves the typed fields, including the result, unset.
+        let resp = VoteResponse::<TypeConfig> {
+            vote: Vote::new(3, 2),
+            vote_granted: true,
+            last_log_id: None,
+        };
+        let decoded = pb::VoteReply::decode(legacy_message(&resp).as_slice()).unwrap();
+        assert!(decoded.vote.is_none());
+        let legacy: VoteResponse<TypeConfig> = decode_legacy(&decoded.legacy_value).unwrap();
+        assert_eq!(legacy, resp);
+        let resp = AppendEntriesResponse::<TypeConfig>::Conflict;
+        let decoded = pb::AppendReply::decode(legacy_message(&resp).as_slice()).unwrap();
+        assert_eq!(decoded.result, 0);
+        let legacy: AppendEntriesResponse<TypeConfig> =
+            decode_legacy(&decoded.legacy_value).unwrap();
+        assert_eq!(legacy, resp);
+
+        // Typed requests always carry a header.
+        let mut typed = vote_request_to_proto(&VoteRequest::<TypeConfig> {
+            vote: Vote::new(3, 2),
+            last_log_id: None,
+        });
+        typed.header = Some(pb::RaftHeader::default());
+        assert!(!is_legacy_request(
+            &typed.legacy_value,
+            typed.header.as_ref()
+        ));
+        assert_eq!(
+            encode_legacy(&req).unwrap(),
+            bincode::serialize(&req).unwrap()
+        );
+    }
+
+    #[test]
+    fn newer_request_versions_are_reported() {
+        let entry = |version: u32, data: &[u8]| pb::Entry {
+            log_id: Some(pb::LogId {
+                term: 1,
+                node_id: 1,
+                index: 1,
+            }),
+            payload: Some(pb::entry::Payload::Normal(pb::AppData {
+                version,
+                data: data.to_vec(),
+            })),
+        };
+        let data = serde_json::to_vec(&AppRequestData::Delete {
+            key: "k1".to_string(),
+        })
+        .unwrap();
+
+        // A known request decodes whatever the version of its writer.
+        assert!(entry_from_proto(entry(APP_REQUEST_VERSION, &data)).is_ok());
+        assert!(entry_from_proto(entry(APP_REQUEST_VERSION + 1, &data)).is_ok());
+
+        let unknown = br#"{"Unknown":{"key":"k1"}}"#;
+        let err = entry_from_proto(entry(APP_REQUEST_VERSION + 1, unknown)).unwrap_err();
+        assert!(err.to_string().contains("must be upgraded"));
+        let err = entry_from_proto(entry(APP_REQUEST_VERSION, unknown)).unwrap_err();
+        assert!(!err.to_string().contains("must be upgraded"));
+    }
+}
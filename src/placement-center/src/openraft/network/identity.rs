@@ -0,0 +1,340 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, RwLock},
+};
+
+use common_base::{
+    config::placement_center::{placement_center_conf, PlacementCenterConfig},
+    errors::RobustMQError,
+    tools::unique_id,
+};
+use log::info;
+use openraft::Vote;
+use protocol::openraft::RaftHeader;
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+
+use crate::openraft::{
+    raft_node::NodeId,
+    store::{
+        codec::{decode_value, encode_value},
+        CF_RAFT_STORE,
+    },
+};
+
+pub const CLUSTER_ID_KEY: &[u8] = b"cluster_id";
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoredClusterId {
+    pub cluster_name: String,
+    pub cluster_id: String,
+}
+
+/// The persisted id of the cluster this node belongs to. It is generated by the node that
+/// initializes the cluster and learned by every other node from the first Raft RPC it
+/// exchanges with a configured node that has one. Once known it never changes, and peers
+/// presenting another id or none are rejected.
+pub struct ClusterIdentity {
+    db: Arc<DB>,
+    cluster_name: String,
+    /// Nodes of the configuration, the only peers an id is learned from.
+    configured: BTreeSet<NodeId>,
+    cluster_id: RwLock<Option<String>>,
+}
+
+impl ClusterIdentity {
+    /// Load the id stored in the data directory. Fails when the directory belongs to a
+    /// cluster other than the configured one.
+    pub fn load(db: Arc<DB>, conf: &PlacementCenterConfig) -> Result<Self, RobustMQError> {
+        let stored = read_cluster_id(&db)?;
+        if let Some(stored) = &stored {
+            if stored.cluster_name != conf.cluster_name {
+                return Err(RobustMQError::CommmonError(format!(
+                    "Data directory belongs to cluster {}, the configured cluster is {}",
+                    stored.cluster_name, conf.cluster_name
+                )));
+            }
+            if let Some(cluster_id) = &conf.cluster_id {
+                if *cluster_id != stored.cluster_id {
+                    return Err(RobustMQError::CommmonError(format!(
+                        "Data directory belongs to cluster id {}, the configured cluster id is {}",
+                        stored.cluster_id, cluster_id
+                    )));
+                }
+            }
+        }
+
+        let identity = ClusterIdentity {
+            db,
+            cluster_name: conf.cluster_name.clone(),
+            configured: configured_node_ids(conf),
+            cluster_id: RwLock::new(stored.map(|stored| stored.cluster_id)),
+        };
+        if identity.cluster_id().is_none() {
+            if let Some(cluster_id) = &conf.cluster_id {
+                identity.save(&conf.cluster_name, cluster_id)?;
+            }
+        }
+        return Ok(identity);
+    }
+
+    pub fn cluster_id(&self) -> Option<String> {
+        return self.cluster_id.read().unwrap().clone();
+    }
+
+    /// Generate the cluster id unless one is known already. Called by the node that
+    /// initializes the cluster.
+    pub fn init(&self, cluster_name: &String) -> Result<String, RobustMQError> {
+        if let Some(cluster_id) = self.cluster_id() {
+            return Ok(cluster_id);
+        }
+        let cluster_id = unique_id();
+        self.save(cluster_name, &cluster_id)?;
+        info!("Cluster {} was assigned id {}", cluster_name, cluster_id);
+        return Ok(cluster_id);
+    }
+
+    /// Check the cluster id presented by the peer `peer_id`. A node without an id adopts
+    /// the id of a configured peer, a peer without an id is only accepted while this node
+    /// has none either.
+    pub fn check_peer(
+        &self,
+        peer_id: NodeId,
+        peer_cluster_id: &String,
+    ) -> Result<(), RobustMQError> {
+        match self.cluster_id() {
+            Some(cluster_id) if cluster_id == *peer_cluster_id => return Ok(()),
+            Some(cluster_id) => {
+                return Err(RobustMQError::CommmonError(format!(
+                    "Peer {} belongs to cluster id {:?}, this node belongs to cluster id {}",
+                    peer_id, peer_cluster_id, cluster_id
+                )));
+            }
+            None if peer_cluster_id.is_empty() => return Ok(()),
+            None => {
+                if !self.configured.contains(&peer_id) {
+                    return Err(RobustMQError::CommmonError(format!(
+                        "Peer {} is not a configured node, its cluster id {} is not adopted",
+                        peer_id, peer_cluster_id
+                    )));
+                }
+                self.save(&self.cluster_name, peer_cluster_id)?;
+                info!(
+                    "Joined cluster id {} learned from node {}",
+                    peer_cluster_id, peer_id
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    /// Header attached by this node to every Raft RPC it sends to `target`.
+    pub fn header(&self, target: NodeId) -> RaftHeader {
+        let conf = placement_center_conf();
+        return RaftHeader {
+            cluster_name: conf.cluster_name.clone(),
+            from_node_id: conf.node_id,
+            to_node_id: target,
+            cluster_id: self.cluster_id().unwrap_or_default(),
+        };
+    }
+
+    fn save(&self, cluster_name: &String, cluster_id: &String) -> Result<(), RobustMQError> {
+        let mut current = self.cluster_id.write().unwrap();
+        // Another request may have stored an id in the meantime, the first one wins.
+        if let Some(existing) = current.as_ref() {
+            if existing != cluster_id {
+                return Err(RobustMQError::CommmonError(format!(
+                    "Cluster id {} is already assigned, refusing {}",
+                    existing, cluster_id
+                )));
+            }
+            return Ok(());
+        }
+        let stored = StoredClusterId {
+            cluster_name: cluster_name.clone(),
+            cluster_id: cluster_id.clone(),
+        };
+        write_cluster_id(&self.db, &stored)?;
+        *current = Some(cluster_id.clone());
+        return Ok(());
+    }
+}
+
+/// Replace the cluster id stored in the data directory.
+pub fn write_cluster_id(db: &DB, stored: &StoredClusterId) -> Result<(), RobustMQError> {
+    let value = match encode_value(stored) {
+        Ok(data) => data,
+        Err(e) => return Err(RobustMQError::CommmonError(e)),
+    };
+    db.put_cf(raft_store(db)?, CLUSTER_ID_KEY, value)?;
+    db.flush_wal(true)?;
+    return Ok(());
+}
+
+pub fn read_cluster_id(db: &DB) -> Result<Option<StoredClusterId>, RobustMQError> {
+    match db.get_cf(raft_store(db)?, CLUSTER_ID_KEY)? {
+        Some(data) => match decode_value::<StoredClusterId>(&data) {
+            Ok(stored) => return Ok(Some(stored)),
+            Err(e) => return Err(RobustMQError::CommmonError(e)),
+        },
+        None => return Ok(None),
+    }
+}
+
+fn raft_store(db: &DB) -> Result<&rocksdb::ColumnFamily, RobustMQError> {
+    match db.cf_handle(CF_RAFT_STORE) {
+        Some(cf) => return Ok(cf),
+        None => {
+            return Err(RobustMQError::CommmonError(format!(
+                "Column family {} does not exist",
+                CF_RAFT_STORE
+            )));
+        }
+    }
+}
+
+fn configured_node_ids(conf: &PlacementCenterConfig) -> BTreeSet<NodeId> {
+    return conf
+        .nodes
+        .keys()
+        .filter_map(|node_id| node_id.parse::<NodeId>().ok())
+        .collect();
+}
+
+/// Accept a Raft RPC only when it comes from the same cluster, is addressed to this node
+/// and the sender is either a configured node or a member of the current membership.
+pub fn verify_header(
+    header: &RaftHeader,
+    conf: &PlacementCenterConfig,
+    members: &BTreeSet<NodeId>,
+) -> Result<(), RobustMQError> {
+    if header.cluster_name != conf.cluster_name {
+        return Err(RobustMQError::CommmonError(format!(
+            "Raft request from node {} belongs to cluster {}, this node belongs to cluster {}",
+            header.from_node_id, header.cluster_name, conf.cluster_name
+        )));
+    }
+
+    if header.to_node_id != conf.node_id {
+        return Err(RobustMQError::CommmonError(format!(
+            "Raft request from node {} is addressed to node {}, this node is {}",
+            header.from_node_id, header.to_node_id, conf.node_id
+        )));
+    }
+
+    let configured = configured_node_ids(conf).contains(&header.from_node_id);
+    if !configured && !members.contains(&header.from_node_id) {
+        return Err(RobustMQError::CommmonError(format!(
+            "Raft request from unknown node {}",
+            header.from_node_id
+        )));
+    }
+    return Ok(());
+}
+
+/// The vote carried by a request names the candidate or leader, which must be the sender.
+pub fn verify_sender(header: &RaftHeader, vote: &Vote<NodeId>) -> Result<(), RobustMQError> {
+    match vote.leader_id.voted_for {
+        Some(node_id) if node_id != header.from_node_id => {
+            return Err(RobustMQError::CommmonError(format!(
+                "Raft request from node {} carries a vote of node {}",
+                header.from_node_id, node_id
+            )));
+        }
+        _ => return Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{verify_header, verify_sender, ClusterIdentity};
+    use crate::openraft::store::new_storage;
+    use common_base::{config::placement_center::PlacementCenterConfig, tools::now_second};
+    use openraft::Vote;
+    use protocol::openraft::RaftHeader;
+
+    fn config() -> PlacementCenterConfig {
+        let mut config = PlacementCenterConfig::default();
+        config.cluster_name = "placement-test".to_string();
+        config.node_id = 1;
+        config.nodes =
+            serde_json::from_str(r#"{"1": "127.0.0.1:1228", "2": "127.0.0.1:1229"}"#).unwrap();
+        return config;
+    }
+
+    fn header(cluster_name: &str, from: u64, to: u64) -> RaftHeader {
+        return RaftHeader {
+            cluster_name: cluster_name.to_string(),
+            from_node_id: from,
+            to_node_id: to,
+            cluster_id: String::new(),
+        };
+    }
+
+    #[test]
+    fn verify_header_test() {
+        let config = config();
+        let members = BTreeSet::from([1, 2, 3]);
+        assert!(verify_header(&header("placement-test", 2, 1), &config, &members).is_ok());
+        // Not configured but already a member, e.g. a learner added at runtime.
+        assert!(verify_header(&header("placement-test", 3, 1), &config, &members).is_ok());
+
+        assert!(verify_header(&header("other", 2, 1), &config, &members).is_err());
+        assert!(verify_header(&header("placement-test", 2, 3), &config, &members).is_err());
+        assert!(verify_header(&header("placement-test", 4, 1), &config, &members).is_err());
+    }
+
+    #[test]
+    fn verify_sender_test() {
+        let header = header("placement-test", 2, 1);
+        assert!(verify_sender(&header, &Vote::new(3, 2)).is_ok());
+        assert!(verify_sender(&header, &Vote::new(3, 3)).is_err());
+    }
+
+    #[tokio::test]
+    async fn cluster_identity_test() {
+        let mut config = config();
+        config.data_path = format!("/tmp/tmp_test_cluster_identity/{}", now_second());
+        let (log_store, _) = new_storage(&config.data_path, &config.rocksdb).await;
+        let db = log_store.db.clone();
+
+        let identity = ClusterIdentity::load(db.clone(), &config).unwrap();
+        assert!(identity.cluster_id().is_none());
+        // Peers without an id are accepted until one is known.
+        assert!(identity.check_peer(2, &String::new()).is_ok());
+
+        let cluster_id = identity.init(&config.cluster_name).unwrap();
+        assert_eq!(identity.init(&config.cluster_name).unwrap(), cluster_id);
+        assert!(identity.check_peer(2, &cluster_id).is_ok());
+        assert!(identity
+            .check_peer(2, &"another-cluster".to_string())
+            .is_err());
+        assert!(identity.check_peer(2, &String::new()).is_err());
+
+        let reloaded = ClusterIdentity::load(db.clone(), &config).unwrap();
+        assert_eq!(reloaded.cluster_id(), Some(cluster_id.clone()));
+
+        // A node without an id only adopts the id of a configured node.
+        let mut joining = config.clone();
+        joining.data_path = format!("{}/joining", config.data_path);
+        let (log_store, _) = new_storage(&joining.data_path, &joining.rocksdb).await;
+        let joining = ClusterIdentity::load(log_store.db.clone(), &joining).unwrap();
+        assert!(joining.check_peer(3, &cluster_id).is_err());
+        assert!(joining.cluster_id().is_none());
+        assert!(joining.check_peer(2, &cluster_id).is_ok());
+        assert_eq!(joining.cluster_id(), Some(cluster_id.clone()));
+
+        let mut renamed = config.clone();
+        renamed.cluster_name = "other".to_string();
+        assert!(ClusterIdentity::load(db.clone(), &renamed).is_err());
+
+        let mut pinned = config.clone();
+        pinned.cluster_id = Some("another-cluster".to_string());
+        assert!(ClusterIdentity::load(db.clone(), &pinned).is_err());
+        pinned.cluster_id = Some(cluster_id);
+        assert!(ClusterIdentity::load(db, &pinned).is_ok());
+    }
+}
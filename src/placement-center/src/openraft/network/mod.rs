@@ -1,2 +1,5 @@
 pub mod connection;
+pub mod convert;
+pub mod identity;
 pub mod network;
+pub mod stream;
@@ -3,7 +3,7 @@ use log::info;
 use openraft::RaftNetworkFactory;
 use std::sync::Arc;
 
-use super::connection::NetworkConnection;
+use super::{connection::NetworkConnection, identity::ClusterIdentity, stream::AppendStreams};
 use crate::openraft::{
     raft_node::{Node, NodeId},
     typeconfig::TypeConfig,
@@ -11,11 +11,17 @@ use crate::openraft::{
 
 pub struct Network {
     client_poll: Arc<ClientPool>,
+    append_streams: AppendStreams,
+    identity: Arc<ClusterIdentity>,
 }
 
 impl Network {
-    pub fn new(client_poll: Arc<ClientPool>) -> Network {
-        return Network { client_poll };
+    pub fn new(client_poll: Arc<ClientPool>, identity: Arc<ClusterIdentity>) -> Network {
+        return Network {
+            client_poll,
+            append_streams: AppendStreams::new(identity.clone()),
+            identity,
+        };
     }
 }
 
@@ -27,6 +33,12 @@ impl RaftNetworkFactory<TypeConfig> for Network {
     #[tracing::instrument(level = "debug", skip_all)]
     async fn new_client(&mut self, target: NodeId, node: &Node) -> Self::Network {
         let addr = format!("{}", node.rpc_addr);
-        return NetworkConnection::new(addr, self.client_poll.clone(), target);
+        return NetworkConnection::new(
+            addr,
+            self.client_poll.clone(),
+            self.append_streams.clone(),
+            self.identity.clone(),
+            target,
+        );
     }
 }
@@ -0,0 +1,480 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use common_base::errors::RobustMQError;
+use dashmap::DashMap;
+use log::{error, info};
+use protocol::openraft::{
+    open_raft_service_client::OpenRaftServiceClient, AppendFrame, AppendReply, AppendRequest,
+    AppendStreamRequest,
+};
+use tokio::sync::{mpsc, oneshot};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::Code;
+
+use super::identity::ClusterIdentity;
+use crate::openraft::raft_node::NodeId;
+
+/// Upper bound of append requests packed into one stream message.
+const MAX_COALESCED_FRAMES: usize = 64;
+
+const STREAM_CHANNEL_SIZE: usize = 1024;
+
+/// A peer found to predate the typed wire format is asked again after this long, it may
+/// have been upgraded since.
+const LEGACY_RECHECK: Duration = Duration::from_secs(30);
+
+type AppendResult = Result<AppendReply, RobustMQError>;
+
+type PendingReplies = DashMap<u64, oneshot::Sender<AppendResult>>;
+
+/// A long-lived bidirectional AppendStream to one peer. Requests are written without
+/// waiting for earlier replies, and requests queued at the same time are coalesced
+/// into a single message. Replies are matched to requests by frame id.
+pub struct AppendStream {
+    addr: String,
+    next_id: AtomicU64,
+    closed: Arc<AtomicBool>,
+    pending: Arc<PendingReplies>,
+    submit_sx: mpsc::Sender<AppendFrame>,
+}
+
+impl AppendStream {
+    /// Open the stream, `None` when the peer has no AppendStream because it was built
+    /// before the typed wire format.
+    pub async fn connect(
+        addr: String,
+        target: NodeId,
+        identity: Arc<ClusterIdentity>,
+    ) -> Result<Option<Self>, RobustMQError> {
+        let mut client = match OpenRaftServiceClient::connect(format!("http://{}", addr)).await {
+            Ok(client) => client,
+            Err(e) => {
+                return Err(RobustMQError::CommmonError(format!("{},{}", e, addr)));
+            }
+        };
+
+        let (request_sx, request_rx) = mpsc::channel::<AppendStreamRequest>(STREAM_CHANNEL_SIZE);
+        let mut inbound = match client.append_stream(ReceiverStream::new(request_rx)).await {
+            Ok(response) => response.into_inner(),
+            Err(e) if e.code() == Code::Unimplemented => return Ok(None),
+            Err(e) => {
+                return Err(RobustMQError::CommmonError(format!("{},{}", e, addr)));
+            }
+        };
+
+        let pending: Arc<PendingReplies> = Arc::new(DashMap::new());
+        let closed = Arc::new(AtomicBool::new(false));
+        let (submit_sx, mut submit_rx) = mpsc::channel::<AppendFrame>(STREAM_CHANNEL_SIZE);
+
+        // Writer: drain every request that is already queued into one message.
+        let writer_pending = pending.clone();
+        let writer_closed = closed.clone();
+        let writer_identity = identity.clone();
+        let writer_addr = addr.clone();
+        tokio::spawn(async move {
+            while let Some(frame) = submit_rx.recv().await {
+                let mut request = AppendStreamRequest {
+                    header: Some(writer_identity.header(target)),
+                    ..Default::default()
+                };
+                request.frames.push(frame);
+                while request.frames.len() < MAX_COALESCED_FRAMES {
+                    match submit_rx.try_recv() {
+                        Ok(frame) => request.frames.push(frame),
+                        Err(_) => break,
+                    }
+                }
+
+                let ids: Vec<u64> = request.frames.iter().map(|frame| frame.id).collect();
+                if request_sx.send(request).await.is_err() {
+                    writer_closed.store(true, Ordering::SeqCst);
+                    for id in ids {
+                        fail_pending(&writer_pending, id, &writer_addr);
+                    }
+                    break;
+                }
+            }
+            writer_closed.store(true, Ordering::SeqCst);
+        });
+
+        // Reader: hand every reply frame to the request waiting for it.
+        let reader_pending = pending.clone();
+        let reader_closed = closed.clone();
+        let reader_addr = addr.clone();
+        tokio::spawn(async move {
+            loop {
+                let reply = match inbound.message().await {
+                    Ok(Some(reply)) => reply,
+                    Ok(None) => {
+                        info!("Append stream to {} was closed by the peer", reader_addr);
+                        break;
+                    }
+                    Err(e) => {
+                        error!("Append stream to {} failed: {}", reader_addr, e);
+                        break;
+                    }
+                };
+
+                // Replies from a node of another cluster fail the requests and close the stream.
+                if let Err(e) = identity.check_peer(target, &reply.cluster_id) {
+                    error!("Append stream to {} rejected: {}", reader_addr, e);
+                    break;
+                }
+
+                for frame in reply.frames {
+                    if let Some((_, reply_sx)) = reader_pending.remove(&frame.id) {
+                        let result = if !frame.error.is_empty() {
+                            Err(RobustMQError::CommmonError(frame.error))
+                        } else if let Some(reply) = frame.reply {
+                            Ok(reply)
+                        } else {
+                            Err(RobustMQError::ParameterCannotBeNull("reply".to_string()))
+                        };
+                        let _ = reply_sx.send(result);
+                    }
+                }
+            }
+
+            // Fail everything still in flight, the caller reconnects. The stream is marked
+            // closed first, so a request registered from now on fails by itself.
+            reader_closed.store(true, Ordering::SeqCst);
+            let ids: Vec<u64> = reader_pending.iter().map(|raw| *raw.key()).collect();
+            for id in ids {
+                fail_pending(&reader_pending, id, &reader_addr);
+            }
+        });
+
+        return Ok(Some(AppendStream {
+            addr,
+            next_id: AtomicU64::new(0),
+            closed,
+            pending,
+            submit_sx,
+        }));
+    }
+
+    pub fn is_closed(&self) -> bool {
+        return self.closed.load(Ordering::SeqCst);
+    }
+
+    /// Send one append request and wait for its reply. A caller that stops waiting, e.g.
+    /// on a timeout, drops the future and its reply slot with it.
+    pub async fn append(&self, request: AppendRequest) -> AppendResult {
+        let frame = AppendFrame {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            request: Some(request),
+        };
+        let (reply_sx, reply_rx) = oneshot::channel();
+        self.pending.insert(frame.id, reply_sx);
+        let _slot = PendingSlot {
+            pending: &self.pending,
+            id: frame.id,
+        };
+
+        // Registered before the check, so the reader either fails it when the stream
+        // closes or the check sees the stream closed.
+        if self.is_closed() || self.submit_sx.send(frame).await.is_err() {
+            return Err(self.closed_error());
+        }
+        match reply_rx.await {
+            Ok(result) => return result,
+            Err(_) => return Err(self.closed_error()),
+        }
+    }
+
+    fn closed_error(&self) -> RobustMQError {
+        return RobustMQError::CommmonError(format!("Append stream to {} was closed", self.addr));
+    }
+}
+
+/// Removes the reply slot of a request when its caller returns or gives up.
+struct PendingSlot<'a> {
+    pending: &'a PendingReplies,
+    id: u64,
+}
+
+impl Drop for PendingSlot<'_> {
+    fn drop(&mut self) {
+        self.pending.remove(&self.id);
+    }
+}
+
+fn fail_pending(pending: &PendingReplies, id: u64, addr: &String) {
+    if let Some((_, reply_sx)) = pending.remove(&id) {
+        let _ = reply_sx.send(Err(RobustMQError::CommmonError(format!(
+            "Append stream to {} was closed",
+            addr
+        ))));
+    }
+}
+
+/// Append streams shared by every connection of the network, one per peer address.
+#[derive(Clone)]
+pub struct AppendStreams {
+    streams: Arc<DashMap<String, Arc<AppendStream>>>,
+    /// Peers built before the typed wire format, with the time that was found out.
+    legacy: Arc<DashMap<String, Instant>>,
+    identity: Arc<ClusterIdentity>,
+}
+
+impl AppendStreams {
+    pub fn new(identity: Arc<ClusterIdentity>) -> Self {
+        return AppendStreams {
+            streams: Arc::new(DashMap::new()),
+            legacy: Arc::new(DashMap::new()),
+            identity,
+        };
+    }
+
+    /// The stream to the peer, `None` when the peer predates the typed wire format and
+    /// has to be sent bincode encoded requests on unary calls.
+    pub async fn get(
+        &self,
+        addr: &String,
+        target: NodeId,
+    ) -> Result<Option<Arc<AppendStream>>, RobustMQError> {
+        if let Some(stream) = self.streams.get(addr) {
+            if !stream.is_closed() {
+                return Ok(Some(stream.clone()));
+            }
+        }
+        let recently_legacy = match self.legacy.get(addr) {
+            Some(since) => since.elapsed() < LEGACY_RECHECK,
+            None => false,
+        };
+        if recently_legacy {
+            return Ok(None);
+        }
+
+        match AppendStream::connect(addr.clone(), target, self.identity.clone()).await? {
+            Some(stream) => {
+                let stream = Arc::new(stream);
+                self.legacy.remove(addr);
+                self.streams.insert(addr.clone(), stream.clone());
+                return Ok(Some(stream));
+            }
+            None => {
+                if self.legacy.insert(addr.clone(), Instant::now()).is_none() {
+                    info!(
+                        "Node {} at {} predates the typed Raft wire format, it is sent bincode encoded requests",
+                        target, addr
+                    );
+                }
+                return Ok(None);
+            }
+        }
+    }
+
+    pub fn remove(&self, addr: &String) {
+        self.streams.remove(addr);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use common_base::config::placement_center::{
+        init_placement_center_conf_by_path, placement_center_conf, RocksDB,
+    };
+    use common_base::tools::unique_id;
+    use protocol::openraft::{
+        open_raft_service_server::{OpenRaftService, OpenRaftServiceServer},
+        AppendReply, AppendReplyFrame, AppendRequest, AppendResult, AppendStreamReply,
+        AppendStreamRequest, LogId, SnapshotReply, SnapshotRequest, VoteReply, VoteRequest,
+    };
+    use tokio::sync::mpsc;
+    use tokio::time::{sleep, timeout};
+    use tokio_stream::wrappers::ReceiverStream;
+    use tonic::{transport::Server, Request, Response, Status, Streaming};
+
+    use super::AppendStreams;
+    use crate::openraft::network::identity::ClusterIdentity;
+    use crate::openraft::store::new_storage;
+
+    /// A frame whose request has this prev_log_id index is never answered.
+    pub(crate) const NO_REPLY: u64 = 1000;
+    /// A frame whose request has this prev_log_id index ends the stream.
+    pub(crate) const CLOSE_STREAM: u64 = 2000;
+
+    /// Peer answering every frame with PARTIAL_SUCCESS up to the prev_log_id of its
+    /// request, the last frame of a message first. It records how many frames each
+    /// message carried.
+    struct ScriptedPeer {
+        messages: Arc<Mutex<Vec<usize>>>,
+    }
+
+    #[tonic::async_trait]
+    impl OpenRaftService for ScriptedPeer {
+        async fn vote(&self, _: Request<VoteRequest>) -> Result<Response<VoteReply>, Status> {
+            return Err(Status::unimplemented("vote"));
+        }
+
+        async fn append(&self, _: Request<AppendRequest>) -> Result<Response<AppendReply>, Status> {
+            return Err(Status::unimplemented("append"));
+        }
+
+        async fn snapshot(
+            &self,
+            _: Request<SnapshotRequest>,
+        ) -> Result<Response<SnapshotReply>, Status> {
+            return Err(Status::unimplemented("snapshot"));
+        }
+
+        type AppendStreamStream = ReceiverStream<Result<AppendStreamReply, Status>>;
+
+        async fn append_stream(
+            &self,
+            request: Request<Streaming<AppendStreamRequest>>,
+        ) -> Result<Response<Self::AppendStreamStream>, Status> {
+            let mut inbound = request.into_inner();
+            let (reply_sx, reply_rx) = mpsc::channel(1024);
+            let messages = self.messages.clone();
+            tokio::spawn(async move {
+                while let Ok(Some(req)) = inbound.message().await {
+                    messages.lock().unwrap().push(req.frames.len());
+                    let mut reply = AppendStreamReply::default();
+                    for frame in req.frames.into_iter().rev() {
+                        let matching = frame.request.and_then(|request| request.prev_log_id);
+                        match matching.as_ref().map(|id| id.index) {
+                            Some(NO_REPLY) => continue,
+                            Some(CLOSE_STREAM) => return,
+                            _ => {}
+                        }
+                        reply.frames.push(AppendReplyFrame {
+                            id: frame.id,
+                            reply: Some(AppendReply {
+                                result: AppendResult::PartialSuccess as i32,
+                                matching,
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        });
+                    }
+                    if reply_sx.send(Ok(reply)).await.is_err() {
+                        return;
+                    }
+                }
+            });
+            return Ok(Response::new(ReceiverStream::new(reply_rx)));
+        }
+    }
+
+    /// Start a scripted peer, returns its address and the frame count of every message
+    /// it received.
+    pub(crate) async fn start_peer() -> (String, Arc<Mutex<Vec<usize>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let peer = ScriptedPeer {
+            messages: messages.clone(),
+        };
+        tokio::spawn(
+            Server::builder()
+                .add_service(OpenRaftServiceServer::new(peer))
+                .serve(addr),
+        );
+        for _ in 0..100 {
+            if tokio::net::TcpStream::connect(addr).await.is_ok() {
+                break;
+            }
+            sleep(Duration::from_millis(20)).await;
+        }
+        return (addr.to_string(), messages);
+    }
+
+    pub(crate) async fn test_identity() -> Arc<ClusterIdentity> {
+        let path = format!(
+            "{}/../../config/placement-center.toml",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        init_placement_center_conf_by_path(&path);
+        let data_path = format!("/tmp/tmp_test_append_stream/{}", unique_id());
+        let (log_store, _) = new_storage(&data_path, &RocksDB::default()).await;
+        return Arc::new(
+            ClusterIdentity::load(log_store.db.clone(), placement_center_conf()).unwrap(),
+        );
+    }
+
+    fn request(index: u64) -> AppendRequest {
+        return AppendRequest {
+            prev_log_id: Some(LogId {
+                term: 1,
+                node_id: 1,
+                index,
+            }),
+            ..Default::default()
+        };
+    }
+
+    #[tokio::test]
+    async fn replies_are_matched_by_frame_id() {
+        let (addr, messages) = start_peer().await;
+        let streams = AppendStreams::new(test_identity().await);
+        let stream = streams.get(&addr, 2).await.unwrap().unwrap();
+
+        // Requests queued together go out in one message and are answered in reverse.
+        let mut handles = Vec::new();
+        for index in 1..=32 {
+            let stream = stream.clone();
+            handles.push(tokio::spawn(
+                async move { stream.append(request(index)).await },
+            ));
+        }
+        for (index, handle) in (1..=32).zip(handles) {
+            let reply = handle.await.unwrap().unwrap();
+            assert_eq!(reply.matching.unwrap().index, index);
+        }
+        let messages = messages.lock().unwrap().clone();
+        assert_eq!(messages.iter().sum::<usize>(), 32);
+        assert!(messages.len() < 32);
+        assert!(stream.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn timed_out_request_releases_its_slot() {
+        let (addr, _) = start_peer().await;
+        let streams = AppendStreams::new(test_identity().await);
+        let stream = streams.get(&addr, 2).await.unwrap().unwrap();
+
+        let result = timeout(Duration::from_millis(200), stream.append(request(NO_REPLY))).await;
+        assert!(result.is_err());
+        assert!(stream.pending.is_empty());
+
+        // The stream itself stays usable.
+        let reply = stream.append(request(1)).await.unwrap();
+        assert_eq!(reply.matching.unwrap().index, 1);
+    }
+
+    #[tokio::test]
+    async fn broken_stream_fails_pending_and_reconnects() {
+        let (addr, _) = start_peer().await;
+        let streams = AppendStreams::new(test_identity().await);
+        let stream = streams.get(&addr, 2).await.unwrap().unwrap();
+
+        let waiting = stream.clone();
+        let waiting = tokio::spawn(async move { waiting.append(request(NO_REPLY)).await });
+        sleep(Duration::from_millis(100)).await;
+        assert!(stream.append(request(CLOSE_STREAM)).await.is_err());
+        assert!(waiting.await.unwrap().is_err());
+        assert!(stream.is_closed());
+        assert!(stream.pending.is_empty());
+        assert!(stream.append(request(1)).await.is_err());
+
+        // A closed stream is replaced on the next lookup.
+        let reconnected = streams.get(&addr, 2).await.unwrap().unwrap();
+        assert!(!Arc::ptr_eq(&stream, &reconnected));
+        let reply = reconnected.append(request(1)).await.unwrap();
+        assert_eq!(reply.matching.unwrap().index, 1);
+    }
+}
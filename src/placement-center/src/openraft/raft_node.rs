@@ -1,12 +1,16 @@
 use crate::raft::node;
 
+use super::learner::join_learners;
+use super::network::identity::ClusterIdentity;
 use super::network::network::Network;
+use super::route::{kvs::StateKvs, StateWatchers};
 use super::store::new_storage;
 use super::typeconfig::TypeConfig;
 use clients::poll::ClientPool;
 use common_base::config::placement_center::placement_center_conf;
 use log::{error, info};
 use openraft::{Config, Raft};
+use rocksdb::DB;
 use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::path::Path;
@@ -52,23 +56,32 @@ pub mod typ {
 
 pub type ExampleRaft = openraft::Raft<TypeConfig>;
 
-pub async fn start_openraft_node(raft_node: Raft<TypeConfig>) {
+pub async fn start_openraft_node(raft_node: Raft<TypeConfig>, identity: Arc<ClusterIdentity>) {
     let conf = placement_center_conf();
+    let configured_nodes = match conf.configured_nodes() {
+        Ok(nodes) => nodes,
+        Err(e) => {
+            panic!("{}", e);
+        }
+    };
+    // Learners are added by the leader once the cluster is up, see `join_learners`.
     let mut nodes = BTreeMap::new();
-    for (node_id, addr) in conf.nodes.clone() {
-        let mut addr = addr.to_string();
-        addr = addr.replace("\"", "");
+    for configured in configured_nodes.iter().filter(|node| !node.learner) {
         let node = Node {
-            rpc_addr: addr,
-            node_id: node_id.parse().unwrap(),
+            rpc_addr: configured.addr.clone(),
+            node_id: configured.node_id,
         };
-
         nodes.insert(node.node_id, node);
     }
 
     info!("Raft Nodes:{:?}", nodes);
     let init_node_id = calc_init_node(&nodes);
     if init_node_id == conf.node_id {
+        // The initializing node assigns the cluster id, the other nodes learn it through
+        // Raft RPCs. Clusters initialized before ids existed get one here as well.
+        if let Err(e) = identity.init(&conf.cluster_name) {
+            panic!("Failed to assign the cluster id, {}", e.to_string());
+        }
         match raft_node.is_initialized().await {
             Ok(flag) => {
                 info!("Whether nodes should be initialized, flag={}", flag);
@@ -89,6 +102,18 @@ pub async fn start_openraft_node(raft_node: Raft<TypeConfig>) {
         }
     }
 
+    let learners: Vec<Node> = configured_nodes
+        .iter()
+        .filter(|node| node.learner)
+        .map(|node| Node {
+            rpc_addr: node.addr.clone(),
+            node_id: node.node_id,
+        })
+        .collect();
+    if !learners.is_empty() {
+        tokio::spawn(join_learners(raft_node, learners));
+    }
+
     // if init_node_id == conf.node_id {
     //     if let Some(local) = nodes.get(&conf.node_id) {
     //         info!("Start trying to initialize node:{}", local.node_id);
@@ -148,22 +173,42 @@ pub fn calc_init_node(nodes: &BTreeMap<u64, Node>) -> u64 {
 
 pub async fn create_raft_node(
     client_poll: Arc<ClientPool>,
-) -> (Raft<TypeConfig>, Arc<RwLock<BTreeMap<String, String>>>) {
-    let config = Config {
-        heartbeat_interval: 250,
-        election_timeout_min: 299,
-        ..Default::default()
-    };
-
-    let config = Arc::new(config.validate().unwrap());
+) -> (
+    Raft<TypeConfig>,
+    Arc<RwLock<StateKvs>>,
+    Arc<DB>,
+    Arc<ClusterIdentity>,
+    StateWatchers,
+) {
     let conf = placement_center_conf();
     let path = format!("{}/_engine_storage", conf.data_path.clone());
     let dir = Path::new(&path);
-    let (log_store, state_machine_store) = new_storage(&dir).await;
+    let (log_store, state_machine_store) = new_storage(&dir, &conf.rocksdb).await;
     let kvs = state_machine_store.data.kvs.clone();
+    let watchers = state_machine_store.data.watchers.clone();
+    let engine_db = log_store.db.clone();
+
+    let config = Config {
+        cluster_name: conf.cluster_name.clone(),
+        heartbeat_interval: conf.raft.heartbeat_interval_ms,
+        election_timeout_min: conf.raft.election_timeout_min_ms,
+        election_timeout_max: conf.raft.election_timeout_max_ms,
+        // A node that lost committed entries in a log recovery must not lead.
+        enable_elect: watchers.recovery.pending().is_none(),
+        ..Default::default()
+    };
+    let config = Arc::new(config.validate().unwrap());
+
+    // Refuse to start on a data directory of another cluster.
+    let identity = match ClusterIdentity::load(engine_db.clone(), conf) {
+        Ok(identity) => Arc::new(identity),
+        Err(e) => {
+            panic!("{}", e.to_string());
+        }
+    };
 
-    let network = Network::new(client_poll);
-    let raft = openraft::Raft::new(
+    let network = Network::new(client_poll, identity.clone());
+    let raft = match openraft::Raft::new(
         conf.node_id,
         config.clone(),
         network,
@@ -171,7 +216,269 @@ pub async fn create_raft_node(
         state_machine_store,
     )
     .await
-    .unwrap();
+    {
+        Ok(raft) => raft,
+        Err(e) => {
+            panic!(
+                "Failed to open the Raft log in {}, error message: {}. If a log entry is corrupted, stop the node and run `placement-center-admin recover-logs` with the same configuration to truncate the log back to its last valid entry",
+                path, e
+            );
+        }
+    };
+
+    if let Some(index) = watchers.recovery.pending() {
+        error!(
+            "The log was recovered below the commit index {}, elections stay disabled until it is replicated again",
+            index
+        );
+        let recovery = watchers.recovery.clone();
+        let tmp_raft = raft.clone();
+        tokio::spawn(async move {
+            recovery.recovered().await;
+            tmp_raft.runtime_config().elect(true);
+        });
+    }
+
+    return (raft, kvs, engine_db, identity, watchers);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashSet};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use common_base::config::placement_center::RocksDB;
+    use common_base::errors::RobustMQError;
+    use common_base::tools::unique_id;
+    use openraft::error::{InstallSnapshotError, RPCError, RaftError};
+    use openraft::network::RPCOption;
+    use openraft::raft::{
+        AppendEntriesRequest, AppendEntriesResponse, InstallSnapshotRequest,
+        InstallSnapshotResponse, VoteRequest, VoteResponse,
+    };
+    use openraft::{Config, Raft, RaftNetwork, RaftNetworkFactory};
+    use tokio::sync::RwLock;
+    use tokio::time::sleep;
+
+    use super::{Node, NodeId};
+    use crate::openraft::error::to_error;
+    use crate::openraft::route::{kvs::StateKvs, AppRequestData};
+    use crate::openraft::store::new_storage;
+    use crate::openraft::typeconfig::TypeConfig;
+
+    /// Delivers RPCs by calling the target node in process. Requests from or to an
+    /// isolated node fail as unreachable.
+    #[derive(Clone)]
+    struct Router {
+        source: NodeId,
+        nodes: Arc<Mutex<BTreeMap<NodeId, Raft<TypeConfig>>>>,
+        isolated: Arc<Mutex<HashSet<NodeId>>>,
+    }
+
+    impl Router {
+        fn route(&self, target: NodeId) -> Result<Raft<TypeConfig>, RobustMQError> {
+            let isolated = self.isolated.lock().unwrap();
+            if isolated.contains(&self.source) || isolated.contains(&target) {
+                return Err(RobustMQError::CommmonError(format!(
+                    "node {} is partitioned from node {}",
+                    self.source, target
+                )));
+            }
+            return match self.nodes.lock().unwrap().get(&target) {
+                Some(raft) => Ok(raft.clone()),
+                None => Err(RobustMQError::CommmonError(format!(
+                    "node {} is not started",
+                    target
+                ))),
+            };
+        }
+    }
+
+    struct RouterConnection {
+        router: Router,
+        target: NodeId,
+    }
+
+    impl RaftNetworkFactory<TypeConfig> for Router {
+        type Network = RouterConnection;
+
+        async fn new_client(&mut self, target: NodeId, _node: &Node) -> Self::Network {
+            return RouterConnection {
+                router: self.clone(),
+                target,
+            };
+        }
+    }
+
+    fn remote_error<E: std::error::Error + 'static + Clone, T: std::fmt::Display>(
+        e: T,
+    ) -> RPCError<TypeConfig, E> {
+        return to_error(RobustMQError::CommmonError(e.to_string()));
+    }
+
+    impl RaftNetwork<TypeConfig> for RouterConnection {
+        async fn append_entries(
+            &mut self,
+            req: AppendEntriesRequest<TypeConfig>,
+            _option: RPCOption,
+        ) -> Result<AppendEntriesResponse<TypeConfig>, RPCError<TypeConfig, RaftError<TypeConfig>>>
+        {
+            let raft = match self.router.route(self.target) {
+                Ok(raft) => raft,
+                Err(e) => return Err(to_error(e)),
+            };
+            return raft.append_entries(req).await.map_err(remote_error);
+        }
+
+        async fn install_snapshot(
+            &mut self,
+            req: InstallSnapshotRequest<TypeConfig>,
+            _option: RPCOption,
+        ) -> Result<
+            InstallSnapshotResponse<TypeConfig>,
+            RPCError<TypeConfig, RaftError<TypeConfig, InstallSnapshotError>>,
+        > {
+            let raft = match self.router.route(self.target) {
+                Ok(raft) => raft,
+                Err(e) => return Err(to_error(e)),
+            };
+            return raft.install_snapshot(req).await.map_err(remote_error);
+        }
+
+        async fn vote(
+            &mut self,
+            req: VoteRequest<TypeConfig>,
+            _option: RPCOption,
+        ) -> Result<VoteResponse<TypeConfig>, RPCError<TypeConfig, RaftError<TypeConfig>>> {
+            let raft = match self.router.route(self.target) {
+                Ok(raft) => raft,
+                Err(e) => return Err(to_error(e)),
+            };
+            return raft.vote(req).await.map_err(remote_error);
+        }
+    }
 
-    return (raft, kvs);
+    struct TestCluster {
+        rafts: BTreeMap<NodeId, Raft<TypeConfig>>,
+        kvs: BTreeMap<NodeId, Arc<RwLock<StateKvs>>>,
+        isolated: Arc<Mutex<HashSet<NodeId>>>,
+    }
+
+    impl TestCluster {
+        async fn start(size: u64) -> Self {
+            let nodes = Arc::new(Mutex::new(BTreeMap::new()));
+            let isolated = Arc::new(Mutex::new(HashSet::new()));
+            let config = Config {
+                heartbeat_interval: 50,
+                election_timeout_min: 200,
+                election_timeout_max: 400,
+                ..Default::default()
+            };
+            let config = Arc::new(config.validate().unwrap());
+            let path = format!("/tmp/tmp_test_openraft_partition/{}", unique_id());
+
+            let mut members = BTreeMap::new();
+            let mut rafts = BTreeMap::new();
+            let mut kvs = BTreeMap::new();
+            for node_id in 1..=size {
+                let (log_store, state_machine_store) =
+                    new_storage(format!("{}/{}", path, node_id), &RocksDB::default()).await;
+                kvs.insert(node_id, state_machine_store.data.kvs.clone());
+                let router = Router {
+                    source: node_id,
+                    nodes: nodes.clone(),
+                    isolated: isolated.clone(),
+                };
+                let raft = Raft::new(
+                    node_id,
+                    config.clone(),
+                    router,
+                    log_store,
+                    state_machine_store,
+                )
+                .await
+                .unwrap();
+                nodes.lock().unwrap().insert(node_id, raft.clone());
+                rafts.insert(node_id, raft);
+                members.insert(
+                    node_id,
+                    Node {
+                        node_id,
+                        rpc_addr: format!("127.0.0.1:{}", 1228 + node_id),
+                    },
+                );
+            }
+            rafts[&1].initialize(members).await.unwrap();
+            return TestCluster {
+                rafts,
+                kvs,
+                isolated,
+            };
+        }
+
+        /// Waits until the given nodes report the same leader in the same term.
+        async fn stable_leader(&self, ids: &[NodeId]) -> (NodeId, u64) {
+            for _ in 0..200 {
+                let metrics: Vec<_> = ids
+                    .iter()
+                    .map(|id| self.rafts[id].metrics().borrow().clone())
+                    .collect();
+                if let Some(leader) = metrics[0].current_leader {
+                    let term = metrics[0].current_term;
+                    if ids.contains(&leader)
+                        && metrics
+                            .iter()
+                            .all(|m| m.current_leader == Some(leader) && m.current_term == term)
+                    {
+                        return (leader, term);
+                    }
+                }
+                sleep(Duration::from_millis(50)).await;
+            }
+            panic!("nodes {:?} did not agree on a leader", ids);
+        }
+
+        async fn write(&self, leader: NodeId, key: &str) -> u64 {
+            let req = AppRequestData::Set {
+                key: key.to_string(),
+                value: "v".to_string(),
+            };
+            let resp = self.rafts[&leader].client_write(req).await.unwrap();
+            return resp.log_id.index;
+        }
+    }
+
+    #[tokio::test]
+    async fn partitioned_node_rejoins_cluster() {
+        let cluster = TestCluster::start(3).await;
+        let (leader, _) = cluster.stable_leader(&[1, 2, 3]).await;
+        let isolated = (1..=3).find(|id| *id != leader).unwrap();
+        let majority: Vec<NodeId> = (1..=3).filter(|id| *id != isolated).collect();
+
+        // Cut a follower off for many election timeouts. The other two keep a leader
+        // and commit without it, the isolated node never wins an election.
+        cluster.isolated.lock().unwrap().insert(isolated);
+        sleep(Duration::from_secs(3)).await;
+        let (leader, _) = cluster.stable_leader(&majority).await;
+        let during = cluster.write(leader, "during").await;
+        let metrics = cluster.rafts[&isolated].metrics().borrow().clone();
+        assert_ne!(metrics.current_leader, Some(isolated));
+        assert!(metrics.last_applied.map(|id| id.index).unwrap_or(0) < during);
+
+        // Once it rejoins every node follows one leader in one term, and the rejoined
+        // node catches up on the entries committed while it was away.
+        cluster.isolated.lock().unwrap().clear();
+        let (leader, _) = cluster.stable_leader(&[1, 2, 3]).await;
+        assert_ne!(leader, isolated);
+        let after = cluster.write(leader, "after").await;
+        cluster.rafts[&isolated]
+            .wait(Some(Duration::from_secs(10)))
+            .applied_index_at_least(Some(after), "rejoined node applied the writes")
+            .await
+            .unwrap();
+        let kvs = cluster.kvs[&isolated].read().await;
+        assert_eq!(kvs.get("during"), Some(&"v".to_string()));
+        assert_eq!(kvs.get("after"), Some(&"v".to_string()));
+    }
 }
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use log::error;
+use tokio::sync::broadcast;
+
+use crate::{
+    raft::node::{BrokerNode, NodeState},
+    storage::keys::{key_node, key_node_prefix, key_node_prefix_all},
+};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Broker node records of the cluster service. They are part of the replicated key space,
+// stored as JSON under `/clusters/node/{cluster_type}/{cluster_name}/{node_id}`.
+
+const NODE_EVENT_CHANNEL_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeEventType {
+    Registered,
+    StateChanged,
+    Removed,
+}
+
+#[derive(Debug, Clone)]
+pub struct NodeEvent {
+    pub event_type: NodeEventType,
+    pub node: BrokerNode,
+}
+
+/// Broadcasts the node changes applied by the state machine. Every node of the placement
+/// center applies them, so watchers may subscribe on any node. Changes that arrive
+/// through a snapshot are not emitted, watchers that lag behind have to list the nodes
+/// again.
+#[derive(Debug, Clone)]
+pub struct NodeWatcher {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl NodeWatcher {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(NODE_EVENT_CHANNEL_SIZE);
+        return NodeWatcher { sender };
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        return self.sender.subscribe();
+    }
+
+    fn emit(&self, event_type: NodeEventType, node: BrokerNode) {
+        // Nobody watching is not an error.
+        let _ = self.sender.send(NodeEvent { event_type, node });
+    }
+}
+
+pub fn register_node(
+    kvs: &mut StateKvs,
+    watcher: &NodeWatcher,
+    mut node: BrokerNode,
+) -> AppResponseData {
+    let key = key_node(&node.cluster_type, &node.cluster_name, node.node_id);
+    // A node registering again, e.g. after a restart, keeps its original create time.
+    if let Some(existing) = get_node(kvs, &key) {
+        node.create_time = existing.create_time;
+    }
+    if save_node(kvs, key, &node) {
+        watcher.emit(NodeEventType::Registered, node);
+    }
+    return AppResponseData::default();
+}
+
+pub fn unregister_node(
+    kvs: &mut StateKvs,
+    watcher: &NodeWatcher,
+    cluster_type: &String,
+    cluster_name: &String,
+    node_id: u64,
+) -> AppResponseData {
+    let key = key_node(cluster_type, cluster_name, node_id);
+    let node = get_node(kvs, &key);
+    let removed = kvs.remove(&key);
+    if let Some(mut node) = node {
+        node.state = NodeState::Stop;
+        watcher.emit(NodeEventType::Removed, node);
+    }
+    return AppResponseData {
+        value: removed,
+        ..Default::default()
+    };
+}
+
+/// Move a node to `state`. A node that stops is removed. The response carries a value
+/// only when the node exists.
+pub fn update_node_state(
+    kvs: &mut StateKvs,
+    watcher: &NodeWatcher,
+    cluster_type: &String,
+    cluster_name: &String,
+    node_id: u64,
+    state: NodeState,
+) -> AppResponseData {
+    let key = key_node(cluster_type, cluster_name, node_id);
+    let mut node = match get_node(kvs, &key) {
+        Some(node) => node,
+        None => return AppResponseData::default(),
+    };
+    if node.state != state {
+        node.state = state;
+        if state == NodeState::Stop {
+            kvs.remove(&key);
+            watcher.emit(NodeEventType::StateChanged, node.clone());
+            watcher.emit(NodeEventType::Removed, node.clone());
+        } else if save_node(kvs, key, &node) {
+            watcher.emit(NodeEventType::StateChanged, node.clone());
+        }
+    }
+    return AppResponseData {
+        value: Some(format!("{:?}", node.state)),
+        ..Default::default()
+    };
+}
+
+pub fn get_node(kvs: &BTreeMap<String, String>, key: &String) -> Option<BrokerNode> {
+    return kvs
+        .get(key)
+        .and_then(|value| serde_json::from_str::<BrokerNode>(value).ok());
+}
+
+pub fn list_all_nodes(kvs: &BTreeMap<String, String>) -> Vec<BrokerNode> {
+    let prefix = key_node_prefix_all();
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<BrokerNode>(value).ok())
+        .collect();
+}
+
+pub fn list_nodes(
+    kvs: &BTreeMap<String, String>,
+    cluster_type: &String,
+    cluster_name: &String,
+) -> Vec<BrokerNode> {
+    let prefix = key_node_prefix(cluster_type, cluster_name);
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<BrokerNode>(value).ok())
+        .collect();
+}
+
+fn save_node(kvs: &mut StateKvs, key: String, node: &BrokerNode) -> bool {
+    match serde_json::to_string(node) {
+        Ok(value) => {
+            kvs.insert(key, value);
+            return true;
+        }
+        Err(e) => {
+            error!("Failed to encode broker node {}: {}", node.node_id, e);
+            return false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{
+        list_all_nodes, list_nodes, register_node, unregister_node, update_node_state,
+        NodeEventType, NodeWatcher,
+    };
+    use crate::raft::node::{BrokerNode, NodeState};
+
+    fn node(cluster_name: &str, node_id: u64, create_time: u128) -> BrokerNode {
+        return BrokerNode {
+            cluster_type: "MQTTBrokerServer".to_string(),
+            cluster_name: cluster_name.to_string(),
+            node_id,
+            node_ip: "127.0.0.1".to_string(),
+            node_inner_addr: format!("127.0.0.1:{}", 9000 + node_id),
+            extend: String::new(),
+            create_time,
+            state: NodeState::Starting,
+        };
+    }
+
+    #[test]
+    fn register_list_unregister() {
+        let watcher = NodeWatcher::new();
+        let mut kvs = StateKvs::default();
+        let cluster_type = "MQTTBrokerServer".to_string();
+        let cluster_name = "mqtt".to_string();
+
+        register_node(&mut kvs, &watcher, node("mqtt", 1, 100));
+        register_node(&mut kvs, &watcher, node("mqtt", 2, 100));
+        // Same prefix, another cluster.
+        register_node(&mut kvs, &watcher, node("mqtt2", 1, 100));
+
+        let nodes = list_nodes(&kvs, &cluster_type, &cluster_name);
+        assert_eq!(nodes.len(), 2);
+
+        let mut updated = node("mqtt", 1, 200);
+        updated.extend = "{\"zone\":\"a\"}".to_string();
+        register_node(&mut kvs, &watcher, updated);
+        let nodes = list_nodes(&kvs, &cluster_type, &cluster_name);
+        assert_eq!(nodes[0].create_time, 100);
+        assert_eq!(nodes[0].extend, "{\"zone\":\"a\"}");
+
+        assert!(
+            unregister_node(&mut kvs, &watcher, &cluster_type, &cluster_name, 1)
+                .value
+                .is_some()
+        );
+        assert!(
+            unregister_node(&mut kvs, &watcher, &cluster_type, &cluster_name, 1)
+                .value
+                .is_none()
+        );
+        assert_eq!(list_nodes(&kvs, &cluster_type, &cluster_name).len(), 1);
+        assert_eq!(
+            list_nodes(&kvs, &cluster_type, &"mqtt2".to_string()).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn node_lifecycle_events() {
+        let watcher = NodeWatcher::new();
+        let mut events = watcher.subscribe();
+        let mut kvs = StateKvs::default();
+        let cluster_type = "MQTTBrokerServer".to_string();
+        let cluster_name = "mqtt".to_string();
+
+        register_node(&mut kvs, &watcher, node("mqtt", 1, 100));
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.event_type, NodeEventType::Registered);
+        assert_eq!(event.node.state, NodeState::Starting);
+
+        let resp = update_node_state(
+            &mut kvs,
+            &watcher,
+            &cluster_type,
+            &cluster_name,
+            1,
+            NodeState::Running,
+        );
+        assert!(resp.value.is_some());
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.event_type, NodeEventType::StateChanged);
+        assert_eq!(list_all_nodes(&kvs)[0].state, NodeState::Running);
+
+        // Unchanged state, no event.
+        update_node_state(
+            &mut kvs,
+            &watcher,
+            &cluster_type,
+            &cluster_name,
+            1,
+            NodeState::Running,
+        );
+        assert!(events.try_recv().is_err());
+
+        update_node_state(
+            &mut kvs,
+            &watcher,
+            &cluster_type,
+            &cluster_name,
+            1,
+            NodeState::Stop,
+        );
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.event_type, NodeEventType::StateChanged);
+        assert_eq!(event.node.state, NodeState::Stop);
+        assert_eq!(
+            events.try_recv().unwrap().event_type,
+            NodeEventType::Removed
+        );
+        assert!(list_all_nodes(&kvs).is_empty());
+
+        let resp = update_node_state(
+            &mut kvs,
+            &watcher,
+            &cluster_type,
+            &cluster_name,
+            1,
+            NodeState::Running,
+        );
+        assert!(resp.value.is_none());
+    }
+}
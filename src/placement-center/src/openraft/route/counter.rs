@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Integer-valued keys of the kv service, changed in place by the state machine so that
+// concurrent increments cannot overwrite each other. The value is stored as a decimal
+// string, a missing key counts as 0.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Counter {
+    /// The new value, or the current one when the increment was refused.
+    pub value: i64,
+    pub applied: bool,
+}
+
+/// Add `delta` to the key, unless the new value would overflow or fall outside
+/// `floor..=ceiling`. The response carries the counter, or no value when the key does not
+/// hold an integer.
+pub fn incr(
+    kvs: &mut StateKvs,
+    key: String,
+    delta: i64,
+    floor: Option<i64>,
+    ceiling: Option<i64>,
+) -> AppResponseData {
+    let current = match kvs.get(&key) {
+        Some(value) => match value.parse::<i64>() {
+            Ok(current) => current,
+            Err(_) => return AppResponseData::default(),
+        },
+        None => 0,
+    };
+
+    let next = current
+        .checked_add(delta)
+        .filter(|next| floor.map_or(true, |floor| *next >= floor))
+        .filter(|next| ceiling.map_or(true, |ceiling| *next <= ceiling));
+    let counter = match next {
+        Some(next) => {
+            kvs.insert(key, next.to_string());
+            Counter {
+                value: next,
+                applied: true,
+            }
+        }
+        None => Counter {
+            value: current,
+            applied: false,
+        },
+    };
+    return AppResponseData {
+        value: serde_json::to_string(&counter).ok(),
+        ..Default::default()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{incr, Counter};
+
+    fn decode(value: Option<String>) -> Counter {
+        return serde_json::from_str(&value.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn increments_stay_within_bounds() {
+        let mut kvs = StateKvs::default();
+        let key = "/quota/connections/broker-1".to_string();
+
+        let counter = decode(incr(&mut kvs, key.clone(), 2, None, Some(3)).value);
+        assert_eq!(
+            counter,
+            Counter {
+                value: 2,
+                applied: true
+            }
+        );
+        let counter = decode(incr(&mut kvs, key.clone(), 1, None, Some(3)).value);
+        assert_eq!(counter.value, 3);
+
+        // Past the ceiling or the floor nothing changes.
+        let counter = decode(incr(&mut kvs, key.clone(), 1, None, Some(3)).value);
+        assert_eq!(
+            counter,
+            Counter {
+                value: 3,
+                applied: false
+            }
+        );
+        let counter = decode(incr(&mut kvs, key.clone(), -4, Some(0), None).value);
+        assert_eq!(
+            counter,
+            Counter {
+                value: 3,
+                applied: false
+            }
+        );
+        assert_eq!(kvs.get(&key).unwrap(), "3");
+
+        let counter = decode(incr(&mut kvs, key.clone(), -3, Some(0), None).value);
+        assert_eq!(
+            counter,
+            Counter {
+                value: 0,
+                applied: true
+            }
+        );
+
+        // Overflow is refused, a value that is not an integer is an error.
+        kvs.insert(key.clone(), i64::MAX.to_string());
+        assert!(!decode(incr(&mut kvs, key.clone(), 1, None, None).value).applied);
+        kvs.insert(key.clone(), "many".to_string());
+        assert!(incr(&mut kvs, key, 1, None, None).value.is_none());
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::storage::keys::{key_election, key_election_prefix};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Leader elections for the brokers, stored as JSON under `/election/{name}`. Only the
+// leadership itself is replicated. Leases are timed by the leader of the placement
+// center, which expires a leadership through the log once its lease runs out.
+
+const ELECTION_EVENT_CHANNEL_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ElectionLeader {
+    pub candidate: String,
+    /// Fencing token of this leadership.
+    pub epoch: u64,
+    pub lease_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Election {
+    pub name: String,
+    pub leader: Option<ElectionLeader>,
+    /// Highest epoch handed out so far. It survives resignations, so epochs never repeat.
+    pub epoch: u64,
+}
+
+/// Broadcasts every change of leadership applied by the state machine, on every node of
+/// the placement center.
+#[derive(Debug, Clone)]
+pub struct ElectionWatcher {
+    sender: broadcast::Sender<Election>,
+}
+
+impl ElectionWatcher {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(ELECTION_EVENT_CHANNEL_SIZE);
+        return ElectionWatcher { sender };
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Election> {
+        return self.sender.subscribe();
+    }
+
+    fn emit(&self, election: Election) {
+        let _ = self.sender.send(election);
+    }
+}
+
+/// The candidate becomes the leader when the election has none, with the next epoch. The
+/// current leader campaigning again keeps its epoch. The response carries the election.
+pub fn campaign(
+    kvs: &mut StateKvs,
+    watcher: &ElectionWatcher,
+    name: &String,
+    candidate: &String,
+    lease_ms: u64,
+) -> AppResponseData {
+    let key = key_election(name);
+    let mut election = get_election(kvs, &key).unwrap_or(Election {
+        name: name.clone(),
+        ..Default::default()
+    });
+
+    let changed = match &mut election.leader {
+        None => {
+            election.epoch += 1;
+            election.leader = Some(ElectionLeader {
+                candidate: candidate.clone(),
+                epoch: election.epoch,
+                lease_ms,
+            });
+            true
+        }
+        Some(leader) if leader.candidate == *candidate && leader.lease_ms != lease_ms => {
+            leader.lease_ms = lease_ms;
+            false
+        }
+        Some(_) => {
+            return AppResponseData {
+                value: serde_json::to_string(&election).ok(),
+                ..Default::default()
+            };
+        }
+    };
+
+    let value = save_election(kvs, key, &election);
+    if changed && value.is_some() {
+        watcher.emit(election);
+    }
+    return AppResponseData {
+        value,
+        ..Default::default()
+    };
+}
+
+/// Clear the leadership of `epoch`, when `candidate` still holds it. A None candidate
+/// matches any holder, which is how a lease expires. The response carries a value only
+/// when the leadership was cleared.
+pub fn release(
+    kvs: &mut StateKvs,
+    watcher: &ElectionWatcher,
+    name: &String,
+    candidate: Option<&String>,
+    epoch: u64,
+) -> AppResponseData {
+    let key = key_election(name);
+    let mut election = match get_election(kvs, &key) {
+        Some(election) => election,
+        None => return AppResponseData::default(),
+    };
+    let holds = match &election.leader {
+        Some(leader) => leader.epoch == epoch && candidate.map_or(true, |c| *c == leader.candidate),
+        None => false,
+    };
+    if !holds {
+        return AppResponseData::default();
+    }
+
+    election.leader = None;
+    let value = save_election(kvs, key, &election);
+    if value.is_some() {
+        watcher.emit(election);
+    }
+    return AppResponseData {
+        value,
+        ..Default::default()
+    };
+}
+
+pub fn get_election(kvs: &BTreeMap<String, String>, key: &String) -> Option<Election> {
+    return kvs
+        .get(key)
+        .and_then(|value| serde_json::from_str::<Election>(value).ok());
+}
+
+pub fn list_elections(kvs: &BTreeMap<String, String>) -> Vec<Election> {
+    let prefix = key_election_prefix();
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<Election>(value).ok())
+        .collect();
+}
+
+fn save_election(kvs: &mut StateKvs, key: String, election: &Election) -> Option<String> {
+    match serde_json::to_string(election) {
+        Ok(value) => {
+            kvs.insert(key, value.clone());
+            return Some(value);
+        }
+        Err(e) => {
+            error!("Failed to encode election {}: {}", election.name, e);
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{campaign, list_elections, release, Election, ElectionWatcher};
+
+    fn decode(value: Option<String>) -> Election {
+        return serde_json::from_str(&value.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn epochs_fence_leaderships() {
+        let watcher = ElectionWatcher::new();
+        let mut events = watcher.subscribe();
+        let mut kvs = StateKvs::default();
+        let name = "shard-1".to_string();
+        let a = "broker-a".to_string();
+        let b = "broker-b".to_string();
+
+        let election = decode(campaign(&mut kvs, &watcher, &name, &a, 3000).value);
+        assert_eq!(election.leader.as_ref().unwrap().candidate, a);
+        assert_eq!(election.epoch, 1);
+        assert_eq!(events.try_recv().unwrap().epoch, 1);
+
+        // Another candidate loses, the leader renewing keeps its epoch.
+        let election = decode(campaign(&mut kvs, &watcher, &name, &b, 3000).value);
+        assert_eq!(election.leader.as_ref().unwrap().candidate, a);
+        let election = decode(campaign(&mut kvs, &watcher, &name, &a, 5000).value);
+        assert_eq!(election.leader.as_ref().unwrap().epoch, 1);
+        assert_eq!(election.leader.as_ref().unwrap().lease_ms, 5000);
+        assert!(events.try_recv().is_err());
+
+        // A stale epoch or another candidate cannot resign the leadership.
+        assert!(release(&mut kvs, &watcher, &name, Some(&a), 0)
+            .value
+            .is_none());
+        assert!(release(&mut kvs, &watcher, &name, Some(&b), 1)
+            .value
+            .is_none());
+        assert!(release(&mut kvs, &watcher, &name, Some(&a), 1)
+            .value
+            .is_some());
+        assert!(events.try_recv().unwrap().leader.is_none());
+
+        let election = decode(campaign(&mut kvs, &watcher, &name, &b, 3000).value);
+        assert_eq!(election.leader.as_ref().unwrap().epoch, 2);
+
+        // Expiring the lease does not need the candidate.
+        assert!(release(&mut kvs, &watcher, &name, None, 2).value.is_some());
+        let election = decode(campaign(&mut kvs, &watcher, &name, &a, 3000).value);
+        assert_eq!(election.epoch, 3);
+        assert_eq!(list_elections(&kvs).len(), 1);
+    }
+}
@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, ops::Deref};
+
+/// Keys of the state machine with the log index each one was last written at. Reads go
+/// through the map, writes through [`StateKvs::insert`] and [`StateKvs::remove`] so the
+/// revision of a key follows the entry being applied.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateKvs {
+    kvs: BTreeMap<String, String>,
+    revisions: BTreeMap<String, u64>,
+    /// Index of the entry being applied.
+    revision: u64,
+    /// Revision of the keys whose own revision is not known, the index of the snapshot
+    /// they were installed from.
+    base_revision: u64,
+}
+
+impl Deref for StateKvs {
+    type Target = BTreeMap<String, String>;
+
+    fn deref(&self) -> &Self::Target {
+        return &self.kvs;
+    }
+}
+
+impl StateKvs {
+    /// State restored from a snapshot taken at log index `base_revision`. `revisions` may
+    /// miss keys, which then report `base_revision`.
+    pub fn restore(
+        kvs: BTreeMap<String, String>,
+        revisions: BTreeMap<String, u64>,
+        base_revision: u64,
+    ) -> Self {
+        return StateKvs {
+            kvs,
+            revisions,
+            revision: base_revision,
+            base_revision,
+        };
+    }
+
+    /// Writes from now on belong to the entry at `revision`.
+    pub fn set_revision(&mut self, revision: u64) {
+        self.revision = revision;
+    }
+
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        self.revisions.insert(key.clone(), self.revision);
+        return self.kvs.insert(key, value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.revisions.remove(key);
+        return self.kvs.remove(key);
+    }
+
+    /// Log index the key was last written at. A key restored from a snapshot without its
+    /// own revision reports the index of the snapshot, which is not lower.
+    pub fn revision(&self, key: &str) -> u64 {
+        return self
+            .revisions
+            .get(key)
+            .copied()
+            .unwrap_or(self.base_revision);
+    }
+
+    pub fn kvs(&self) -> &BTreeMap<String, String> {
+        return &self.kvs;
+    }
+
+    pub fn revisions(&self) -> &BTreeMap<String, u64> {
+        return &self.revisions;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::StateKvs;
+
+    #[test]
+    fn revisions_follow_writes() {
+        let mut revisions = BTreeMap::new();
+        revisions.insert("/a".to_string(), 3);
+        let mut kvs = BTreeMap::new();
+        kvs.insert("/a".to_string(), "1".to_string());
+        kvs.insert("/b".to_string(), "2".to_string());
+        let mut state = StateKvs::restore(kvs, revisions, 5);
+        assert_eq!(state.revision("/a"), 3);
+        assert_eq!(state.revision("/b"), 5);
+
+        state.set_revision(6);
+        state.insert("/b".to_string(), "3".to_string());
+        state.insert("/c".to_string(), "4".to_string());
+        assert_eq!(state.get("/b"), Some(&"3".to_string()));
+        assert_eq!(state.revision("/b"), 6);
+        assert_eq!(state.revision("/c"), 6);
+
+        state.set_revision(7);
+        assert_eq!(state.remove("/c"), Some("4".to_string()));
+        assert!(!state.revisions().contains_key("/c"));
+        assert_eq!(state.len(), 2);
+    }
+}
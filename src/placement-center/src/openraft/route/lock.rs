@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::storage::keys::{key_lock, key_lock_prefix};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Named locks, stored as JSON under `/lock/{name}`. A released lock goes to the first
+// waiter in the same log entry, so waiters are served in the order they queued. Like
+// elections, the TTL is timed by the leader of the placement center, which expires a
+// lock through the log.
+
+const LOCK_EVENT_CHANNEL_SIZE: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockHolder {
+    pub owner: String,
+    /// Fencing token of this acquisition.
+    pub token: u64,
+    pub ttl_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LockWaiter {
+    pub owner: String,
+    pub ttl_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DistributedLock {
+    pub name: String,
+    pub holder: Option<LockHolder>,
+    pub waiters: Vec<LockWaiter>,
+    /// Highest token handed out so far. The record is kept once the lock is free, so
+    /// tokens never repeat.
+    pub token: u64,
+}
+
+impl DistributedLock {
+    pub fn held_by(&self, owner: &String) -> Option<&LockHolder> {
+        return self.holder.as_ref().filter(|holder| holder.owner == *owner);
+    }
+
+    /// Hand a free lock to the first waiter.
+    fn grant_next(&mut self) {
+        if self.holder.is_some() || self.waiters.is_empty() {
+            return;
+        }
+        let waiter = self.waiters.remove(0);
+        self.token += 1;
+        self.holder = Some(LockHolder {
+            owner: waiter.owner,
+            token: self.token,
+            ttl_ms: waiter.ttl_ms,
+        });
+    }
+}
+
+/// Broadcasts every change of holder applied by the state machine, so waiters learn
+/// that the lock was handed to them.
+#[derive(Debug, Clone)]
+pub struct LockWatcher {
+    sender: broadcast::Sender<DistributedLock>,
+}
+
+impl LockWatcher {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(LOCK_EVENT_CHANNEL_SIZE);
+        return LockWatcher { sender };
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DistributedLock> {
+        return self.sender.subscribe();
+    }
+
+    fn emit(&self, lock: DistributedLock) {
+        let _ = self.sender.send(lock);
+    }
+}
+
+/// Take the lock when it is free and nobody waits, or queue up for it when `wait` is
+/// set. The holder locking again refreshes its TTL and keeps its token. The response
+/// carries the lock.
+pub fn lock(
+    kvs: &mut StateKvs,
+    watcher: &LockWatcher,
+    name: &String,
+    owner: &String,
+    ttl_ms: u64,
+    wait: bool,
+) -> AppResponseData {
+    let key = key_lock(name);
+    let mut lock = get_lock(kvs, &key).unwrap_or(DistributedLock {
+        name: name.clone(),
+        ..Default::default()
+    });
+
+    let mut changed = false;
+    match &mut lock.holder {
+        Some(holder) if holder.owner == *owner => {
+            holder.ttl_ms = ttl_ms;
+        }
+        Some(_) => {
+            queue(&mut lock, owner, ttl_ms, wait);
+        }
+        None => {
+            queue(&mut lock, owner, ttl_ms, true);
+            // Anyone queued earlier goes first.
+            lock.grant_next();
+            changed = true;
+            if lock.held_by(owner).is_none() && !wait {
+                lock.waiters.retain(|waiter| waiter.owner != *owner);
+            }
+        }
+    }
+    return save(kvs, watcher, key, lock, changed);
+}
+
+/// Release the lock held with `token`. A None owner matches any holder, which is how a
+/// TTL expires. The next waiter gets the lock. The response carries a value only when
+/// the lock was released.
+pub fn unlock(
+    kvs: &mut StateKvs,
+    watcher: &LockWatcher,
+    name: &String,
+    owner: Option<&String>,
+    token: u64,
+) -> AppResponseData {
+    let key = key_lock(name);
+    let mut lock = match get_lock(kvs, &key) {
+        Some(lock) => lock,
+        None => return AppResponseData::default(),
+    };
+    let holds = match &lock.holder {
+        Some(holder) => holder.token == token && owner.map_or(true, |o| *o == holder.owner),
+        None => false,
+    };
+    if !holds {
+        return AppResponseData::default();
+    }
+
+    lock.holder = None;
+    lock.grant_next();
+    return save(kvs, watcher, key, lock, true);
+}
+
+/// Leave the queue of the lock. The response carries the lock, the owner may have been
+/// granted it before it gave up.
+pub fn cancel_wait(
+    kvs: &mut StateKvs,
+    watcher: &LockWatcher,
+    name: &String,
+    owner: &String,
+) -> AppResponseData {
+    let key = key_lock(name);
+    let mut lock = match get_lock(kvs, &key) {
+        Some(lock) => lock,
+        None => return AppResponseData::default(),
+    };
+    lock.waiters.retain(|waiter| waiter.owner != *owner);
+    return save(kvs, watcher, key, lock, false);
+}
+
+pub fn get_lock(kvs: &BTreeMap<String, String>, key: &String) -> Option<DistributedLock> {
+    return kvs
+        .get(key)
+        .and_then(|value| serde_json::from_str::<DistributedLock>(value).ok());
+}
+
+pub fn list_locks(kvs: &BTreeMap<String, String>) -> Vec<DistributedLock> {
+    let prefix = key_lock_prefix();
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<DistributedLock>(value).ok())
+        .collect();
+}
+
+fn queue(lock: &mut DistributedLock, owner: &String, ttl_ms: u64, wait: bool) {
+    if !wait {
+        return;
+    }
+    match lock
+        .waiters
+        .iter_mut()
+        .find(|waiter| waiter.owner == *owner)
+    {
+        Some(waiter) => waiter.ttl_ms = ttl_ms,
+        None => lock.waiters.push(LockWaiter {
+            owner: owner.clone(),
+            ttl_ms,
+        }),
+    }
+}
+
+fn save(
+    kvs: &mut StateKvs,
+    watcher: &LockWatcher,
+    key: String,
+    lock: DistributedLock,
+    changed: bool,
+) -> AppResponseData {
+    let value = match serde_json::to_string(&lock) {
+        Ok(value) => value,
+        Err(e) => {
+            error!("Failed to encode lock {}: {}", lock.name, e);
+            return AppResponseData::default();
+        }
+    };
+    kvs.insert(key, value.clone());
+    if changed {
+        watcher.emit(lock);
+    }
+    return AppResponseData {
+        value: Some(value),
+        ..Default::default()
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{cancel_wait, list_locks, lock, unlock, DistributedLock, LockWatcher};
+
+    fn decode(value: Option<String>) -> DistributedLock {
+        return serde_json::from_str(&value.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn waiters_are_served_in_order() {
+        let watcher = LockWatcher::new();
+        let mut events = watcher.subscribe();
+        let mut kvs = StateKvs::default();
+        let name = "retain-compaction".to_string();
+        let a = "broker-a".to_string();
+        let b = "broker-b".to_string();
+        let c = "broker-c".to_string();
+
+        let state = decode(lock(&mut kvs, &watcher, &name, &a, 1000, false).value);
+        assert_eq!(state.held_by(&a).unwrap().token, 1);
+        assert_eq!(events.try_recv().unwrap().token, 1);
+
+        // Without waiting nobody is queued.
+        let state = decode(lock(&mut kvs, &watcher, &name, &b, 1000, false).value);
+        assert!(state.held_by(&b).is_none());
+        assert!(state.waiters.is_empty());
+
+        lock(&mut kvs, &watcher, &name, &c, 1000, true);
+        lock(&mut kvs, &watcher, &name, &b, 1000, true);
+        // Locking again as the holder keeps the token.
+        let state = decode(lock(&mut kvs, &watcher, &name, &a, 2000, false).value);
+        assert_eq!(state.held_by(&a).unwrap().token, 1);
+        assert!(events.try_recv().is_err());
+
+        // A stale token does not release the lock, the right one hands it to c.
+        assert!(unlock(&mut kvs, &watcher, &name, Some(&a), 0)
+            .value
+            .is_none());
+        let state = decode(unlock(&mut kvs, &watcher, &name, Some(&a), 1).value);
+        assert_eq!(state.held_by(&c).unwrap().token, 2);
+        assert_eq!(events.try_recv().unwrap().token, 2);
+
+        // b gives up, the expired lock of c is free afterwards.
+        let state = decode(cancel_wait(&mut kvs, &watcher, &name, &b).value);
+        assert!(state.waiters.is_empty());
+        let state = decode(unlock(&mut kvs, &watcher, &name, None, 2).value);
+        assert!(state.holder.is_none());
+
+        // A free lock keeps its token count.
+        let state = decode(lock(&mut kvs, &watcher, &name, &b, 1000, false).value);
+        assert_eq!(state.held_by(&b).unwrap().token, 3);
+        assert_eq!(list_locks(&kvs).len(), 1);
+    }
+}
@@ -1,16 +1,186 @@
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    openraft::store::log_store::{CommitWatcher, RecoveryWatcher},
+    placement::topic::PlacementTopic,
+    raft::node::{BrokerNode, NodeState},
+};
+
+use self::{
+    cluster::NodeWatcher,
+    election::ElectionWatcher,
+    lock::LockWatcher,
+    mqtt::{MqttLastWill, MqttSession, MqttSubscription},
+    offset::GroupOffset,
+};
+
+pub mod cluster;
+pub mod counter;
+pub mod election;
+pub mod kvs;
+pub mod lock;
+pub mod mqtt;
+pub mod offset;
+pub mod sequence;
+pub mod topic;
+
+/// Version of the set of `AppRequestData` variants, sent with every replicated request.
+/// Raise it whenever a variant is appended, so a node that cannot decode a request from a
+/// newer node can tell that it needs an upgrade.
+pub const APP_REQUEST_VERSION: u32 = 1;
+
 /**
  * Here you will set the types of request that will interact with the raft nodes.
  * For example the `Set` will be used to write data (key and value) to the raft database.
  * The `AddNode` will append a new node to the current existing shared list of nodes.
  * You will want to add any request that can write data in all nodes here.
+ *
+ * Log entries are encoded with bincode, which tags each variant by its position. Only
+ * append new variants at the end, never insert, reorder or remove one, or entries
+ * already in the log decode as a different request.
  */
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum AppRequestData {
     Set { key: String, value: String },
 
     Delete { key: String },
+
+    /// Requests gathered by the group commit layer and committed as one log entry.
+    /// They are applied in order and answered one by one.
+    Batch { requests: Vec<AppRequestData> },
+
+    /// Register a broker node, or update it when it is registered already.
+    RegisterNode { node: BrokerNode },
+
+    UnRegisterNode {
+        cluster_type: String,
+        cluster_name: String,
+        node_id: u64,
+    },
+
+    /// Lifecycle transition of a broker node decided by the leader, `Stop` removes it.
+    UpdateNodeState {
+        cluster_type: String,
+        cluster_name: String,
+        node_id: u64,
+        state: NodeState,
+    },
+
+    /// Store a topic with the assignment the leader picked, unless it exists already.
+    CreateTopic { topic: PlacementTopic },
+
+    ReassignPartition {
+        cluster_type: String,
+        cluster_name: String,
+        topic_name: String,
+        partition: u32,
+        replicas: Vec<u64>,
+    },
+
+    Campaign {
+        name: String,
+        candidate: String,
+        lease_ms: u64,
+    },
+
+    Resign {
+        name: String,
+        candidate: String,
+        epoch: u64,
+    },
+
+    /// The lease of a leadership ran out, decided by the leader.
+    ExpireLeader { name: String, epoch: u64 },
+
+    /// Take a lock, or queue up for it when `wait` is set.
+    Lock {
+        name: String,
+        owner: String,
+        ttl_ms: u64,
+        wait: bool,
+    },
+
+    Unlock {
+        name: String,
+        owner: String,
+        token: u64,
+    },
+
+    CancelLockWait { name: String, owner: String },
+
+    /// The TTL of a lock ran out, decided by the leader.
+    ExpireLock { name: String, token: u64 },
+
+    /// Take the next block of IDs of a sequence.
+    AllocateIds { name: String, count: u64 },
+
+    /// Add a delta to an integer-valued key, refused past the floor or the ceiling.
+    Incr {
+        key: String,
+        delta: i64,
+        floor: Option<i64>,
+        ceiling: Option<i64>,
+    },
+
+    SaveMqttSession {
+        cluster_name: String,
+        session: MqttSession,
+    },
+
+    /// Remove an MQTT session together with its subscriptions and last will.
+    DeleteMqttSession {
+        cluster_name: String,
+        client_id: String,
+    },
+
+    MqttSubscribe {
+        cluster_name: String,
+        client_id: String,
+        subscriptions: Vec<MqttSubscription>,
+    },
+
+    MqttUnsubscribe {
+        cluster_name: String,
+        client_id: String,
+        filters: Vec<String>,
+    },
+
+    /// Store the last will of an MQTT session, None removes it.
+    SaveMqttLastWill {
+        cluster_name: String,
+        client_id: String,
+        last_will: Option<MqttLastWill>,
+    },
+
+    /// Store a batch of consumer offsets.
+    CommitOffsets { offsets: Vec<GroupOffset> },
+
+    /// Remove the offsets of the groups that committed nothing since `before`, decided by
+    /// the leader.
+    ExpireOffsets { groups: Vec<String>, before: u64 },
+}
+
+/// Receive the changes of every domain as the state machine applies them, and the commit
+/// index as the log store learns it, along with the commit index lost by a log recovery.
+#[derive(Debug, Clone)]
+pub struct StateWatchers {
+    pub node: NodeWatcher,
+    pub election: ElectionWatcher,
+    pub lock: LockWatcher,
+    pub commit: CommitWatcher,
+    pub recovery: RecoveryWatcher,
+}
+
+impl StateWatchers {
+    pub fn new() -> Self {
+        return StateWatchers {
+            node: NodeWatcher::new(),
+            election: ElectionWatcher::new(),
+            lock: LockWatcher::new(),
+            commit: CommitWatcher::new(),
+            recovery: RecoveryWatcher::new(),
+        };
+    }
 }
 
 /**
@@ -21,7 +191,11 @@ pub enum AppRequestData {
  * TODO: Should we explain how to create multiple `AppDataResponse`?
  *
  */
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
 pub struct AppResponseData {
     pub value: Option<String>,
+
+    /// Responses of the requests of a `Batch`, in request order. Empty otherwise.
+    #[serde(default)]
+    pub batch: Vec<AppResponseData>,
 }
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::keys::{
+    storage_key_mqtt_last_will, storage_key_mqtt_session, storage_key_mqtt_session_cluster_prefix,
+    storage_key_mqtt_subscription,
+};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// State of the clients of the MQTT brokers, stored as JSON under `/mqtt/` per cluster and
+// client id: the session, its subscriptions and its last will each have their own key.
+// Subscriptions and last wills only exist next to a session and go away with it.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttSession {
+    pub client_id: String,
+    pub clean_start: bool,
+    /// Seconds the session outlives the connection of the client.
+    pub session_expiry_interval: u64,
+    /// Broker node the client is connected to.
+    pub broker_id: u64,
+    pub create_time: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttSubscription {
+    pub filter: String,
+    pub qos: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttLastWill {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub qos: u32,
+    pub retain: bool,
+    /// Seconds to wait after the connection is lost before publishing.
+    pub delay_interval: u64,
+}
+
+/// A session with everything stored for it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MqttSessionState {
+    pub session: MqttSession,
+    pub subscriptions: Vec<MqttSubscription>,
+    pub last_will: Option<MqttLastWill>,
+}
+
+/// Store the session, replacing the one of the same client. A clean start drops the
+/// subscriptions of the previous session.
+pub fn save_session(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    session: MqttSession,
+) -> AppResponseData {
+    if session.clean_start {
+        kvs.remove(&storage_key_mqtt_subscription(
+            cluster_name,
+            &session.client_id,
+        ));
+    }
+    let key = storage_key_mqtt_session(cluster_name, &session.client_id);
+    return AppResponseData {
+        value: save(kvs, key, &session),
+        ..Default::default()
+    };
+}
+
+/// Remove the session with its subscriptions and last will. The response carries a value
+/// only when the session existed.
+pub fn delete_session(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    client_id: &String,
+) -> AppResponseData {
+    kvs.remove(&storage_key_mqtt_subscription(cluster_name, client_id));
+    kvs.remove(&storage_key_mqtt_last_will(cluster_name, client_id));
+    return AppResponseData {
+        value: kvs.remove(&storage_key_mqtt_session(cluster_name, client_id)),
+        ..Default::default()
+    };
+}
+
+/// Add subscriptions to the session, a filter subscribed again gets the new QoS. The
+/// response carries every subscription, or no value when the session does not exist.
+pub fn subscribe(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    client_id: &String,
+    subscriptions: Vec<MqttSubscription>,
+) -> AppResponseData {
+    if !kvs.contains_key(&storage_key_mqtt_session(cluster_name, client_id)) {
+        return AppResponseData::default();
+    }
+    let mut current = get_subscriptions(kvs, cluster_name, client_id);
+    for subscription in subscriptions {
+        match current
+            .iter_mut()
+            .find(|existing| existing.filter == subscription.filter)
+        {
+            Some(existing) => existing.qos = subscription.qos,
+            None => current.push(subscription),
+        }
+    }
+    return save_subscriptions(kvs, cluster_name, client_id, current);
+}
+
+/// Remove subscriptions from the session. The response carries the remaining ones, or no
+/// value when the session does not exist.
+pub fn unsubscribe(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    client_id: &String,
+    filters: Vec<String>,
+) -> AppResponseData {
+    if !kvs.contains_key(&storage_key_mqtt_session(cluster_name, client_id)) {
+        return AppResponseData::default();
+    }
+    let mut current = get_subscriptions(kvs, cluster_name, client_id);
+    current.retain(|subscription| !filters.contains(&subscription.filter));
+    return save_subscriptions(kvs, cluster_name, client_id, current);
+}
+
+/// Store the last will of the session, or remove it with None. The response carries no
+/// value when the session does not exist.
+pub fn save_last_will(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    client_id: &String,
+    last_will: Option<MqttLastWill>,
+) -> AppResponseData {
+    if !kvs.contains_key(&storage_key_mqtt_session(cluster_name, client_id)) {
+        return AppResponseData::default();
+    }
+    let key = storage_key_mqtt_last_will(cluster_name, client_id);
+    let value = match last_will {
+        Some(last_will) => save(kvs, key, &last_will),
+        None => {
+            kvs.remove(&key);
+            Some(String::new())
+        }
+    };
+    return AppResponseData {
+        value,
+        ..Default::default()
+    };
+}
+
+pub fn get_session(
+    kvs: &BTreeMap<String, String>,
+    cluster_name: &String,
+    client_id: &String,
+) -> Option<MqttSessionState> {
+    let session = kvs
+        .get(&storage_key_mqtt_session(cluster_name, client_id))
+        .and_then(|value| serde_json::from_str::<MqttSession>(value).ok())?;
+    return Some(session_state(kvs, cluster_name, session));
+}
+
+/// Sessions of the cluster, only those owned by `broker_id` when it is set. Another
+/// broker taking over a failed one lists its sessions this way.
+pub fn list_sessions(
+    kvs: &BTreeMap<String, String>,
+    cluster_name: &String,
+    broker_id: Option<u64>,
+) -> Vec<MqttSessionState> {
+    let prefix = storage_key_mqtt_session_cluster_prefix(cluster_name);
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<MqttSession>(value).ok())
+        .filter(|session| broker_id.map_or(true, |broker_id| session.broker_id == broker_id))
+        .map(|session| session_state(kvs, cluster_name, session))
+        .collect();
+}
+
+fn session_state(
+    kvs: &BTreeMap<String, String>,
+    cluster_name: &String,
+    session: MqttSession,
+) -> MqttSessionState {
+    let subscriptions = get_subscriptions(kvs, cluster_name, &session.client_id);
+    let last_will = kvs
+        .get(&storage_key_mqtt_last_will(
+            cluster_name,
+            &session.client_id,
+        ))
+        .and_then(|value| serde_json::from_str::<MqttLastWill>(value).ok());
+    return MqttSessionState {
+        session,
+        subscriptions,
+        last_will,
+    };
+}
+
+fn get_subscriptions(
+    kvs: &BTreeMap<String, String>,
+    cluster_name: &String,
+    client_id: &String,
+) -> Vec<MqttSubscription> {
+    return kvs
+        .get(&storage_key_mqtt_subscription(cluster_name, client_id))
+        .and_then(|value| serde_json::from_str::<Vec<MqttSubscription>>(value).ok())
+        .unwrap_or_default();
+}
+
+fn save_subscriptions(
+    kvs: &mut StateKvs,
+    cluster_name: &String,
+    client_id: &String,
+    subscriptions: Vec<MqttSubscription>,
+) -> AppResponseData {
+    let key = storage_key_mqtt_subscription(cluster_name, client_id);
+    if subscriptions.is_empty() {
+        kvs.remove(&key);
+        return AppResponseData {
+            value: Some("[]".to_string()),
+            ..Default::default()
+        };
+    }
+    return AppResponseData {
+        value: save(kvs, key, &subscriptions),
+        ..Default::default()
+    };
+}
+
+fn save<T: Serialize>(kvs: &mut StateKvs, key: String, data: &T) -> Option<String> {
+    match serde_json::to_string(data) {
+        Ok(value) => {
+            kvs.insert(key, value.clone());
+            return Some(value);
+        }
+        Err(e) => {
+            error!("Failed to encode {}: {}", key, e);
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{
+        delete_session, get_session, list_sessions, save_last_will, save_session, subscribe,
+        unsubscribe, MqttLastWill, MqttSession, MqttSubscription,
+    };
+
+    fn session(client_id: &str, broker_id: u64, clean_start: bool) -> MqttSession {
+        return MqttSession {
+            client_id: client_id.to_string(),
+            clean_start,
+            session_expiry_interval: 3600,
+            broker_id,
+            create_time: 100,
+        };
+    }
+
+    fn subscription(filter: &str, qos: u32) -> MqttSubscription {
+        return MqttSubscription {
+            filter: filter.to_string(),
+            qos,
+        };
+    }
+
+    #[test]
+    fn sessions_keep_their_subscriptions_and_last_will() {
+        let mut kvs = StateKvs::default();
+        let cluster = "mqtt".to_string();
+        let c1 = "c1".to_string();
+
+        // Nothing can be attached to a missing session.
+        assert!(
+            subscribe(&mut kvs, &cluster, &c1, vec![subscription("a/#", 1)])
+                .value
+                .is_none()
+        );
+
+        save_session(&mut kvs, &cluster, session("c1", 1, true));
+        save_session(&mut kvs, &cluster, session("c2", 2, true));
+        subscribe(
+            &mut kvs,
+            &cluster,
+            &c1,
+            vec![subscription("a/#", 1), subscription("b/+", 0)],
+        );
+        subscribe(&mut kvs, &cluster, &c1, vec![subscription("a/#", 2)]);
+        unsubscribe(&mut kvs, &cluster, &c1, vec!["b/+".to_string()]);
+        let will = MqttLastWill {
+            topic: "status/c1".to_string(),
+            payload: b"offline".to_vec(),
+            qos: 1,
+            retain: true,
+            delay_interval: 5,
+        };
+        assert!(save_last_will(&mut kvs, &cluster, &c1, Some(will.clone()))
+            .value
+            .is_some());
+
+        let state = get_session(&kvs, &cluster, &c1).unwrap();
+        assert_eq!(state.subscriptions, vec![subscription("a/#", 2)]);
+        assert_eq!(state.last_will, Some(will));
+
+        // The sessions of a failed broker, taken over by another one without a clean start.
+        let owned = list_sessions(&kvs, &cluster, Some(1));
+        assert_eq!(owned.len(), 1);
+        assert_eq!(owned[0].session.client_id, c1);
+        save_session(&mut kvs, &cluster, session("c1", 2, false));
+        assert_eq!(list_sessions(&kvs, &cluster, Some(2)).len(), 2);
+        assert_eq!(
+            get_session(&kvs, &cluster, &c1)
+                .unwrap()
+                .subscriptions
+                .len(),
+            1
+        );
+
+        // A clean start drops the subscriptions, deleting drops everything.
+        save_session(&mut kvs, &cluster, session("c1", 2, true));
+        assert!(get_session(&kvs, &cluster, &c1)
+            .unwrap()
+            .subscriptions
+            .is_empty());
+        assert!(delete_session(&mut kvs, &cluster, &c1).value.is_some());
+        assert!(get_session(&kvs, &cluster, &c1).is_none());
+        assert_eq!(kvs.len(), 1);
+    }
+}
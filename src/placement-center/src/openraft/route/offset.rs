@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::keys::{key_offset, key_offset_group_prefix, key_offset_prefix};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Committed offsets of consumer groups, stored as JSON under
+// `/offset/{group}/{topic}/{partition}`. The commit time is set by the leader before the
+// commit is proposed, so every node expires the same groups.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GroupOffset {
+    pub group: String,
+    pub topic: String,
+    pub partition: u32,
+    pub offset: u64,
+    pub metadata: String,
+    /// Seconds since the epoch, by the clock of the leader.
+    pub commit_time: u64,
+}
+
+/// Store a batch of offsets in one go. The response carries the number stored.
+pub fn commit_offsets(kvs: &mut StateKvs, offsets: Vec<GroupOffset>) -> AppResponseData {
+    let mut stored = 0;
+    for offset in offsets {
+        let key = key_offset(&offset.group, &offset.topic, offset.partition);
+        match serde_json::to_string(&offset) {
+            Ok(value) => {
+                kvs.insert(key, value);
+                stored += 1;
+            }
+            Err(e) => {
+                error!("Failed to encode offset {}: {}", key, e);
+            }
+        }
+    }
+    return AppResponseData {
+        value: Some(stored.to_string()),
+        ..Default::default()
+    };
+}
+
+pub fn fetch_offset(
+    kvs: &BTreeMap<String, String>,
+    group: &String,
+    topic: &String,
+    partition: u32,
+) -> Option<GroupOffset> {
+    return kvs
+        .get(&key_offset(group, topic, partition))
+        .and_then(|value| serde_json::from_str::<GroupOffset>(value).ok());
+}
+
+pub fn list_group_offsets(kvs: &BTreeMap<String, String>, group: &String) -> Vec<GroupOffset> {
+    // A group named like the prefix of another one shares its key prefix.
+    return scan_offsets(kvs, key_offset_group_prefix(group))
+        .into_iter()
+        .filter(|offset| offset.group == *group)
+        .collect();
+}
+
+/// Groups whose last commit happened before `before`.
+pub fn idle_groups(kvs: &BTreeMap<String, String>, before: u64) -> Vec<String> {
+    let mut last_commit: BTreeMap<String, u64> = BTreeMap::new();
+    for offset in scan_offsets(kvs, key_offset_prefix()) {
+        let last = last_commit.entry(offset.group).or_insert(0);
+        *last = (*last).max(offset.commit_time);
+    }
+    return last_commit
+        .into_iter()
+        .filter(|(_, last)| *last < before)
+        .map(|(group, _)| group)
+        .collect();
+}
+
+/// Remove the offsets of the groups that are still idle, a group that committed since it
+/// was picked is kept. The response carries the number of groups removed.
+pub fn expire_groups(kvs: &mut StateKvs, groups: Vec<String>, before: u64) -> AppResponseData {
+    let mut expired = 0;
+    for group in groups {
+        let offsets = list_group_offsets(kvs, &group);
+        if offsets.is_empty() || offsets.iter().any(|offset| offset.commit_time >= before) {
+            continue;
+        }
+        for offset in offsets {
+            kvs.remove(&key_offset(&offset.group, &offset.topic, offset.partition));
+        }
+        expired += 1;
+    }
+    return AppResponseData {
+        value: Some(expired.to_string()),
+        ..Default::default()
+    };
+}
+
+fn scan_offsets(kvs: &BTreeMap<String, String>, prefix: String) -> Vec<GroupOffset> {
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<GroupOffset>(value).ok())
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{
+        commit_offsets, expire_groups, fetch_offset, idle_groups, list_group_offsets, GroupOffset,
+    };
+
+    fn offset(group: &str, partition: u32, offset: u64, commit_time: u64) -> GroupOffset {
+        return GroupOffset {
+            group: group.to_string(),
+            topic: "orders".to_string(),
+            partition,
+            offset,
+            metadata: String::new(),
+            commit_time,
+        };
+    }
+
+    #[test]
+    fn idle_groups_expire() {
+        let mut kvs = StateKvs::default();
+        let billing = "billing".to_string();
+        let audit = "audit".to_string();
+        let topic = "orders".to_string();
+
+        commit_offsets(
+            &mut kvs,
+            vec![offset("billing", 0, 10, 100), offset("billing", 1, 20, 100)],
+        );
+        commit_offsets(&mut kvs, vec![offset("audit", 0, 5, 100)]);
+        // A later commit replaces the offset.
+        commit_offsets(&mut kvs, vec![offset("billing", 1, 25, 300)]);
+        assert_eq!(fetch_offset(&kvs, &billing, &topic, 1).unwrap().offset, 25);
+        assert!(fetch_offset(&kvs, &billing, &topic, 2).is_none());
+        assert_eq!(list_group_offsets(&kvs, &billing).len(), 2);
+
+        // Only a group whose every offset is old enough is idle.
+        assert_eq!(idle_groups(&kvs, 200), vec![audit.clone()]);
+
+        // A commit after the group was picked keeps it.
+        commit_offsets(&mut kvs, vec![offset("audit", 0, 6, 250)]);
+        let resp = expire_groups(&mut kvs, vec![audit.clone()], 200);
+        assert_eq!(resp.value.unwrap(), "0");
+
+        let resp = expire_groups(&mut kvs, idle_groups(&kvs, 400), 400);
+        assert_eq!(resp.value.unwrap(), "2");
+        assert!(kvs.is_empty());
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage::keys::key_sequence;
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Named sequences, stored under `/sequence/{name}` as the next ID to hand out. IDs start
+// at 1. A block is taken in one log entry and only handed out once it is committed, so a
+// new leader continues after every block the old one gave away.
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdBlock {
+    /// First ID of the block, the block holds `start..start + count`.
+    pub start: u64,
+    pub count: u64,
+}
+
+/// Take the next `count` IDs of the sequence. The response carries the block, or no value
+/// when the sequence would overflow or its stored value is not a valid ID.
+pub fn allocate_ids(kvs: &mut StateKvs, name: &String, count: u64) -> AppResponseData {
+    let key = key_sequence(name);
+    let start = match next_id(kvs, &key) {
+        Some(start) => start,
+        None => return AppResponseData::default(),
+    };
+    let next = match start.checked_add(count) {
+        Some(next) => next,
+        None => return AppResponseData::default(),
+    };
+    kvs.insert(key, next.to_string());
+    return AppResponseData {
+        value: serde_json::to_string(&IdBlock { start, count }).ok(),
+        ..Default::default()
+    };
+}
+
+/// Next ID of the sequence stored at `key`, 1 when it was never used. A value that does not
+/// parse gives `None`, restarting at 1 would hand out IDs that are already in use.
+pub fn next_id(kvs: &BTreeMap<String, String>, key: &String) -> Option<u64> {
+    return match kvs.get(key) {
+        Some(value) => value.parse::<u64>().ok(),
+        None => Some(1),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{allocate_ids, IdBlock};
+    use crate::storage::keys::key_sequence;
+
+    fn decode(value: Option<String>) -> IdBlock {
+        return serde_json::from_str(&value.unwrap()).unwrap();
+    }
+
+    #[test]
+    fn blocks_do_not_overlap() {
+        let mut kvs = StateKvs::default();
+        let session = "session".to_string();
+        let segment = "segment".to_string();
+
+        assert_eq!(
+            decode(allocate_ids(&mut kvs, &session, 100).value),
+            IdBlock {
+                start: 1,
+                count: 100
+            }
+        );
+        assert_eq!(
+            decode(allocate_ids(&mut kvs, &session, 10).value).start,
+            101
+        );
+        // Sequences are independent.
+        assert_eq!(decode(allocate_ids(&mut kvs, &segment, 10).value).start, 1);
+
+        // A block past the end of the range is refused and takes nothing.
+        kvs.insert(key_sequence(&session), (u64::MAX - 5).to_string());
+        assert!(allocate_ids(&mut kvs, &session, 10).value.is_none());
+        assert_eq!(
+            decode(allocate_ids(&mut kvs, &session, 5).value).start,
+            u64::MAX - 5
+        );
+
+        // A damaged value refuses the allocation instead of starting over.
+        kvs.insert(key_sequence(&segment), "not-a-number".to_string());
+        assert!(allocate_ids(&mut kvs, &segment, 10).value.is_none());
+        assert_eq!(
+            kvs.get(&key_sequence(&segment)),
+            Some(&"not-a-number".to_string())
+        );
+    }
+}
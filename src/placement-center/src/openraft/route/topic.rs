@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use log::error;
+
+use crate::{
+    placement::topic::{PartitionAssignment, PlacementTopic},
+    storage::keys::{key_topic, key_topic_prefix},
+};
+
+use super::{kvs::StateKvs, AppResponseData};
+
+// Topics placed by the placement center, stored as JSON under
+// `/placement/topic/{cluster_type}/{cluster_name}/{topic_name}`. The leader decides the
+// assignment before proposing it, applying only stores it, so every node ends up with
+// the same placement.
+
+/// Store a new topic. An existing topic is left as it is and the response carries no
+/// value, so a create that lost a race can tell.
+pub fn create_topic(kvs: &mut StateKvs, topic: PlacementTopic) -> AppResponseData {
+    let key = key_topic(&topic.cluster_type, &topic.cluster_name, &topic.topic_name);
+    if kvs.contains_key(&key) {
+        return AppResponseData::default();
+    }
+    return AppResponseData {
+        value: save_topic(kvs, key, &topic),
+        ..Default::default()
+    };
+}
+
+/// Replace the replicas of one partition. The response carries the new assignment, or no
+/// value when the topic or the partition does not exist.
+pub fn reassign_partition(
+    kvs: &mut StateKvs,
+    cluster_type: &String,
+    cluster_name: &String,
+    topic_name: &String,
+    partition: u32,
+    replicas: Vec<u64>,
+) -> AppResponseData {
+    let key = key_topic(cluster_type, cluster_name, topic_name);
+    let mut topic = match get_topic(kvs, &key) {
+        Some(topic) => topic,
+        None => return AppResponseData::default(),
+    };
+    let assignment = match topic
+        .partitions
+        .iter_mut()
+        .find(|assignment| assignment.partition == partition)
+    {
+        Some(assignment) => assignment,
+        None => return AppResponseData::default(),
+    };
+    assignment.replicas = replicas;
+    let assignment = assignment.clone();
+
+    if save_topic(kvs, key, &topic).is_none() {
+        return AppResponseData::default();
+    }
+    return AppResponseData {
+        value: serde_json::to_string(&assignment).ok(),
+        ..Default::default()
+    };
+}
+
+pub fn get_topic(kvs: &BTreeMap<String, String>, key: &String) -> Option<PlacementTopic> {
+    return kvs
+        .get(key)
+        .and_then(|value| serde_json::from_str::<PlacementTopic>(value).ok());
+}
+
+pub fn list_topics(
+    kvs: &BTreeMap<String, String>,
+    cluster_type: &String,
+    cluster_name: &String,
+) -> Vec<PlacementTopic> {
+    let prefix = key_topic_prefix(cluster_type, cluster_name);
+    return kvs
+        .range(prefix.clone()..)
+        .take_while(|(key, _)| key.starts_with(&prefix))
+        .filter_map(|(_, value)| serde_json::from_str::<PlacementTopic>(value).ok())
+        .collect();
+}
+
+pub fn decode_assignment(value: &String) -> Option<PartitionAssignment> {
+    return serde_json::from_str(value).ok();
+}
+
+fn save_topic(kvs: &mut StateKvs, key: String, topic: &PlacementTopic) -> Option<String> {
+    match serde_json::to_string(topic) {
+        Ok(value) => {
+            kvs.insert(key, value.clone());
+            return Some(value);
+        }
+        Err(e) => {
+            error!("Failed to encode topic {}: {}", topic.topic_name, e);
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::route::kvs::StateKvs;
+
+    use super::{create_topic, decode_assignment, list_topics, reassign_partition};
+    use crate::placement::topic::{PartitionAssignment, PlacementTopic};
+
+    fn topic(topic_name: &str) -> PlacementTopic {
+        return PlacementTopic {
+            cluster_type: "JournalServer".to_string(),
+            cluster_name: "journal".to_string(),
+            topic_name: topic_name.to_string(),
+            partition_num: 2,
+            replication_factor: 2,
+            partitions: vec![
+                PartitionAssignment {
+                    partition: 0,
+                    replicas: vec![1, 2],
+                },
+                PartitionAssignment {
+                    partition: 1,
+                    replicas: vec![2, 3],
+                },
+            ],
+            create_time: 100,
+        };
+    }
+
+    #[test]
+    fn create_and_reassign() {
+        let mut kvs = StateKvs::default();
+        let cluster_type = "JournalServer".to_string();
+        let cluster_name = "journal".to_string();
+        let topic_name = "t1".to_string();
+
+        assert!(create_topic(&mut kvs, topic("t1")).value.is_some());
+        assert!(create_topic(&mut kvs, topic("t2")).value.is_some());
+        // Creating again keeps the first assignment.
+        let mut again = topic("t1");
+        again.partitions[0].replicas = vec![3, 4];
+        assert!(create_topic(&mut kvs, again).value.is_none());
+
+        let resp = reassign_partition(
+            &mut kvs,
+            &cluster_type,
+            &cluster_name,
+            &topic_name,
+            1,
+            vec![3, 1],
+        );
+        assert_eq!(
+            decode_assignment(&resp.value.unwrap()).unwrap().replicas,
+            vec![3, 1]
+        );
+        let topics = list_topics(&kvs, &cluster_type, &cluster_name);
+        assert_eq!(topics.len(), 2);
+        assert_eq!(topics[0].partition(0).unwrap().replicas, vec![1, 2]);
+        assert_eq!(topics[0].partition(1).unwrap().replicas, vec![3, 1]);
+
+        // Unknown partition or topic.
+        assert!(reassign_partition(
+            &mut kvs,
+            &cluster_type,
+            &cluster_name,
+            &topic_name,
+            2,
+            vec![1]
+        )
+        .value
+        .is_none());
+        assert!(reassign_partition(
+            &mut kvs,
+            &cluster_type,
+            &cluster_name,
+            &"t3".to_string(),
+            0,
+            vec![1]
+        )
+        .value
+        .is_none());
+    }
+}
@@ -0,0 +1,490 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use clients::{
+    placement::cluster::call::{placement_cluster_status, placement_member_status},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use openraft::{Raft, RaftMetrics, ServerState};
+use protocol::cluster::{
+    ClusterStatusReply, ClusterStatusRequest, MemberStatus as MemberStatusInfo, MemberStatusReply,
+    MemberStatusRequest, MembershipStatus as MembershipStatusInfo, StorageSize as StorageSizeInfo,
+    VoterSet,
+};
+use serde::Serialize;
+use tokio::{task::JoinSet, time::timeout};
+
+use crate::{
+    raft::metadata::RaftGroupMetadata,
+    storage::metrics::{DBMetrics, StorageMetricsRegistry},
+};
+
+use super::{
+    bounded_read::leader_addr, learner::replication_lag, raft_node::NodeId, typeconfig::TypeConfig,
+};
+
+/// How long a member may take to report its status before it is reported unreachable.
+const MEMBER_STATUS_TIMEOUT_MS: u64 = 500;
+
+/// One member of the placement center cluster. Fields of a member that did not answer
+/// the leader are None, except what the leader knows from replicating to it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MemberStatus {
+    pub node_id: NodeId,
+    pub addr: String,
+    pub role: String,
+    pub term: Option<u64>,
+    pub last_log_index: Option<u64>,
+    pub applied_index: Option<u64>,
+    pub replication_lag: Option<u64>,
+    pub reachable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MembershipStatus {
+    pub log_index: Option<u64>,
+    /// More than one set while a membership change is in progress.
+    pub voters: Vec<Vec<NodeId>>,
+    pub learners: Vec<NodeId>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StorageSize {
+    pub db: String,
+    pub estimate_num_keys: u64,
+    pub sst_files_size: u64,
+    pub mem_table_size: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterStatus {
+    pub cluster_name: String,
+    pub node_id: NodeId,
+    pub leader_id: Option<NodeId>,
+    pub current_term: u64,
+    pub membership: MembershipStatus,
+    pub members: Vec<MemberStatus>,
+    pub storage: Vec<StorageSize>,
+}
+
+/// Status of the whole cluster as seen by the leader. A follower forwards the request,
+/// the leader asks every other member for its local status and reports the members that
+/// do not answer as unreachable.
+pub async fn cluster_status(
+    raft_node: &Raft<TypeConfig>,
+    client_poll: &Arc<ClientPool>,
+    placement_cache: &Arc<RwLock<RaftGroupMetadata>>,
+    storage_metrics: &StorageMetricsRegistry,
+) -> Result<ClusterStatus, RobustMQError> {
+    if let Some(leader_addr) = leader_addr(raft_node) {
+        let reply = placement_cluster_status(
+            client_poll.clone(),
+            vec![leader_addr],
+            ClusterStatusRequest {},
+        )
+        .await?;
+        return Ok(cluster_status_from_proto(reply));
+    }
+
+    let metrics = raft_node.metrics().borrow().clone();
+    let cluster_name = placement_cache.read().unwrap().local.cluster_name.clone();
+
+    let peers: Vec<(NodeId, String)> = metrics
+        .membership_config
+        .membership()
+        .nodes()
+        .filter(|(node_id, _)| **node_id != metrics.id)
+        .map(|(node_id, node)| (*node_id, node.rpc_addr.clone()))
+        .collect();
+    let reported = collect_member_status(client_poll, peers).await;
+
+    return Ok(build_cluster_status(
+        cluster_name,
+        &metrics,
+        &reported,
+        storage_metrics.collect(),
+    ));
+}
+
+/// Raft state of this node, as reported to the leader.
+pub fn local_member_status(metrics: &RaftMetrics<TypeConfig>) -> MemberStatusReply {
+    return MemberStatusReply {
+        node_id: metrics.id,
+        role: format!("{:?}", metrics.state),
+        term: metrics.current_term,
+        last_log_index: metrics.last_log_index,
+        applied_index: metrics.last_applied.map(|log_id| log_id.index),
+    };
+}
+
+pub fn build_cluster_status(
+    cluster_name: String,
+    metrics: &RaftMetrics<TypeConfig>,
+    reported: &BTreeMap<NodeId, MemberStatusReply>,
+    storage: Vec<DBMetrics>,
+) -> ClusterStatus {
+    let membership = metrics.membership_config.membership();
+    let learners: BTreeSet<NodeId> = membership.learner_ids().collect();
+    let replication: BTreeMap<NodeId, (Option<u64>, u64)> = match &metrics.replication {
+        Some(replication) => replication_lag(metrics.last_log_index, replication, &learners)
+            .into_iter()
+            .map(|lag| (lag.node_id, (lag.matched_index, lag.lag)))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+    let local = local_member_status(metrics);
+
+    let mut members = Vec::new();
+    for (node_id, node) in membership.nodes() {
+        let report = if *node_id == metrics.id {
+            Some(&local)
+        } else {
+            reported.get(node_id)
+        };
+        let role = member_role(
+            *node_id,
+            metrics.id,
+            metrics.state,
+            metrics.current_leader,
+            learners.contains(node_id),
+        );
+        members.push(member_status(
+            *node_id,
+            node.rpc_addr.clone(),
+            role,
+            report,
+            replication.get(node_id).copied(),
+        ));
+    }
+
+    return ClusterStatus {
+        cluster_name,
+        node_id: metrics.id,
+        leader_id: metrics.current_leader,
+        current_term: metrics.current_term,
+        membership: MembershipStatus {
+            log_index: metrics
+                .membership_config
+                .log_id()
+                .as_ref()
+                .map(|log_id| log_id.index),
+            voters: membership
+                .get_joint_config()
+                .iter()
+                .map(|voters| voters.iter().copied().collect())
+                .collect(),
+            learners: learners.into_iter().collect(),
+        },
+        members,
+        storage: storage_sizes(storage),
+    };
+}
+
+/// Status of one member from its own report, or from what the leader replicated to it
+/// when it did not answer. `role` is the role this node assumes for it.
+pub fn member_status(
+    node_id: NodeId,
+    addr: String,
+    role: String,
+    report: Option<&MemberStatusReply>,
+    replicated: Option<(Option<u64>, u64)>,
+) -> MemberStatus {
+    let replication_lag = replicated.map(|(_, lag)| lag);
+    match report {
+        Some(report) => {
+            return MemberStatus {
+                node_id,
+                addr,
+                role: report.role.clone(),
+                term: Some(report.term),
+                last_log_index: report.last_log_index,
+                applied_index: report.applied_index,
+                replication_lag,
+                reachable: true,
+            };
+        }
+        None => {
+            return MemberStatus {
+                node_id,
+                addr,
+                role,
+                term: None,
+                last_log_index: replicated.and_then(|(matched_index, _)| matched_index),
+                applied_index: None,
+                replication_lag,
+                reachable: false,
+            };
+        }
+    }
+}
+
+pub fn member_role(
+    node_id: NodeId,
+    local_id: NodeId,
+    local_state: ServerState,
+    current_leader: Option<NodeId>,
+    learner: bool,
+) -> String {
+    if node_id == local_id {
+        return format!("{:?}", local_state);
+    }
+    if current_leader == Some(node_id) {
+        return format!("{:?}", ServerState::Leader);
+    }
+    if learner {
+        return format!("{:?}", ServerState::Learner);
+    }
+    return format!("{:?}", ServerState::Follower);
+}
+
+/// Size of every database, summed over its column families.
+pub fn storage_sizes(dbs: Vec<DBMetrics>) -> Vec<StorageSize> {
+    return dbs
+        .into_iter()
+        .map(|db| StorageSize {
+            estimate_num_keys: db
+                .column_families
+                .iter()
+                .map(|cf| cf.estimate_num_keys)
+                .sum(),
+            sst_files_size: db
+                .column_families
+                .iter()
+                .map(|cf| cf.total_sst_files_size)
+                .sum(),
+            mem_table_size: db
+                .column_families
+                .iter()
+                .map(|cf| cf.cur_size_all_mem_tables)
+                .sum(),
+            db: db.db,
+        })
+        .collect();
+}
+
+/// Asks every peer for its local status, peers that fail or time out are left out.
+async fn collect_member_status(
+    client_poll: &Arc<ClientPool>,
+    peers: Vec<(NodeId, String)>,
+) -> BTreeMap<NodeId, MemberStatusReply> {
+    let mut requests = JoinSet::new();
+    for (node_id, addr) in peers {
+        let client_poll = client_poll.clone();
+        requests.spawn(async move {
+            let request = timeout(
+                Duration::from_millis(MEMBER_STATUS_TIMEOUT_MS),
+                placement_member_status(client_poll, vec![addr], MemberStatusRequest {}),
+            );
+            match request.await {
+                Ok(Ok(reply)) => return Some((node_id, reply)),
+                _ => return None,
+            }
+        });
+    }
+
+    let mut reported = BTreeMap::new();
+    while let Some(request) = requests.join_next().await {
+        if let Ok(Some((node_id, reply))) = request {
+            reported.insert(node_id, reply);
+        }
+    }
+    return reported;
+}
+
+pub fn cluster_status_to_proto(status: ClusterStatus) -> ClusterStatusReply {
+    let members = status
+        .members
+        .into_iter()
+        .map(|member| MemberStatusInfo {
+            node_id: member.node_id,
+            addr: member.addr,
+            role: member.role,
+            term: member.term,
+            last_log_index: member.last_log_index,
+            applied_index: member.applied_index,
+            replication_lag: member.replication_lag,
+            reachable: member.reachable,
+        })
+        .collect();
+    let membership = MembershipStatusInfo {
+        log_index: status.membership.log_index,
+        voters: status
+            .membership
+            .voters
+            .into_iter()
+            .map(|node_ids| VoterSet { node_ids })
+            .collect(),
+        learners: status.membership.learners,
+    };
+    let storage = status
+        .storage
+        .into_iter()
+        .map(|size| StorageSizeInfo {
+            db: size.db,
+            estimate_num_keys: size.estimate_num_keys,
+            sst_files_size: size.sst_files_size,
+            mem_table_size: size.mem_table_size,
+        })
+        .collect();
+    return ClusterStatusReply {
+        cluster_name: status.cluster_name,
+        node_id: status.node_id,
+        leader_id: status.leader_id,
+        current_term: status.current_term,
+        membership: Some(membership),
+        members,
+        storage,
+    };
+}
+
+pub fn cluster_status_from_proto(reply: ClusterStatusReply) -> ClusterStatus {
+    let membership = reply.membership.unwrap_or_default();
+    return ClusterStatus {
+        cluster_name: reply.cluster_name,
+        node_id: reply.node_id,
+        leader_id: reply.leader_id,
+        current_term: reply.current_term,
+        membership: MembershipStatus {
+            log_index: membership.log_index,
+            voters: membership
+                .voters
+                .into_iter()
+                .map(|voters| voters.node_ids)
+                .collect(),
+            learners: membership.learners,
+        },
+        members: reply
+            .members
+            .into_iter()
+            .map(|member| MemberStatus {
+                node_id: member.node_id,
+                addr: member.addr,
+                role: member.role,
+                term: member.term,
+                last_log_index: member.last_log_index,
+                applied_index: member.applied_index,
+                replication_lag: member.replication_lag,
+                reachable: member.reachable,
+            })
+            .collect(),
+        storage: reply
+            .storage
+            .into_iter()
+            .map(|size| StorageSize {
+                db: size.db,
+                estimate_num_keys: size.estimate_num_keys,
+                sst_files_size: size.sst_files_size,
+                mem_table_size: size.mem_table_size,
+            })
+            .collect(),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use openraft::ServerState;
+    use protocol::cluster::MemberStatusReply;
+
+    use super::{member_role, member_status, storage_sizes, MemberStatus, StorageSize};
+    use crate::storage::metrics::{ColumnFamilyMetrics, DBMetrics};
+
+    #[test]
+    fn member_role_test() {
+        assert_eq!(
+            member_role(1, 1, ServerState::Candidate, None, false),
+            "Candidate"
+        );
+        assert_eq!(
+            member_role(2, 1, ServerState::Follower, Some(2), false),
+            "Leader"
+        );
+        assert_eq!(
+            member_role(3, 1, ServerState::Follower, Some(2), true),
+            "Learner"
+        );
+        assert_eq!(
+            member_role(4, 1, ServerState::Follower, Some(2), false),
+            "Follower"
+        );
+    }
+
+    #[test]
+    fn member_status_test() {
+        let report = MemberStatusReply {
+            node_id: 2,
+            role: "Follower".to_string(),
+            term: 3,
+            last_log_index: Some(10),
+            applied_index: Some(9),
+        };
+        assert_eq!(
+            member_status(
+                2,
+                "127.0.0.1:1228".to_string(),
+                "Follower".to_string(),
+                Some(&report),
+                Some((Some(8), 2)),
+            ),
+            MemberStatus {
+                node_id: 2,
+                addr: "127.0.0.1:1228".to_string(),
+                role: "Follower".to_string(),
+                term: Some(3),
+                last_log_index: Some(10),
+                applied_index: Some(9),
+                replication_lag: Some(2),
+                reachable: true,
+            }
+        );
+
+        // A member that did not answer keeps what the leader replicated to it.
+        assert_eq!(
+            member_status(
+                3,
+                "127.0.0.1:1238".to_string(),
+                "Learner".to_string(),
+                None,
+                Some((Some(5), 5)),
+            ),
+            MemberStatus {
+                node_id: 3,
+                addr: "127.0.0.1:1238".to_string(),
+                role: "Learner".to_string(),
+                term: None,
+                last_log_index: Some(5),
+                applied_index: None,
+                replication_lag: Some(5),
+                reachable: false,
+            }
+        );
+    }
+
+    #[test]
+    fn storage_sizes_test() {
+        let cf = |name: &str, keys, sst, mem| ColumnFamilyMetrics {
+            column_family: name.to_string(),
+            estimate_num_keys: keys,
+            total_sst_files_size: sst,
+            cur_size_all_mem_tables: mem,
+            ..Default::default()
+        };
+        let db = DBMetrics {
+            db: "engine".to_string(),
+            column_families: vec![cf("store", 10, 1000, 64), cf("logs", 5, 500, 32)],
+            ..Default::default()
+        };
+        assert_eq!(
+            storage_sizes(vec![db]),
+            vec![StorageSize {
+                db: "engine".to_string(),
+                estimate_num_keys: 15,
+                sst_files_size: 1500,
+                mem_table_size: 96,
+            }]
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use openraft::{AnyError, Entry, ErrorSubject, ErrorVerb, StorageError};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::StorageResult;
+use crate::openraft::typeconfig::TypeConfig;
+
+/// First byte of every framed value. Values written before framing was added are
+/// plain JSON, which never starts with this byte, so the two can never be confused.
+const FRAME_MAGIC: u8 = 0xFF;
+
+/// Payload of the frame is JSON.
+pub const FORMAT_JSON: u8 = 1;
+
+/// Payload of the frame is bincode. This is the format new values are written in.
+pub const FORMAT_BINCODE: u8 = 2;
+
+/// Pseudo format of values written before framing: bare JSON without a checksum.
+/// It can be read but is never written.
+pub const FORMAT_LEGACY_JSON: u8 = 0;
+
+pub const CURRENT_FORMAT: u8 = FORMAT_BINCODE;
+
+/// magic(1) + format(1) + crc32 of the payload(4)
+const FRAME_HEADER_LEN: usize = 6;
+
+/// Encode a value as `magic | format | crc32 | payload` in the current format.
+pub fn encode_value<T: Serialize>(value: &T) -> Result<Vec<u8>, String> {
+    return encode_value_with_format(value, CURRENT_FORMAT);
+}
+
+pub fn encode_value_with_format<T: Serialize>(value: &T, format: u8) -> Result<Vec<u8>, String> {
+    let payload = match format {
+        FORMAT_LEGACY_JSON => return serde_json::to_vec(value).map_err(|e| e.to_string()),
+        FORMAT_JSON => serde_json::to_vec(value).map_err(|e| e.to_string())?,
+        FORMAT_BINCODE => bincode::serialize(value).map_err(|e| e.to_string())?,
+        _ => return Err(format!("unknown format {}", format)),
+    };
+    let mut buf = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    buf.push(FRAME_MAGIC);
+    buf.push(format);
+    buf.extend_from_slice(&crc32(&payload).to_be_bytes());
+    buf.extend_from_slice(&payload);
+    return Ok(buf);
+}
+
+/// Decode a value written by `encode_value` in any supported format, or a legacy JSON value.
+pub fn decode_value<T: DeserializeOwned>(buf: &[u8]) -> Result<T, String> {
+    match buf.first() {
+        Some(&FRAME_MAGIC) => {
+            if buf.len() < FRAME_HEADER_LEN {
+                return Err("value is shorter than its header".to_string());
+            }
+            let format = buf[1];
+            let checksum = u32::from_be_bytes([buf[2], buf[3], buf[4], buf[5]]);
+            let payload = &buf[FRAME_HEADER_LEN..];
+            if crc32(payload) != checksum {
+                return Err("checksum mismatch".to_string());
+            }
+            return match format {
+                FORMAT_JSON => serde_json::from_slice(payload).map_err(|e| e.to_string()),
+                FORMAT_BINCODE => bincode::deserialize(payload).map_err(|e| e.to_string()),
+                _ => Err(format!("unknown format {}", format)),
+            };
+        }
+        // Legacy value without frame. JSON never starts with the magic byte.
+        _ => return serde_json::from_slice(buf).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn encode_entry(entry: &Entry<TypeConfig>) -> StorageResult<Vec<u8>> {
+    encode_value(entry).map_err(|e| {
+        StorageError::new(
+            ErrorSubject::Log(entry.log_id),
+            ErrorVerb::Write,
+            AnyError::error(e),
+        )
+    })
+}
+
+/// Decode the entry stored under `index`, verifying its checksum and that the entry
+/// really belongs to that index.
+pub fn decode_entry(index: u64, buf: &[u8]) -> StorageResult<Entry<TypeConfig>> {
+    let entry: Entry<TypeConfig> = decode_value(buf).map_err(|e| corrupted(index, e))?;
+    if entry.log_id.index != index {
+        return Err(corrupted(
+            index,
+            format!("entry is stored under a wrong index {}", entry.log_id.index),
+        ));
+    }
+    Ok(entry)
+}
+
+fn corrupted(index: u64, reason: String) -> StorageError<TypeConfig> {
+    StorageError::new(
+        ErrorSubject::LogIndex(index),
+        ErrorVerb::Read,
+        AnyError::error(format!("corrupted log entry {}: {}", index, reason)),
+    )
+}
+
+/// CRC-32 (IEEE 802.3), the same polynomial used by zlib and gzip.
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        crc32, decode_entry, decode_value, encode_entry, encode_value_with_format, FORMAT_BINCODE,
+        FORMAT_JSON, FORMAT_LEGACY_JSON, FRAME_HEADER_LEN,
+    };
+    use crate::openraft::route::{mqtt::MqttSession, AppRequestData};
+    use crate::{
+        placement::topic::PlacementTopic,
+        raft::node::{BrokerNode, NodeState},
+    };
+    use openraft::{CommittedLeaderId, Entry, EntryPayload, LogId, Vote};
+
+    fn entry(index: u64) -> Entry<crate::openraft::typeconfig::TypeConfig> {
+        Entry {
+            log_id: LogId::new(CommittedLeaderId::new(1, 1), index),
+            payload: EntryPayload::Normal(AppRequestData::Set {
+                key: "k1".to_string(),
+                value: "v1".to_string(),
+            }),
+        }
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn encode_decode_entry() {
+        let buf = encode_entry(&entry(3)).unwrap();
+        assert_eq!(buf[1], FORMAT_BINCODE);
+        assert_eq!(decode_entry(3, &buf).unwrap().log_id.index, 3);
+        assert!(decode_entry(4, &buf).is_err());
+
+        let mut damaged = buf.clone();
+        damaged[FRAME_HEADER_LEN + 2] ^= 0x01;
+        assert!(decode_entry(3, &damaged).is_err());
+        assert!(decode_entry(3, &buf[..3]).is_err());
+    }
+
+    #[test]
+    fn read_older_formats() {
+        for format in [FORMAT_LEGACY_JSON, FORMAT_JSON] {
+            let buf = encode_value_with_format(&entry(5), format).unwrap();
+            assert_eq!(decode_entry(5, &buf).unwrap().log_id.index, 5);
+        }
+
+        let vote = Vote::new(2, 1);
+        let legacy = serde_json::to_vec(&vote).unwrap();
+        assert_eq!(decode_value::<Vote<u64>>(&legacy).unwrap(), vote);
+    }
+
+    #[test]
+    fn request_variant_tags_are_stable() {
+        let name = "name".to_string();
+        // Every variant with the bincode tag it was first written with. A variant
+        // that moves breaks the decoding of the entries already in the log.
+        let requests = vec![
+            AppRequestData::Set {
+                key: name.clone(),
+                value: name.clone(),
+            },
+            AppRequestData::Delete { key: name.clone() },
+            AppRequestData::Batch { requests: vec![] },
+            AppRequestData::RegisterNode {
+                node: BrokerNode::default(),
+            },
+            AppRequestData::UnRegisterNode {
+                cluster_type: name.clone(),
+                cluster_name: name.clone(),
+                node_id: 1,
+            },
+            AppRequestData::UpdateNodeState {
+                cluster_type: name.clone(),
+                cluster_name: name.clone(),
+                node_id: 1,
+                state: NodeState::Running,
+            },
+            AppRequestData::CreateTopic {
+                topic: PlacementTopic::default(),
+            },
+            AppRequestData::ReassignPartition {
+                cluster_type: name.clone(),
+                cluster_name: name.clone(),
+                topic_name: name.clone(),
+                partition: 0,
+                replicas: vec![],
+            },
+            AppRequestData::Campaign {
+                name: name.clone(),
+                candidate: name.clone(),
+                lease_ms: 1,
+            },
+            AppRequestData::Resign {
+                name: name.clone(),
+                candidate: name.clone(),
+                epoch: 1,
+            },
+            AppRequestData::ExpireLeader {
+                name: name.clone(),
+                epoch: 1,
+            },
+            AppRequestData::Lock {
+                name: name.clone(),
+                owner: name.clone(),
+                ttl_ms: 1,
+                wait: false,
+            },
+            AppRequestData::Unlock {
+                name: name.clone(),
+                owner: name.clone(),
+                token: 1,
+            },
+            AppRequestData::CancelLockWait {
+                name: name.clone(),
+                owner: name.clone(),
+            },
+            AppRequestData::ExpireLock {
+                name: name.clone(),
+                token: 1,
+            },
+            AppRequestData::AllocateIds {
+                name: name.clone(),
+                count: 1,
+            },
+            AppRequestData::Incr {
+                key: name.clone(),
+                delta: 1,
+                floor: None,
+                ceiling: None,
+            },
+            AppRequestData::SaveMqttSession {
+                cluster_name: name.clone(),
+                session: MqttSession {
+                    client_id: name.clone(),
+                    clean_start: true,
+                    session_expiry_interval: 0,
+                    broker_id: 1,
+                    create_time: 0,
+                },
+            },
+            AppRequestData::DeleteMqttSession {
+                cluster_name: name.clone(),
+                client_id: name.clone(),
+            },
+            AppRequestData::MqttSubscribe {
+                cluster_name: name.clone(),
+                client_id: name.clone(),
+                subscriptions: vec![],
+            },
+            AppRequestData::MqttUnsubscribe {
+                cluster_name: name.clone(),
+                client_id: name.clone(),
+                filters: vec![],
+            },
+            AppRequestData::SaveMqttLastWill {
+                cluster_name: name.clone(),
+                client_id: name.clone(),
+                last_will: None,
+            },
+            AppRequestData::CommitOffsets { offsets: vec![] },
+            AppRequestData::ExpireOffsets {
+                groups: vec![],
+                before: 0,
+            },
+        ];
+
+        for (tag, request) in requests.iter().enumerate() {
+            let buf = bincode::serialize(request).unwrap();
+            assert_eq!(buf[..4], (tag as u32).to_le_bytes(), "{:?}", request);
+        }
+    }
+}
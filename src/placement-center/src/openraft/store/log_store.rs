@@ -1,19 +1,103 @@
-use super::{id_to_bin, StorageResult};
+use super::{
+    codec::{decode_entry, decode_value, encode_entry, encode_value},
+    id_to_bin, StorageResult,
+};
 use crate::openraft::{raft_node::NodeId, store::bin_to_id, typeconfig::TypeConfig};
+use log::{error, info};
 use openraft::{
     storage::{IOFlushed, RaftLogStorage},
     AnyError, Entry, ErrorSubject, ErrorVerb, LogId, LogState, OptionalSend, RaftLogReader,
     StorageError, Vote,
 };
 use rocksdb::{ColumnFamily, Direction, DB};
+use serde::{de::DeserializeOwned, Serialize};
 use std::{fmt::Debug, ops::RangeBounds, sync::Arc};
+use tokio::sync::watch;
+
+/// Key of the commit index lost by a log recovery, see [`RecoveryWatcher`].
+const RECOVERY_KEY: &[u8] = b"recovery_committed";
+
+/// The commit index this node knows of, from its own quorum as the leader or from the
+/// leader through replication.
+#[derive(Debug, Clone)]
+pub struct CommitWatcher {
+    sender: Arc<watch::Sender<Option<u64>>>,
+}
+
+impl CommitWatcher {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(None);
+        return CommitWatcher {
+            sender: Arc::new(sender),
+        };
+    }
+
+    pub fn committed(&self) -> Option<u64> {
+        return *self.sender.borrow();
+    }
+
+    fn update(&self, committed: &Option<LogId<NodeId>>) {
+        self.sender
+            .send_replace(committed.map(|log_id| log_id.index));
+    }
+}
+
+/// The commit index this node had acknowledged before recovery mode truncated its log
+/// below it. Until the node has replicated past that index again it may miss entries a
+/// quorum counted on, so it neither campaigns nor grants votes.
+#[derive(Debug, Clone)]
+pub struct RecoveryWatcher {
+    sender: Arc<watch::Sender<Option<u64>>>,
+}
+
+impl RecoveryWatcher {
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(None);
+        return RecoveryWatcher {
+            sender: Arc::new(sender),
+        };
+    }
+
+    pub fn pending(&self) -> Option<u64> {
+        return *self.sender.borrow();
+    }
+
+    /// Resolve once the node has replicated past the commit index it lost.
+    pub async fn recovered(&self) {
+        let mut receiver = self.sender.subscribe();
+        let _ = receiver.wait_for(|pending| pending.is_none()).await;
+    }
+
+    fn update(&self, pending: Option<u64>) {
+        self.sender.send_replace(pending);
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct LogStore {
     pub db: Arc<DB>,
+    commit: CommitWatcher,
+    recovery: RecoveryWatcher,
 }
 
 impl LogStore {
+    pub(crate) fn new(
+        db: Arc<DB>,
+        commit: CommitWatcher,
+        recovery: RecoveryWatcher,
+    ) -> StorageResult<Self> {
+        let log_store = LogStore {
+            db,
+            commit,
+            recovery,
+        };
+        log_store.commit.update(&log_store.get_committed_()?);
+        log_store
+            .recovery
+            .update(log_store.get_store_value_(RECOVERY_KEY, ErrorSubject::Store)?);
+        Ok(log_store)
+    }
+
     fn store(&self) -> &ColumnFamily {
         self.db.cf_handle("_raft_store").unwrap()
     }
@@ -33,64 +117,175 @@ impl LogStore {
         Ok(())
     }
 
-    fn get_last_purged_(&self) -> StorageResult<Option<LogId<u64>>> {
-        Ok(self
+    fn get_store_value_<T: DeserializeOwned>(
+        &self,
+        key: &[u8],
+        subject: ErrorSubject<TypeConfig>,
+    ) -> StorageResult<Option<T>> {
+        match self
             .db
-            .get_cf(self.store(), b"last_purged_log_id")
+            .get_cf(self.store(), key)
             .map_err(|e| StorageError::read(&e))?
-            .and_then(|v| serde_json::from_slice(&v).ok()))
+        {
+            Some(v) => decode_value(&v)
+                .map(Some)
+                .map_err(|e| StorageError::new(subject, ErrorVerb::Read, AnyError::error(e))),
+            None => Ok(None),
+        }
     }
 
-    fn set_last_purged_(&self, log_id: LogId<u64>) -> StorageResult<()> {
+    fn put_store_value_<T: Serialize>(
+        &self,
+        key: &[u8],
+        value: &T,
+        subject: ErrorSubject<TypeConfig>,
+    ) -> StorageResult<()> {
+        let buf = encode_value(value).map_err(|e| {
+            StorageError::new(subject.clone(), ErrorVerb::Write, AnyError::error(e))
+        })?;
         self.db
-            .put_cf(
-                self.store(),
-                b"last_purged_log_id",
-                serde_json::to_vec(&log_id).unwrap().as_slice(),
-            )
-            .map_err(|e| StorageError::write(&e))?;
-
-        self.flush(ErrorSubject::Store, ErrorVerb::Write)?;
+            .put_cf(self.store(), key, buf)
+            .map_err(|e| StorageError::new(subject.clone(), ErrorVerb::Write, AnyError::new(&e)))?;
+
+        self.flush(subject, ErrorVerb::Write)?;
         Ok(())
     }
 
+    fn get_last_purged_(&self) -> StorageResult<Option<LogId<u64>>> {
+        self.get_store_value_(b"last_purged_log_id", ErrorSubject::Store)
+    }
+
+    fn set_last_purged_(&self, log_id: LogId<u64>) -> StorageResult<()> {
+        self.put_store_value_(b"last_purged_log_id", &log_id, ErrorSubject::Store)
+    }
+
     fn set_committed_(
         &self,
         committed: &Option<LogId<NodeId>>,
     ) -> Result<(), StorageError<TypeConfig>> {
-        let json = serde_json::to_vec(committed).unwrap();
-
-        self.db
-            .put_cf(self.store(), b"committed", json)
-            .map_err(|e| StorageError::write(&e))?;
-
-        self.flush(ErrorSubject::Store, ErrorVerb::Write)?;
-        Ok(())
+        self.put_store_value_(b"committed", committed, ErrorSubject::Store)
     }
 
     fn get_committed_(&self) -> StorageResult<Option<LogId<NodeId>>> {
-        Ok(self
-            .db
-            .get_cf(self.store(), b"committed")
-            .map_err(|e| StorageError::read(&e))?
-            .and_then(|v| serde_json::from_slice(&v).ok()))
+        let committed: Option<Option<LogId<NodeId>>> =
+            self.get_store_value_(b"committed", ErrorSubject::Store)?;
+        Ok(committed.flatten())
     }
 
     fn set_vote_(&self, vote: &Vote<NodeId>) -> StorageResult<()> {
+        self.put_store_value_(b"vote", vote, ErrorSubject::Vote)
+    }
+
+    fn get_vote_(&self) -> StorageResult<Option<Vote<NodeId>>> {
+        self.get_store_value_(b"vote", ErrorSubject::Vote)
+    }
+
+    /// Drop every log entry and restart the log from `last_log_id`, which must
+    /// already be covered by the stored snapshot.
+    pub(crate) fn reset_to_(
+        &self,
+        last_log_id: LogId<NodeId>,
+        vote: &Vote<NodeId>,
+    ) -> StorageResult<()> {
+        let from = id_to_bin(0);
+        let to = id_to_bin(0xff_ff_ff_ff_ff_ff_ff_ff);
         self.db
-            .put_cf(self.store(), b"vote", serde_json::to_vec(vote).unwrap())
-            .map_err(|e| StorageError::write_vote(&e))?;
+            .delete_range_cf(self.logs(), &from, &to)
+            .map_err(|e| StorageError::write_logs(&e))?;
 
-        self.flush(ErrorSubject::Vote, ErrorVerb::Write)?;
-        Ok(())
+        self.set_last_purged_(last_log_id)?;
+        self.set_committed_(&Some(last_log_id))?;
+        self.set_vote_(vote)
     }
 
-    fn get_vote_(&self) -> StorageResult<Option<Vote<NodeId>>> {
-        Ok(self
+    /// Verify the log from its first entry and drop everything from the first entry that
+    /// is corrupted or does not follow its predecessor. Returns the index the log was
+    /// truncated at, or None when every entry is valid.
+    pub(crate) fn truncate_corrupted_(&self) -> StorageResult<Option<u64>> {
+        let mut last_valid = self.get_last_purged_()?;
+        let mut corrupted_at = None;
+        for res in self
             .db
-            .get_cf(self.store(), b"vote")
-            .map_err(|e| StorageError::write_vote(&e))?
-            .and_then(|v| serde_json::from_slice(&v).ok()))
+            .iterator_cf(self.logs(), rocksdb::IteratorMode::Start)
+        {
+            let (id, val) = res.map_err(|e| StorageError::read_logs(&e))?;
+            let index = bin_to_id(&id);
+            let in_sequence = match last_valid {
+                Some(log_id) => log_id.index + 1 == index,
+                None => true,
+            };
+            match decode_entry(index, &val) {
+                Ok(entry) if in_sequence => {
+                    last_valid = Some(entry.log_id);
+                }
+                _ => {
+                    corrupted_at = Some(index);
+                    break;
+                }
+            }
+        }
+
+        let index = match corrupted_at {
+            Some(index) => index,
+            None => return Ok(None),
+        };
+        let last_index = match self
+            .db
+            .iterator_cf(self.logs(), rocksdb::IteratorMode::End)
+            .next()
+        {
+            Some(res) => bin_to_id(&res.map_err(|e| StorageError::read_logs(&e))?.0),
+            None => index,
+        };
+
+        let from = id_to_bin(index);
+        let to = id_to_bin(0xff_ff_ff_ff_ff_ff_ff_ff);
+        self.db
+            .delete_range_cf(self.logs(), &from, &to)
+            .map_err(|e| StorageError::write_logs(&e))?;
+        self.flush(ErrorSubject::Logs, ErrorVerb::Delete)?;
+        error!(
+            "Log entries {}..={} were dropped, the entry at index {} is corrupted",
+            index, last_index, index
+        );
+
+        // The committed index may not point past the end of the log. Entries up to the
+        // old one were acknowledged to the leader, the node stays out of elections until
+        // they are replicated again.
+        if let Some(committed) = self.get_committed_()? {
+            if committed.index >= index {
+                error!(
+                    "Committed entries {}..={} were lost, the node will neither vote nor campaign until it has replicated index {} again",
+                    index, committed.index, committed.index
+                );
+                self.put_store_value_(RECOVERY_KEY, &committed.index, ErrorSubject::Store)?;
+                self.recovery.update(Some(committed.index));
+                self.set_committed_(&last_valid)?;
+            }
+        }
+        Ok(Some(index))
+    }
+
+    /// Lift the election ban of a recovered node once `committed` reaches the commit index
+    /// it lost.
+    fn check_recovered_(&self, committed: &Option<LogId<NodeId>>) -> StorageResult<()> {
+        let (pending, committed) = match (self.recovery.pending(), committed) {
+            (Some(pending), Some(committed)) => (pending, committed),
+            _ => return Ok(()),
+        };
+        if committed.index < pending {
+            return Ok(());
+        }
+        self.db.delete_cf(self.store(), RECOVERY_KEY).map_err(|e| {
+            StorageError::new(ErrorSubject::Store, ErrorVerb::Delete, AnyError::new(&e))
+        })?;
+        self.flush(ErrorSubject::Store, ErrorVerb::Delete)?;
+        self.recovery.update(None);
+        info!(
+            "Log recovered past the lost commit index {}, the node votes again",
+            pending
+        );
+        Ok(())
     }
 }
 
@@ -104,23 +299,19 @@ impl RaftLogReader<TypeConfig> for LogStore {
             std::ops::Bound::Excluded(x) => id_to_bin(*x + 1),
             std::ops::Bound::Unbounded => id_to_bin(0),
         };
-        self.db
-            .iterator_cf(
-                self.logs(),
-                rocksdb::IteratorMode::From(&start, Direction::Forward),
-            )
-            .map(|res| {
-                let (id, val) = res.unwrap();
-                let entry: StorageResult<Entry<_>> =
-                    serde_json::from_slice(&val).map_err(|e| StorageError::read_logs(&e));
-                let id = bin_to_id(&id);
-
-                assert_eq!(Ok(id), entry.as_ref().map(|e| e.log_id.index));
-                (id, entry)
-            })
-            .take_while(|(id, _)| range.contains(id))
-            .map(|x| x.1)
-            .collect()
+        let mut entries = Vec::new();
+        for res in self.db.iterator_cf(
+            self.logs(),
+            rocksdb::IteratorMode::From(&start, Direction::Forward),
+        ) {
+            let (id, val) = res.map_err(|e| StorageError::read_logs(&e))?;
+            let id = bin_to_id(&id);
+            if !range.contains(&id) {
+                break;
+            }
+            entries.push(decode_entry(id, &val)?);
+        }
+        Ok(entries)
     }
 
     async fn read_vote(&mut self) -> Result<Option<Vote<NodeId>>, StorageError<TypeConfig>> {
@@ -132,18 +323,17 @@ impl RaftLogStorage<TypeConfig> for LogStore {
     type LogReader = Self;
 
     async fn get_log_state(&mut self) -> StorageResult<LogState<TypeConfig>> {
-        let last = self
+        let last = match self
             .db
             .iterator_cf(self.logs(), rocksdb::IteratorMode::End)
             .next()
-            .and_then(|res| {
-                let (_, ent) = res.unwrap();
-                Some(
-                    serde_json::from_slice::<Entry<TypeConfig>>(&ent)
-                        .ok()?
-                        .log_id,
-                )
-            });
+        {
+            Some(res) => {
+                let (id, ent) = res.map_err(|e| StorageError::read_logs(&e))?;
+                Some(decode_entry(bin_to_id(&id), &ent)?.log_id)
+            }
+            None => None,
+        };
 
         let last_purged_log_id = self.get_last_purged_()?;
 
@@ -162,6 +352,8 @@ impl RaftLogStorage<TypeConfig> for LogStore {
         _committed: Option<LogId<NodeId>>,
     ) -> Result<(), StorageError<TypeConfig>> {
         self.set_committed_(&_committed)?;
+        self.commit.update(&_committed);
+        self.check_recovered_(&_committed)?;
         Ok(())
     }
 
@@ -185,11 +377,7 @@ impl RaftLogStorage<TypeConfig> for LogStore {
             let id = id_to_bin(entry.log_id.index);
             assert_eq!(bin_to_id(&id), entry.log_id.index);
             self.db
-                .put_cf(
-                    self.logs(),
-                    id,
-                    serde_json::to_vec(&entry).map_err(|e| StorageError::write_logs(&e))?,
-                )
+                .put_cf(self.logs(), id, encode_entry(&entry)?)
                 .map_err(|e| StorageError::write_logs(&e))?;
         }
 
@@ -225,3 +413,77 @@ impl RaftLogStorage<TypeConfig> for LogStore {
         self.clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::openraft::{route::AppRequestData, store::new_storage};
+    use common_base::{config::placement_center::RocksDB, tools::now_second};
+    use openraft::{
+        storage::{IOFlushed, RaftLogStorage},
+        CommittedLeaderId, Entry, EntryPayload, LogId, RaftLogReader,
+    };
+
+    use super::{id_to_bin, CommitWatcher, LogStore, RecoveryWatcher};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn corrupted_entry_recovery() {
+        let path = format!("/tmp/tmp_test_log_store/{}", now_second());
+        let (mut log_store, _) = new_storage(&path, &RocksDB::default()).await;
+
+        let entries: Vec<Entry<_>> = (1..=3)
+            .map(|index| Entry {
+                log_id: LogId::new(CommittedLeaderId::new(1, 1), index),
+                payload: EntryPayload::Normal(AppRequestData::Set {
+                    key: format!("k{}", index),
+                    value: "v".to_string(),
+                }),
+            })
+            .collect();
+        log_store.append(entries, IOFlushed::noop()).await.unwrap();
+        log_store
+            .save_committed(Some(LogId::new(CommittedLeaderId::new(1, 1), 3)))
+            .await
+            .unwrap();
+
+        log_store
+            .db
+            .put_cf(log_store.logs(), id_to_bin(2), b"garbage")
+            .unwrap();
+        assert!(log_store.try_get_log_entries(1..=3).await.is_err());
+
+        assert_eq!(log_store.truncate_corrupted_().unwrap(), Some(2));
+        assert_eq!(log_store.try_get_log_entries(1..).await.unwrap().len(), 1);
+        assert_eq!(
+            log_store.get_committed_().unwrap().map(|id| id.index),
+            Some(1)
+        );
+        assert_eq!(log_store.truncate_corrupted_().unwrap(), None);
+
+        // The lost commit index survives a restart and keeps the node out of elections
+        // until it is committed again.
+        assert_eq!(log_store.recovery.pending(), Some(3));
+        let reopened =
+            LogStore::new(log_store.db.clone(), CommitWatcher::new(), RecoveryWatcher::new())
+                .unwrap();
+        assert_eq!(reopened.recovery.pending(), Some(3));
+
+        log_store
+            .save_committed(Some(LogId::new(CommittedLeaderId::new(2, 2), 2)))
+            .await
+            .unwrap();
+        assert_eq!(log_store.recovery.pending(), Some(3));
+        log_store
+            .save_committed(Some(LogId::new(CommittedLeaderId::new(2, 2), 3)))
+            .await
+            .unwrap();
+        assert_eq!(log_store.recovery.pending(), None);
+        tokio::time::timeout(Duration::from_secs(1), log_store.recovery.recovered())
+            .await
+            .unwrap();
+        let reopened =
+            LogStore::new(log_store.db.clone(), CommitWatcher::new(), RecoveryWatcher::new())
+                .unwrap();
+        assert_eq!(reopened.recovery.pending(), None);
+    }
+}
@@ -1,14 +1,16 @@
+use std::collections::BTreeMap;
 use std::path::Path;
 use std::sync::Arc;
 
 use super::typeconfig::TypeConfig;
+use crate::storage::rocksdb::{block_cache, open_cf_opts, open_db_opts};
 use byteorder::BigEndian;
 use byteorder::ReadBytesExt;
 use byteorder::WriteBytesExt;
 use log_store::LogStore;
+use common_base::config::placement_center::RocksDB;
 use openraft::{SnapshotMeta, StorageError};
 use rocksdb::ColumnFamilyDescriptor;
-use rocksdb::Options;
 use rocksdb::DB;
 use serde::{Deserialize, Serialize};
 use state_machine_store::StateMachineStore;
@@ -18,38 +20,70 @@ pub struct StoredSnapshot {
 
     /// The data of the state machine at the time of this snapshot.
     pub data: Vec<u8>,
+
+    /// Log index each key was last written at. Only kept locally, a snapshot sent to
+    /// another node carries `data` alone and its keys report the snapshot index.
+    #[serde(default)]
+    pub revisions: BTreeMap<String, u64>,
 }
 
 type StorageResult<T> = Result<T, StorageError<TypeConfig>>;
 
+pub const CF_RAFT_STORE: &str = "_raft_store";
+pub const CF_RAFT_LOGS: &str = "_raft_logs";
+
+pub mod codec;
 pub mod log_store;
 pub mod state_machine_store;
 
 /// converts an id to a byte vector for storing in the database.
 /// Note that we're using big endian encoding to ensure correct sorting of keys
-fn id_to_bin(id: u64) -> Vec<u8> {
+pub(crate) fn id_to_bin(id: u64) -> Vec<u8> {
     let mut buf = Vec::with_capacity(8);
     buf.write_u64::<BigEndian>(id).unwrap();
     buf
 }
 
-fn bin_to_id(buf: &[u8]) -> u64 {
+pub(crate) fn bin_to_id(buf: &[u8]) -> u64 {
     (&buf[0..8]).read_u64::<BigEndian>().unwrap()
 }
 
-pub(crate) async fn new_storage<P: AsRef<Path>>(db_path: P) -> (LogStore, StateMachineStore) {
-    let mut db_opts = Options::default();
-    db_opts.create_missing_column_families(true);
-    db_opts.create_if_missing(true);
+pub(crate) async fn new_storage<P: AsRef<Path>>(
+    db_path: P,
+    config: &RocksDB,
+) -> (LogStore, StateMachineStore) {
+    let db_opts = open_db_opts(config);
 
-    let store = ColumnFamilyDescriptor::new("_raft_store", Options::default());
-    let logs = ColumnFamilyDescriptor::new("_raft_logs", Options::default());
+    let cache = block_cache(config);
+    let store = ColumnFamilyDescriptor::new(
+        CF_RAFT_STORE,
+        open_cf_opts(config, CF_RAFT_STORE, &cache).unwrap(),
+    );
+    let logs = ColumnFamilyDescriptor::new(
+        CF_RAFT_LOGS,
+        open_cf_opts(config, CF_RAFT_LOGS, &cache).unwrap(),
+    );
 
     let db = DB::open_cf_descriptors(&db_opts, db_path, vec![store, logs]).unwrap();
     let db = Arc::new(db);
 
-    let log_store = LogStore { db: db.clone() };
-    let sm_store = StateMachineStore::new(db).await.unwrap();
+    let sm_store = StateMachineStore::new(db.clone()).await.unwrap();
+    let log_store = LogStore::new(
+        db,
+        sm_store.data.watchers.commit.clone(),
+        sm_store.data.watchers.recovery.clone(),
+    )
+    .unwrap();
 
     (log_store, sm_store)
 }
+
+/// Recovery mode for a stopped node whose log fails verification: the log is truncated
+/// back to the last valid entry, and the node catches up from the leader after restart.
+pub async fn recover_log_store<P: AsRef<Path>>(
+    db_path: P,
+    config: &RocksDB,
+) -> StorageResult<Option<u64>> {
+    let (log_store, _) = new_storage(db_path, config).await;
+    log_store.truncate_corrupted_()
+}
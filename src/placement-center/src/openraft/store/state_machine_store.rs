@@ -8,7 +8,10 @@ use tokio::sync::RwLock;
 
 use crate::openraft::{
     raft_node::{typ, NodeId},
-    route::{AppRequestData, AppResponseData},
+    route::{
+        cluster, counter, election, kvs::StateKvs, lock, mqtt, offset, sequence, topic,
+        AppRequestData, AppResponseData, StateWatchers,
+    },
     typeconfig::{SnapshotData, TypeConfig},
 };
 
@@ -35,7 +38,10 @@ pub struct StateMachineData {
     pub last_membership: StoredMembership<TypeConfig>,
 
     /// State built from applying the raft logs
-    pub kvs: Arc<RwLock<BTreeMap<String, String>>>,
+    pub kvs: Arc<RwLock<StateKvs>>,
+
+    /// Receive the changes as they are applied.
+    pub watchers: StateWatchers,
 }
 
 impl RaftSnapshotBuilder<TypeConfig> for StateMachineStore {
@@ -43,9 +49,11 @@ impl RaftSnapshotBuilder<TypeConfig> for StateMachineStore {
         let last_applied_log = self.data.last_applied_log_id;
         let last_membership = self.data.last_membership.clone();
 
-        let kv_json = {
+        let (kv_json, revisions) = {
             let kvs = self.data.kvs.read().await;
-            serde_json::to_vec(&*kvs).map_err(|e| StorageError::read_state_machine(&e))?
+            let kv_json =
+                serde_json::to_vec(kvs.kvs()).map_err(|e| StorageError::read_state_machine(&e))?;
+            (kv_json, kvs.revisions().clone())
         };
 
         let snapshot_id = if let Some(last) = last_applied_log {
@@ -63,6 +71,7 @@ impl RaftSnapshotBuilder<TypeConfig> for StateMachineStore {
         let snapshot = StoredSnapshot {
             meta: meta.clone(),
             data: kv_json.clone(),
+            revisions,
         };
 
         self.set_current_snapshot_(snapshot)?;
@@ -81,6 +90,7 @@ impl StateMachineStore {
                 last_applied_log_id: None,
                 last_membership: Default::default(),
                 kvs: Arc::new(Default::default()),
+                watchers: StateWatchers::new(),
             },
             snapshot_idx: 0,
             db,
@@ -103,8 +113,12 @@ impl StateMachineStore {
 
         self.data.last_applied_log_id = snapshot.meta.last_log_id;
         self.data.last_membership = snapshot.meta.last_membership.clone();
+        let base_revision = match snapshot.meta.last_log_id {
+            Some(log_id) => log_id.index,
+            None => 0,
+        };
         let mut x = self.data.kvs.write().await;
-        *x = kvs;
+        *x = StateKvs::restore(kvs, snapshot.revisions, base_revision);
 
         Ok(())
     }
@@ -175,28 +189,20 @@ impl RaftStateMachine<TypeConfig> for StateMachineStore {
         for ent in entries {
             self.data.last_applied_log_id = Some(ent.log_id);
 
-            let mut resp_value = None;
-
             match ent.payload {
-                EntryPayload::Blank => {}
-                EntryPayload::Normal(req) => match req {
-                    AppRequestData::Set { key, value } => {
-                        resp_value = Some(value.clone());
-
-                        let mut st = self.data.kvs.write().await;
-                        st.insert(key, value);
-                    }
-                    AppRequestData::Delete { key } => {
-                        let mut st = self.data.kvs.write().await;
-                        st.remove(&key);
-                    }
-                },
+                EntryPayload::Blank => {
+                    replies.push(AppResponseData::default());
+                }
+                EntryPayload::Normal(req) => {
+                    let mut st = self.data.kvs.write().await;
+                    st.set_revision(ent.log_id.index);
+                    replies.push(apply_request(&mut st, &self.data.watchers, req));
+                }
                 EntryPayload::Membership(mem) => {
                     self.data.last_membership = StoredMembership::new(Some(ent.log_id), mem);
+                    replies.push(AppResponseData::default());
                 }
             }
-
-            replies.push(AppResponseData { value: resp_value });
         }
         Ok(replies)
     }
@@ -220,6 +226,7 @@ impl RaftStateMachine<TypeConfig> for StateMachineStore {
         let new_snapshot = StoredSnapshot {
             meta: meta.clone(),
             data: snapshot.into_inner(),
+            revisions: BTreeMap::new(),
         };
 
         self.update_state_machine_(new_snapshot.clone()).await?;
@@ -239,3 +246,164 @@ impl RaftStateMachine<TypeConfig> for StateMachineStore {
         }))
     }
 }
+
+pub(crate) fn apply_request(
+    kvs: &mut StateKvs,
+    watchers: &StateWatchers,
+    req: AppRequestData,
+) -> AppResponseData {
+    match req {
+        AppRequestData::Set { key, value } => {
+            kvs.insert(key, value.clone());
+            return AppResponseData {
+                value: Some(value),
+                ..Default::default()
+            };
+        }
+        AppRequestData::Delete { key } => {
+            kvs.remove(&key);
+            return AppResponseData::default();
+        }
+        AppRequestData::Incr {
+            key,
+            delta,
+            floor,
+            ceiling,
+        } => {
+            return counter::incr(kvs, key, delta, floor, ceiling);
+        }
+        AppRequestData::RegisterNode { node } => {
+            return cluster::register_node(kvs, &watchers.node, node);
+        }
+        AppRequestData::UnRegisterNode {
+            cluster_type,
+            cluster_name,
+            node_id,
+        } => {
+            return cluster::unregister_node(
+                kvs,
+                &watchers.node,
+                &cluster_type,
+                &cluster_name,
+                node_id,
+            );
+        }
+        AppRequestData::UpdateNodeState {
+            cluster_type,
+            cluster_name,
+            node_id,
+            state,
+        } => {
+            return cluster::update_node_state(
+                kvs,
+                &watchers.node,
+                &cluster_type,
+                &cluster_name,
+                node_id,
+                state,
+            );
+        }
+        AppRequestData::CreateTopic { topic: placement } => {
+            return topic::create_topic(kvs, placement);
+        }
+        AppRequestData::ReassignPartition {
+            cluster_type,
+            cluster_name,
+            topic_name,
+            partition,
+            replicas,
+        } => {
+            return topic::reassign_partition(
+                kvs,
+                &cluster_type,
+                &cluster_name,
+                &topic_name,
+                partition,
+                replicas,
+            );
+        }
+        AppRequestData::Campaign {
+            name,
+            candidate,
+            lease_ms,
+        } => {
+            return election::campaign(kvs, &watchers.election, &name, &candidate, lease_ms);
+        }
+        AppRequestData::Resign {
+            name,
+            candidate,
+            epoch,
+        } => {
+            return election::release(kvs, &watchers.election, &name, Some(&candidate), epoch);
+        }
+        AppRequestData::ExpireLeader { name, epoch } => {
+            return election::release(kvs, &watchers.election, &name, None, epoch);
+        }
+        AppRequestData::Lock {
+            name,
+            owner,
+            ttl_ms,
+            wait,
+        } => {
+            return lock::lock(kvs, &watchers.lock, &name, &owner, ttl_ms, wait);
+        }
+        AppRequestData::Unlock { name, owner, token } => {
+            return lock::unlock(kvs, &watchers.lock, &name, Some(&owner), token);
+        }
+        AppRequestData::CancelLockWait { name, owner } => {
+            return lock::cancel_wait(kvs, &watchers.lock, &name, &owner);
+        }
+        AppRequestData::ExpireLock { name, token } => {
+            return lock::unlock(kvs, &watchers.lock, &name, None, token);
+        }
+        AppRequestData::AllocateIds { name, count } => {
+            return sequence::allocate_ids(kvs, &name, count);
+        }
+        AppRequestData::SaveMqttSession {
+            cluster_name,
+            session,
+        } => {
+            return mqtt::save_session(kvs, &cluster_name, session);
+        }
+        AppRequestData::DeleteMqttSession {
+            cluster_name,
+            client_id,
+        } => {
+            return mqtt::delete_session(kvs, &cluster_name, &client_id);
+        }
+        AppRequestData::MqttSubscribe {
+            cluster_name,
+            client_id,
+            subscriptions,
+        } => {
+            return mqtt::subscribe(kvs, &cluster_name, &client_id, subscriptions);
+        }
+        AppRequestData::MqttUnsubscribe {
+            cluster_name,
+            client_id,
+            filters,
+        } => {
+            return mqtt::unsubscribe(kvs, &cluster_name, &client_id, filters);
+        }
+        AppRequestData::SaveMqttLastWill {
+            cluster_name,
+            client_id,
+            last_will,
+        } => {
+            return mqtt::save_last_will(kvs, &cluster_name, &client_id, last_will);
+        }
+        AppRequestData::CommitOffsets { offsets } => {
+            return offset::commit_offsets(kvs, offsets);
+        }
+        AppRequestData::ExpireOffsets { groups, before } => {
+            return offset::expire_groups(kvs, groups, before);
+        }
+        AppRequestData::Batch { requests } => {
+            let batch = requests
+                .into_iter()
+                .map(|req| apply_request(kvs, watchers, req))
+                .collect();
+            return AppResponseData { value: None, batch };
+        }
+    }
+}
@@ -0,0 +1,211 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use common_base::errors::RobustMQError;
+
+use crate::raft::node::BrokerNode;
+
+use super::topic::{PartitionAssignment, PlacementTopic};
+
+/// Replicas a broker node hosts, and how many of them it is the preferred leader of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeLoad {
+    pub replicas: u32,
+    pub leaders: u32,
+}
+
+/// Load of every node that hosts a replica of `topics`.
+pub fn node_loads<'a>(
+    topics: impl IntoIterator<Item = &'a PlacementTopic>,
+) -> BTreeMap<u64, NodeLoad> {
+    let mut loads: BTreeMap<u64, NodeLoad> = BTreeMap::new();
+    for topic in topics {
+        for assignment in topic.partitions.iter() {
+            add_load(&mut loads, &assignment.replicas);
+        }
+    }
+    return loads;
+}
+
+pub fn add_load(loads: &mut BTreeMap<u64, NodeLoad>, replicas: &[u64]) {
+    for (i, node_id) in replicas.iter().enumerate() {
+        let load = loads.entry(*node_id).or_default();
+        load.replicas += 1;
+        if i == 0 {
+            load.leaders += 1;
+        }
+    }
+}
+
+pub fn remove_load(loads: &mut BTreeMap<u64, NodeLoad>, replicas: &[u64]) {
+    for (i, node_id) in replicas.iter().enumerate() {
+        if let Some(load) = loads.get_mut(node_id) {
+            load.replicas = load.replicas.saturating_sub(1);
+            if i == 0 {
+                load.leaders = load.leaders.saturating_sub(1);
+            }
+        }
+    }
+}
+
+/// Assign the replicas of every partition of a new topic, updating `loads` as it goes.
+pub fn assign_topic(
+    brokers: &[BrokerNode],
+    loads: &mut BTreeMap<u64, NodeLoad>,
+    partition_num: u32,
+    replication_factor: u32,
+) -> Result<Vec<PartitionAssignment>, RobustMQError> {
+    let mut partitions = Vec::with_capacity(partition_num as usize);
+    for partition in 0..partition_num {
+        let replicas = assign_partition(brokers, loads, replication_factor)?;
+        partitions.push(PartitionAssignment {
+            partition,
+            replicas,
+        });
+    }
+    return Ok(partitions);
+}
+
+/// Pick `replication_factor` distinct nodes for one partition and add them to `loads`.
+/// Every replica goes to a rack the partition does not use yet while there is one, then
+/// to the node with the fewest replicas. Nodes without a rack never conflict.
+pub fn assign_partition(
+    brokers: &[BrokerNode],
+    loads: &mut BTreeMap<u64, NodeLoad>,
+    replication_factor: u32,
+) -> Result<Vec<u64>, RobustMQError> {
+    if brokers.is_empty() {
+        return Err(RobustMQError::ClusterNoAvailableNode);
+    }
+    if replication_factor == 0 || replication_factor as usize > brokers.len() {
+        return Err(RobustMQError::CommmonError(format!(
+            "Replication factor {} is not possible with {} broker nodes",
+            replication_factor,
+            brokers.len()
+        )));
+    }
+
+    let mut replicas: Vec<u64> = Vec::with_capacity(replication_factor as usize);
+    let mut racks = BTreeSet::new();
+    while replicas.len() < replication_factor as usize {
+        let leader = replicas.is_empty();
+        let candidate = brokers
+            .iter()
+            .filter(|broker| !replicas.contains(&broker.node_id))
+            .min_by_key(|broker| {
+                let load = loads.get(&broker.node_id).copied().unwrap_or_default();
+                let rack_used = match broker.rack() {
+                    Some(rack) => racks.contains(&rack),
+                    None => false,
+                };
+                // Among equally loaded nodes the leader goes to the one leading the
+                // fewest partitions, the followers to the ones leading the most, which
+                // keeps the others free to lead the next partitions.
+                let leaders = if leader {
+                    load.leaders as i64
+                } else {
+                    -(load.leaders as i64)
+                };
+                return (rack_used, load.replicas, leaders, broker.node_id);
+            });
+        let candidate = match candidate {
+            Some(candidate) => candidate,
+            None => break,
+        };
+        if let Some(rack) = candidate.rack() {
+            racks.insert(rack);
+        }
+        replicas.push(candidate.node_id);
+    }
+
+    add_load(loads, &replicas);
+    return Ok(replicas);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{assign_partition, assign_topic, node_loads, remove_load};
+    use crate::{placement::topic::PlacementTopic, raft::node::BrokerNode};
+
+    fn broker(node_id: u64, rack: &str) -> BrokerNode {
+        return BrokerNode {
+            node_id,
+            extend: format!("{{\"rack\":\"{}\"}}", rack),
+            ..Default::default()
+        };
+    }
+
+    #[test]
+    fn replicas_and_leaders_are_balanced() {
+        let brokers: Vec<BrokerNode> = (1..=4).map(|id| broker(id, "a")).collect();
+        let mut loads = BTreeMap::new();
+        let partitions = assign_topic(&brokers, &mut loads, 8, 2).unwrap();
+
+        assert_eq!(partitions.len(), 8);
+        for assignment in partitions.iter() {
+            let distinct: BTreeSet<u64> = assignment.replicas.iter().copied().collect();
+            assert_eq!(distinct.len(), 2);
+        }
+        for id in 1..=4 {
+            assert_eq!(loads[&id].replicas, 4);
+            assert_eq!(loads[&id].leaders, 2);
+        }
+
+        // A topic placed later starts on the least loaded nodes.
+        let topic = PlacementTopic {
+            partitions,
+            ..Default::default()
+        };
+        let mut loads = node_loads([&topic]);
+        remove_load(&mut loads, &topic.partitions[0].replicas);
+        let replicas = assign_partition(&brokers, &mut loads, 2).unwrap();
+        let mut expected = topic.partitions[0].replicas.clone();
+        expected.sort();
+        let mut replicas_sorted = replicas.clone();
+        replicas_sorted.sort();
+        assert_eq!(replicas_sorted, expected);
+    }
+
+    #[test]
+    fn replicas_spread_over_racks() {
+        let brokers = vec![
+            broker(1, "a"),
+            broker(2, "a"),
+            broker(3, "b"),
+            broker(4, "b"),
+            broker(5, "c"),
+        ];
+        let mut loads = BTreeMap::new();
+        for assignment in assign_topic(&brokers, &mut loads, 10, 3).unwrap() {
+            let racks: BTreeSet<String> = assignment
+                .replicas
+                .iter()
+                .map(|id| brokers[*id as usize - 1].rack().unwrap())
+                .collect();
+            assert_eq!(racks.len(), 3);
+        }
+    }
+
+    #[test]
+    fn not_enough_brokers() {
+        let brokers = vec![broker(1, "a"), broker(2, "b")];
+        assert!(assign_partition(&brokers, &mut BTreeMap::new(), 3).is_err());
+        assert!(assign_partition(&brokers, &mut BTreeMap::new(), 0).is_err());
+        assert!(assign_partition(&[], &mut BTreeMap::new(), 1).is_err());
+    }
+}
@@ -0,0 +1,43 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use serde::{Deserialize, Serialize};
+
+/// Replicas of one partition. The first replica is the preferred leader.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PartitionAssignment {
+    pub partition: u32,
+    pub replicas: Vec<u64>,
+}
+
+/// A topic placed by the placement center, with the replicas of every partition.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PlacementTopic {
+    pub cluster_type: String,
+    pub cluster_name: String,
+    pub topic_name: String,
+    pub partition_num: u32,
+    pub replication_factor: u32,
+    pub partitions: Vec<PartitionAssignment>,
+    pub create_time: u128,
+}
+
+impl PlacementTopic {
+    pub fn partition(&self, partition: u32) -> Option<&PartitionAssignment> {
+        return self
+            .partitions
+            .iter()
+            .find(|assignment| assignment.partition == partition);
+    }
+}
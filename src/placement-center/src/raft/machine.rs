@@ -20,14 +20,14 @@ use crate::raft::metadata::RaftGroupMetadata;
 use crate::raft::peer::PeerMessage;
 use crate::storage::raft::RaftMachineStorage;
 use bincode::{deserialize, serialize};
-use common_base::config::placement_center::placement_center_conf;
+use common_base::config::placement_center::{placement_center_conf, RaftConfig};
 use log::{error, info};
 use prost::Message as _;
 use raft::eraftpb::{
     ConfChange, ConfChangeType, Entry, EntryType, Message as raftPreludeMessage, MessageType,
     Snapshot,
 };
-use raft::{Config, RawNode};
+use raft::{Config, RawNode, ReadOnlyOption};
 use slog::o;
 use slog::Drain;
 use std::collections::HashMap;
@@ -40,6 +40,10 @@ use tokio::sync::mpsc::{Receiver, Sender};
 use tokio::sync::{broadcast, oneshot};
 use tokio::time::timeout;
 
+/// Interval at which the run loop ticks the raft-rs node. Millisecond settings in the
+/// `[raft]` section are converted to ticks of this length.
+pub const RAFT_TICK_INTERVAL_MS: u64 = 100;
+
 pub struct RaftMachine {
     placement_cluster: Arc<RwLock<RaftGroupMetadata>>,
     receiver: Receiver<RaftMessage>,
@@ -79,7 +83,7 @@ impl RaftMachine {
 
     pub async fn run(&mut self) {
         let mut raft_node: RawNode<RaftRocksDBStorage> = self.new_node().await;
-        let heartbeat = Duration::from_millis(100);
+        let heartbeat = Duration::from_millis(RAFT_TICK_INTERVAL_MS);
         let mut now = Instant::now();
         loop {
             match self.stop_recv.try_recv() {
@@ -345,27 +349,7 @@ impl RaftMachine {
 
     fn build_config(&self, apply: u64) -> Config {
         let conf = placement_center_conf();
-        Config {
-            // The unique ID for the Raft node.
-            // id: self.config.node_id,
-            id: conf.node_id,
-            // Election tick is for how long the follower may campaign again after
-            // it doesn't receive any message from the leader.
-            election_tick: 10,
-            // Heartbeat tick is for how long the leader needs to send
-            // a heartbeat to keep alive.
-            heartbeat_tick: 3,
-            // The max size limits the max size of each appended message. Mostly, 1 MB is enough.
-            max_size_per_msg: 1024 * 1024 * 1024,
-            // Max inflight msgs that the leader sends messages to follower without
-            // receiving ACKs.
-            max_inflight_msgs: 256,
-            // The Raft applied index.
-            // You need to save your applied index when you apply the committed Raft logs.
-            applied: apply,
-            // check_quorum: true,
-            ..Default::default()
-        }
+        return build_raft_config(&conf.raft, conf.node_id, apply);
     }
 
     fn build_slog(&self) -> slog::Logger {
@@ -423,3 +407,67 @@ impl RaftMachine {
         }
     }
 }
+
+fn to_ticks(ms: u64) -> usize {
+    return ms.div_ceil(RAFT_TICK_INTERVAL_MS).max(1) as usize;
+}
+
+pub fn build_raft_config(raft_conf: &RaftConfig, node_id: u64, apply: u64) -> Config {
+    let heartbeat_tick = to_ticks(raft_conf.heartbeat_interval_ms);
+    // raft-rs requires the election tick to be larger than the heartbeat tick, and
+    // picks the randomized election timeout from [min_election_tick, max_election_tick).
+    let election_tick = to_ticks(raft_conf.election_timeout_min_ms).max(heartbeat_tick + 1);
+    let max_election_tick = to_ticks(raft_conf.election_timeout_max_ms).max(election_tick + 1);
+    Config {
+        // The unique ID for the Raft node.
+        id: node_id,
+        // Election tick is for how long the follower may campaign again after
+        // it doesn't receive any message from the leader.
+        election_tick,
+        min_election_tick: election_tick,
+        max_election_tick,
+        // Heartbeat tick is for how long the leader needs to send
+        // a heartbeat to keep alive.
+        heartbeat_tick,
+        // The max size limits the max size of each appended message. Mostly, 1 MB is enough.
+        max_size_per_msg: 1024 * 1024 * 1024,
+        // Max inflight msgs that the leader sends messages to follower without
+        // receiving ACKs.
+        max_inflight_msgs: 256,
+        // The Raft applied index.
+        // You need to save your applied index when you apply the committed Raft logs.
+        applied: apply,
+        // A rejoining node first asks whether it could win an election, so it cannot
+        // bump the term of a healthy cluster.
+        pre_vote: true,
+        // The leader steps down when it loses contact with a quorum.
+        check_quorum: true,
+        read_only_option: ReadOnlyOption::Safe,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::build_raft_config;
+    use common_base::config::placement_center::RaftConfig;
+
+    #[test]
+    fn build_raft_config_test() {
+        let raft_conf = RaftConfig::default();
+        let config = build_raft_config(&raft_conf, 1, 0);
+        assert!(config.validate().is_ok());
+        assert_eq!(config.heartbeat_tick, 3);
+        assert_eq!(config.election_tick, 10);
+        assert_eq!(config.max_election_tick, 20);
+        assert!(config.pre_vote);
+        assert!(config.check_quorum);
+
+        let raft_conf = RaftConfig {
+            heartbeat_interval_ms: 10,
+            election_timeout_min_ms: 20,
+            election_timeout_max_ms: 30,
+        };
+        assert!(build_raft_config(&raft_conf, 1, 0).validate().is_ok());
+    }
+}
@@ -18,18 +18,7 @@ use protocol::common::ClusterType;
 use raft::StateRole;
 use std::collections::HashMap;
 
-use super::node::BrokerNode;
-
-#[derive(PartialEq, Default, Debug, Eq, PartialOrd, Ord, Clone)]
-pub enum NodeState {
-    #[default]
-    Running,
-    Starting,
-    #[allow(dead_code)]
-    Stoping,
-    #[allow(dead_code)]
-    Stop,
-}
+use super::node::{BrokerNode, NodeState};
 
 #[derive(Clone, Default, Debug)]
 pub struct RaftGroupMetadata {
@@ -51,14 +40,18 @@ impl RaftGroupMetadata {
         local.node_id = config.node_id;
 
         let mut peers = HashMap::new();
-        for (node_id, addr) in config.nodes.clone() {
-            let (ip, _) = addr.as_str().unwrap().split_once(":").unwrap();
-            let id: u64 = node_id.to_string().trim().parse().unwrap();
+        // raft-rs has no learners here, only voters take part.
+        for configured in config.configured_nodes().unwrap() {
+            if configured.learner {
+                continue;
+            }
+            let (ip, _) = configured.addr.split_once(":").unwrap();
+            let id = configured.node_id;
             let mut node = BrokerNode::default();
 
             node.cluster_type = ClusterType::PlacementCenter.as_str_name().to_string();
             node.cluster_name = config.cluster_name.clone();
-            node.node_inner_addr = addr.to_string();
+            node.node_inner_addr = configured.addr.clone();
             node.node_ip = ip.to_string();
             node.node_id = id;
             peers.insert(id, node);
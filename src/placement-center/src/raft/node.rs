@@ -14,6 +14,18 @@
 
 use serde::{Deserialize, Serialize};
 
+/// Lifecycle of a node. For broker nodes it is persisted with the node record: a node is
+/// `Starting` once registered, `Running` from its first heartbeat, and `Stop` when it is
+/// declared dead, after which the record is removed.
+#[derive(PartialEq, Default, Debug, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
+pub enum NodeState {
+    #[default]
+    Running,
+    Starting,
+    Stoping,
+    Stop,
+}
+
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct BrokerNode {
     pub cluster_name: String,
@@ -23,10 +35,21 @@ pub struct BrokerNode {
     pub node_inner_addr: String,
     pub extend: String,
     pub create_time: u128,
+    #[serde(default)]
+    pub state: NodeState,
 }
 
 impl BrokerNode {
     pub fn encode(&self) -> Vec<u8> {
         return serde_json::to_vec(&self).unwrap();
     }
+
+    /// Rack of the node, taken from the `rack` field when `extend` holds a JSON object.
+    pub fn rack(&self) -> Option<String> {
+        let extend: serde_json::Value = serde_json::from_str(&self.extend).ok()?;
+        return extend
+            .get("rack")?
+            .as_str()
+            .map(|rack| rack.to_string());
+    }
 }
\ No newline at end of file
@@ -13,7 +13,13 @@
 // limitations under the License.
 
 pub mod server;
+mod services_cluster;
+mod services_election;
+mod services_id;
 mod services_kv;
+mod services_lock;
+mod services_mqtt;
+mod services_offset;
 mod services_openraft;
 mod services_raft;
-mod services_kv_new;
+mod services_topic;
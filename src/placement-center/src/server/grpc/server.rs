@@ -13,13 +13,23 @@
 // limitations under the License.
 
 use crate::{
-    openraft::typeconfig::TypeConfig,
+    cluster::heartbeat::HeartbeatTracker,
+    lease::tracker::LeaseTracker,
+    openraft::{
+        group_commit::GroupCommitter,
+        network::identity::ClusterIdentity,
+        route::{kvs::StateKvs, StateWatchers},
+        typeconfig::TypeConfig,
+    },
     raft::{apply::RaftMachineApply, metadata::RaftGroupMetadata},
     server::grpc::{
-        services_kv::GrpcKvServices, services_openraft::GrpcOpenRaftServices,
-        services_raft::GrpcRaftServices,
+        services_cluster::GrpcClusterServices, services_election::GrpcElectionServices,
+        services_id::GrpcIdServices, services_kv::GrpcKvServices, services_lock::GrpcLockServices,
+        services_mqtt::GrpcMqttServices, services_offset::GrpcOffsetServices,
+        services_openraft::GrpcOpenRaftServices, services_raft::GrpcRaftServices,
+        services_topic::GrpcTopicServices,
     },
-    storage::rocksdb::RocksDBEngine,
+    storage::metrics::StorageMetricsRegistry,
 };
 
 use clients::poll::ClientPool;
@@ -27,20 +37,34 @@ use common_base::config::placement_center::placement_center_conf;
 use log::info;
 use openraft::Raft;
 use protocol::{
-    kv::kv_service_server::KvServiceServer,
+    cluster::cluster_service_server::ClusterServiceServer,
+    election::election_service_server::ElectionServiceServer,
+    id::id_service_server::IdServiceServer, kv::kv_service_server::KvServiceServer,
+    lock::lock_service_server::LockServiceServer, mqtt::mqtt_service_server::MqttServiceServer,
+    offset::offset_service_server::OffsetServiceServer,
     openraft::open_raft_service_server::OpenRaftServiceServer,
     placement::placement_center_service_server::PlacementCenterServiceServer,
+    topic::topic_service_server::TopicServiceServer,
+};
+use std::sync::{Arc, RwLock as StdRwLock};
+use tokio::{
+    select,
+    sync::{broadcast, RwLock},
 };
-use std::sync::{Arc, RwLock};
-use tokio::{select, sync::broadcast};
 use tonic::transport::Server;
 
 pub async fn start_grpc_server(
     client_poll: Arc<ClientPool>,
     raft_node: Raft<TypeConfig>,
+    cluster_identity: Arc<ClusterIdentity>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    heartbeat_tracker: Arc<HeartbeatTracker>,
+    lease_tracker: Arc<LeaseTracker>,
+    watchers: StateWatchers,
+    placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+    storage_metrics: Arc<StorageMetricsRegistry>,
     placement_center_storage: Arc<RaftMachineApply>,
-    rocksdb_engine_handler: Arc<RocksDBEngine>,
-    placement_cluster: Arc<RwLock<RaftGroupMetadata>>,
     stop_sx: broadcast::Sender<bool>,
 ) {
     let config = placement_center_conf();
@@ -49,10 +73,16 @@ pub async fn start_grpc_server(
         .start(
             client_poll,
             placement_center_storage,
-            rocksdb_engine_handler,
-            placement_cluster,
+            group_committer,
+            kvs,
+            heartbeat_tracker,
+            lease_tracker,
+            watchers,
+            placement_cache,
+            storage_metrics,
             stop_sx,
             raft_node,
+            cluster_identity,
         )
         .await;
 }
@@ -69,23 +99,82 @@ impl GrpcServer {
         &self,
         client_poll: Arc<ClientPool>,
         placement_center_storage: Arc<RaftMachineApply>,
-        rocksdb_engine_handler: Arc<RocksDBEngine>,
-        placement_cluster: Arc<RwLock<RaftGroupMetadata>>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        heartbeat_tracker: Arc<HeartbeatTracker>,
+        lease_tracker: Arc<LeaseTracker>,
+        watchers: StateWatchers,
+        placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+        storage_metrics: Arc<StorageMetricsRegistry>,
         stop_sx: broadcast::Sender<bool>,
         raft_node: Raft<TypeConfig>,
+        cluster_identity: Arc<ClusterIdentity>,
     ) {
         let addr = format!("0.0.0.0:{}", self.port).parse().unwrap();
         info!("Broker Grpc Server start. port:{}", self.port);
 
+        let cluster_service_handler = GrpcClusterServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+            heartbeat_tracker,
+            watchers.node,
+            placement_cache,
+            storage_metrics,
+        );
+        let topic_service_handler = GrpcTopicServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+            placement_center_conf().topic.clone(),
+        );
+        let election_service_handler = GrpcElectionServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+            lease_tracker.clone(),
+            watchers.election,
+        );
+        let lock_service_handler = GrpcLockServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+            lease_tracker,
+            watchers.lock,
+        );
+        let mqtt_service_handler = GrpcMqttServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+        );
+        let offset_service_handler = GrpcOffsetServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+            kvs.clone(),
+        );
+        let id_service_handler = GrpcIdServices::new(
+            client_poll.clone(),
+            raft_node.clone(),
+            group_committer.clone(),
+        );
         let kv_service_handler = GrpcKvServices::new(
             client_poll.clone(),
-            placement_center_storage.clone(),
-            rocksdb_engine_handler,
-            placement_cluster,
+            raft_node.clone(),
+            group_committer,
+            kvs,
+            watchers.commit,
+            placement_center_conf().raft.election_timeout_min_ms,
         );
         let raft_service_handler = GrpcRaftServices::new(placement_center_storage);
 
-        let openraft_service_handler = GrpcOpenRaftServices::new(raft_node);
+        let openraft_service_handler =
+            GrpcOpenRaftServices::new(raft_node, cluster_identity, watchers.recovery);
 
         let mut stop_rx = stop_sx.subscribe();
         select! {
@@ -105,6 +194,13 @@ impl GrpcServer {
             val =  Server::builder().add_service(KvServiceServer::new(kv_service_handler))
                                     .add_service(PlacementCenterServiceServer::new(raft_service_handler))
                                     .add_service(OpenRaftServiceServer::new(openraft_service_handler))
+                                    .add_service(ClusterServiceServer::new(cluster_service_handler))
+                                    .add_service(TopicServiceServer::new(topic_service_handler))
+                                    .add_service(ElectionServiceServer::new(election_service_handler))
+                                    .add_service(LockServiceServer::new(lock_service_handler))
+                                    .add_service(IdServiceServer::new(id_service_handler))
+                                    .add_service(MqttServiceServer::new(mqtt_service_handler))
+                                    .add_service(OffsetServiceServer::new(offset_service_handler))
                                     .serve(addr)=>{
                 match val{
                     Ok(()) => {
@@ -0,0 +1,378 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::{Arc, RwLock as StdRwLock},
+    time::Instant,
+};
+
+use crate::{
+    cluster::heartbeat::HeartbeatTracker,
+    openraft::{
+        bounded_read::{leader_addr, local_read_index},
+        group_commit::GroupCommitter,
+        route::{
+            cluster::{get_node, list_nodes, NodeEventType, NodeWatcher},
+            kvs::StateKvs,
+            AppRequestData,
+        },
+        status::{cluster_status, cluster_status_to_proto, local_member_status},
+        typeconfig::TypeConfig,
+    },
+    raft::{
+        metadata::RaftGroupMetadata,
+        node::{BrokerNode, NodeState},
+    },
+    storage::{keys::key_node, metrics::StorageMetricsRegistry},
+};
+use clients::{
+    placement::cluster::call::{
+        placement_heartbeat, placement_list_nodes, placement_register_node,
+        placement_un_register_node,
+    },
+    poll::ClientPool,
+};
+use common_base::{errors::RobustMQError, tools::now_second};
+use openraft::Raft;
+use protocol::{
+    cluster::{
+        cluster_service_server::ClusterService, BrokerNode as BrokerNodeInfo, ClusterStatusReply,
+        ClusterStatusRequest, HeartbeatRequest, ListNodesReply, ListNodesRequest,
+        MemberStatusReply, MemberStatusRequest, NodeEvent as NodeEventInfo,
+        NodeEventType as NodeEventTypeInfo, NodeState as NodeStateInfo, RegisterNodeRequest,
+        UnRegisterNodeRequest, WatchNodesRequest,
+    },
+    common::{ClusterType, CommonReply},
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+use super::services_offset::check_key_segment;
+
+pub struct GrpcClusterServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    heartbeat_tracker: Arc<HeartbeatTracker>,
+    node_watcher: NodeWatcher,
+    placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+    storage_metrics: Arc<StorageMetricsRegistry>,
+}
+
+impl GrpcClusterServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        heartbeat_tracker: Arc<HeartbeatTracker>,
+        node_watcher: NodeWatcher,
+        placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+        storage_metrics: Arc<StorageMetricsRegistry>,
+    ) -> Self {
+        return GrpcClusterServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+            heartbeat_tracker,
+            node_watcher,
+            placement_cache,
+            storage_metrics,
+        };
+    }
+
+    async fn commit(&self, data: AppRequestData) -> Result<Response<CommonReply>, Status> {
+        match self.group_committer.write(data).await {
+            Ok(_) => return Ok(Response::new(CommonReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+}
+
+pub fn cluster_type_name(cluster_type: i32) -> Result<String, Status> {
+    match ClusterType::try_from(cluster_type) {
+        Ok(cluster_type) => return Ok(cluster_type.as_str_name().to_string()),
+        Err(_) => {
+            return Err(Status::invalid_argument(format!(
+                "Unknown cluster type {}",
+                cluster_type
+            )));
+        }
+    }
+}
+
+pub fn broker_node_to_proto(node: BrokerNode) -> BrokerNodeInfo {
+    let cluster_type = ClusterType::from_str_name(&node.cluster_type)
+        .map(|cluster_type| cluster_type as i32)
+        .unwrap_or_default();
+    let state = match node.state {
+        NodeState::Starting => NodeStateInfo::Starting,
+        NodeState::Running => NodeStateInfo::Running,
+        NodeState::Stoping => NodeStateInfo::Stoping,
+        NodeState::Stop => NodeStateInfo::Stop,
+    };
+    return BrokerNodeInfo {
+        cluster_type,
+        cluster_name: node.cluster_name,
+        node_id: node.node_id,
+        node_ip: node.node_ip,
+        node_inner_addr: node.node_inner_addr,
+        extend: node.extend,
+        create_time: node.create_time as u64,
+        state: state.into(),
+    };
+}
+
+fn node_event_to_proto(event_type: NodeEventType, node: BrokerNode) -> NodeEventInfo {
+    let event_type = match event_type {
+        NodeEventType::Registered => NodeEventTypeInfo::Registered,
+        NodeEventType::StateChanged => NodeEventTypeInfo::StateChanged,
+        NodeEventType::Removed => NodeEventTypeInfo::Removed,
+    };
+    return NodeEventInfo {
+        event_type: event_type.into(),
+        node: Some(broker_node_to_proto(node)),
+    };
+}
+
+#[tonic::async_trait]
+impl ClusterService for GrpcClusterServices {
+    async fn register_node(
+        &self,
+        request: Request<RegisterNodeRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.cluster_name.is_empty() || req.node_inner_addr.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("cluster_name or node_inner_addr".to_string())
+                    .to_string(),
+            ));
+        }
+        check_key_segment("Cluster name", &req.cluster_name)?;
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_register_node(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let node = BrokerNode {
+            cluster_type,
+            cluster_name: req.cluster_name,
+            node_id: req.node_id,
+            node_ip: req.node_ip,
+            node_inner_addr: req.node_inner_addr,
+            extend: req.extend,
+            create_time: now_second() as u128,
+            state: NodeState::Starting,
+        };
+        return self.commit(AppRequestData::RegisterNode { node }).await;
+    }
+
+    async fn un_register_node(
+        &self,
+        request: Request<UnRegisterNodeRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.cluster_name.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("cluster_name".to_string()).to_string(),
+            ));
+        }
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_un_register_node(self.client_poll.clone(), vec![leader_addr], req).await
+            {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        return self
+            .commit(AppRequestData::UnRegisterNode {
+                cluster_type,
+                cluster_name: req.cluster_name,
+                node_id: req.node_id,
+            })
+            .await;
+    }
+
+    async fn list_nodes(
+        &self,
+        request: Request<ListNodesRequest>,
+    ) -> Result<Response<ListNodesReply>, Status> {
+        let req = request.into_inner();
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        // Brokers discover each other from the list, a follower could still return a
+        // node that was already removed.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_list_nodes(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+        if let Err(e) = local_read_index(&self.raft_node).await {
+            return Err(Status::unavailable(e.to_string()));
+        }
+
+        let kvs = self.kvs.read().await;
+        let nodes = list_nodes(&kvs, &cluster_type, &req.cluster_name)
+            .into_iter()
+            .map(broker_node_to_proto)
+            .collect();
+        return Ok(Response::new(ListNodesReply { nodes }));
+    }
+
+    async fn heartbeat(
+        &self,
+        request: Request<HeartbeatRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.cluster_name.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("cluster_name".to_string()).to_string(),
+            ));
+        }
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        // Liveness is tracked by the leader.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_heartbeat(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let key = key_node(&cluster_type, &req.cluster_name, req.node_id);
+        let node = match get_node(&*self.kvs.read().await, &key) {
+            Some(node) => node,
+            None => {
+                return Err(Status::not_found(format!(
+                    "Node {} is not registered in cluster {}",
+                    req.node_id, req.cluster_name
+                )));
+            }
+        };
+        self.heartbeat_tracker.report(&node, Instant::now());
+
+        if node.state == NodeState::Running {
+            return Ok(Response::new(CommonReply::default()));
+        }
+        return self
+            .commit(AppRequestData::UpdateNodeState {
+                cluster_type,
+                cluster_name: req.cluster_name,
+                node_id: req.node_id,
+                state: NodeState::Running,
+            })
+            .await;
+    }
+
+    type watch_nodesStream = ReceiverStream<Result<NodeEventInfo, Status>>;
+
+    async fn watch_nodes(
+        &self,
+        request: Request<WatchNodesRequest>,
+    ) -> Result<Response<Self::watch_nodesStream>, Status> {
+        let req = request.into_inner();
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        let mut events = self.node_watcher.subscribe();
+        let (event_sx, event_rx) = mpsc::channel(1024);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        let _ = event_sx
+                            .send(Err(Status::data_loss(format!(
+                                "Watcher fell behind by {} events, list the nodes again",
+                                skipped
+                            ))))
+                            .await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                if event.node.cluster_type != cluster_type
+                    || event.node.cluster_name != req.cluster_name
+                {
+                    continue;
+                }
+                let event = node_event_to_proto(event.event_type, event.node);
+                if event_sx.send(Ok(event)).await.is_err() {
+                    // The watcher went away.
+                    return;
+                }
+            }
+        });
+        return Ok(Response::new(ReceiverStream::new(event_rx)));
+    }
+
+    async fn cluster_status(
+        &self,
+        _: Request<ClusterStatusRequest>,
+    ) -> Result<Response<ClusterStatusReply>, Status> {
+        let status = match cluster_status(
+            &self.raft_node,
+            &self.client_poll,
+            &self.placement_cache,
+            &self.storage_metrics,
+        )
+        .await
+        {
+            Ok(status) => status,
+            Err(e) => {
+                return Err(Status::unavailable(e.to_string()));
+            }
+        };
+        return Ok(Response::new(cluster_status_to_proto(status)));
+    }
+
+    async fn member_status(
+        &self,
+        _: Request<MemberStatusRequest>,
+    ) -> Result<Response<MemberStatusReply>, Status> {
+        let metrics = self.raft_node.metrics().borrow().clone();
+        return Ok(Response::new(local_member_status(&metrics)));
+    }
+}
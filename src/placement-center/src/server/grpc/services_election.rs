@@ -0,0 +1,268 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    lease::tracker::LeaseTracker,
+    openraft::{
+        bounded_read::leader_addr,
+        group_commit::GroupCommitter,
+        route::{
+            election::{get_election, Election, ElectionLeader, ElectionWatcher},
+            kvs::StateKvs,
+            AppRequestData,
+        },
+        typeconfig::TypeConfig,
+    },
+    storage::keys::key_election,
+};
+use clients::{
+    placement::election::call::{placement_campaign, placement_resign},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use openraft::Raft;
+use protocol::{
+    common::CommonReply,
+    election::{
+        election_service_server::ElectionService, CampaignReply, CampaignRequest, ElectionState,
+        Leader, ObserveRequest, ResignRequest,
+    },
+};
+use tokio::sync::{broadcast::error::RecvError, mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+
+pub struct GrpcElectionServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    lease_tracker: Arc<LeaseTracker>,
+    election_watcher: ElectionWatcher,
+}
+
+impl GrpcElectionServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        lease_tracker: Arc<LeaseTracker>,
+        election_watcher: ElectionWatcher,
+    ) -> Self {
+        return GrpcElectionServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+            lease_tracker,
+            election_watcher,
+        };
+    }
+
+    async fn commit(&self, data: AppRequestData) -> Result<Option<String>, Status> {
+        match self.group_committer.write(data).await {
+            Ok(resp) => return Ok(resp.value),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+}
+
+fn leader_to_proto(leader: ElectionLeader) -> Leader {
+    return Leader {
+        candidate: leader.candidate,
+        epoch: leader.epoch,
+        lease_ms: leader.lease_ms,
+    };
+}
+
+fn election_to_proto(election: Election) -> ElectionState {
+    return ElectionState {
+        name: election.name,
+        leader: election.leader.map(leader_to_proto),
+        epoch: election.epoch,
+    };
+}
+
+async fn current_election(kvs: &RwLock<StateKvs>, name: &String) -> Election {
+    let key = key_election(name);
+    return get_election(&*kvs.read().await, &key).unwrap_or(Election {
+        name: name.clone(),
+        ..Default::default()
+    });
+}
+
+#[tonic::async_trait]
+impl ElectionService for GrpcElectionServices {
+    async fn campaign(
+        &self,
+        request: Request<CampaignRequest>,
+    ) -> Result<Response<CampaignReply>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() || req.candidate.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name or candidate".to_string()).to_string(),
+            ));
+        }
+        if req.lease_ms == 0 {
+            return Err(Status::invalid_argument("lease_ms must be greater than 0"));
+        }
+
+        // Leases are timed by the leader.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_campaign(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let value = self
+            .commit(AppRequestData::Campaign {
+                name: req.name.clone(),
+                candidate: req.candidate.clone(),
+                lease_ms: req.lease_ms,
+            })
+            .await?;
+        let election = match value.map(|value| serde_json::from_str::<Election>(&value)) {
+            Some(Ok(election)) => election,
+            Some(Err(e)) => {
+                return Err(Status::internal(e.to_string()));
+            }
+            None => {
+                return Err(Status::internal(format!(
+                    "Election {} could not be stored",
+                    req.name
+                )));
+            }
+        };
+
+        let leader = match election.leader {
+            Some(leader) => leader,
+            None => {
+                return Err(Status::internal(format!(
+                    "Election {} has no leader after a campaign",
+                    req.name
+                )));
+            }
+        };
+        let elected = leader.candidate == req.candidate;
+        if elected {
+            let deadline = Instant::now() + Duration::from_millis(leader.lease_ms);
+            self.lease_tracker
+                .renew(&key_election(&req.name), leader.epoch, deadline);
+        }
+        return Ok(Response::new(CampaignReply {
+            elected,
+            leader: Some(leader_to_proto(leader)),
+        }));
+    }
+
+    async fn resign(
+        &self,
+        request: Request<ResignRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() || req.candidate.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name or candidate".to_string()).to_string(),
+            ));
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_resign(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let value = self
+            .commit(AppRequestData::Resign {
+                name: req.name.clone(),
+                candidate: req.candidate.clone(),
+                epoch: req.epoch,
+            })
+            .await?;
+        if value.is_none() {
+            return Err(Status::failed_precondition(format!(
+                "{} is not the leader of election {} with epoch {}",
+                req.candidate, req.name, req.epoch
+            )));
+        }
+        return Ok(Response::new(CommonReply::default()));
+    }
+
+    type observeStream = ReceiverStream<Result<ElectionState, Status>>;
+
+    async fn observe(
+        &self,
+        request: Request<ObserveRequest>,
+    ) -> Result<Response<Self::observeStream>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name".to_string()).to_string(),
+            ));
+        }
+
+        // Subscribe before reading the current state, so no change falls in between.
+        let mut changes = self.election_watcher.subscribe();
+        let current = current_election(&self.kvs, &req.name).await;
+        let kvs = self.kvs.clone();
+        let (state_sx, state_rx) = mpsc::channel(1024);
+        tokio::spawn(async move {
+            if state_sx.send(Ok(election_to_proto(current))).await.is_err() {
+                return;
+            }
+            loop {
+                let election = match changes.recv().await {
+                    Ok(election) => election,
+                    // Only the latest leader matters, missed changes are replaced by the
+                    // current state.
+                    Err(RecvError::Lagged(_)) => current_election(&kvs, &req.name).await,
+                    Err(RecvError::Closed) => return,
+                };
+                if election.name != req.name {
+                    continue;
+                }
+                if state_sx
+                    .send(Ok(election_to_proto(election)))
+                    .await
+                    .is_err()
+                {
+                    // The observer went away.
+                    return;
+                }
+            }
+        });
+        return Ok(Response::new(ReceiverStream::new(state_rx)));
+    }
+}
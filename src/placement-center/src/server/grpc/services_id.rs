@@ -0,0 +1,111 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::openraft::{
+    bounded_read::leader_addr,
+    group_commit::GroupCommitter,
+    route::{sequence::IdBlock, AppRequestData},
+    typeconfig::TypeConfig,
+};
+use clients::{placement::id::call::placement_allocate_id, poll::ClientPool};
+use common_base::errors::RobustMQError;
+use openraft::Raft;
+use protocol::id::{id_service_server::IdService, AllocateIdReply, AllocateIdRequest};
+use tonic::{Request, Response, Status};
+
+/// Largest block a single request may take.
+const MAX_ID_BLOCK_SIZE: u64 = 1_000_000;
+
+pub struct GrpcIdServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+}
+
+impl GrpcIdServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+    ) -> Self {
+        return GrpcIdServices {
+            client_poll,
+            raft_node,
+            group_committer,
+        };
+    }
+}
+
+#[tonic::async_trait]
+impl IdService for GrpcIdServices {
+    async fn allocate(
+        &self,
+        request: Request<AllocateIdRequest>,
+    ) -> Result<Response<AllocateIdReply>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name".to_string()).to_string(),
+            ));
+        }
+        if req.count == 0 || req.count > MAX_ID_BLOCK_SIZE {
+            return Err(Status::invalid_argument(format!(
+                "count must be between 1 and {}",
+                MAX_ID_BLOCK_SIZE
+            )));
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_allocate_id(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::AllocateIds {
+            name: req.name.clone(),
+            count: req.count,
+        };
+        let value = match self.group_committer.write(data).await {
+            Ok(resp) => resp.value,
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        };
+        match value.map(|value| serde_json::from_str::<IdBlock>(&value)) {
+            Some(Ok(block)) => {
+                return Ok(Response::new(AllocateIdReply {
+                    start: block.start,
+                    count: block.count,
+                }));
+            }
+            Some(Err(e)) => {
+                return Err(Status::internal(e.to_string()));
+            }
+            None => {
+                return Err(Status::resource_exhausted(format!(
+                    "Sequence {} cannot hand out {} IDs, it is exhausted or its stored value is damaged",
+                    req.name, req.count
+                )));
+            }
+        }
+    }
+}
@@ -12,60 +12,105 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
-use crate::{
-    openraft::typeconfig::TypeConfig,
-    raft::{
-        apply::{RaftMachineApply, StorageData, StorageDataType},
-        metadata::RaftGroupMetadata,
-    },
-    storage::{kv::KvStorage, rocksdb::RocksDBEngine},
+use crate::openraft::{
+    bounded_read::{leader_addr, local_read_index, BoundedReader},
+    group_commit::GroupCommitter,
+    route::{counter::Counter, kvs::StateKvs, AppRequestData},
+    store::log_store::CommitWatcher,
+    typeconfig::TypeConfig,
 };
+use crate::storage::{export::import_request, keys::is_reserved_key};
 use clients::{
-    placement::kv::call::{placement_delete, placement_set},
+    placement::kv::call::{
+        placement_delete, placement_get, placement_import, placement_incr, placement_scan,
+        placement_set,
+    },
     poll::ClientPool,
 };
 use common_base::errors::RobustMQError;
 use openraft::Raft;
-use prost::Message;
 use protocol::kv::{
     kv_service_server::KvService, CommonReply, DeleteRequest, ExistsReply, ExistsRequest, GetReply,
-    GetRequest, SetRequest,
+    GetRequest, ImportRequest, IncrReply, IncrRequest, KeyValue, ReadIndexReply, ReadIndexRequest,
+    ScanReply, ScanRequest, SetRequest, StalenessBound,
 };
+use tokio::sync::RwLock;
 use tonic::{Request, Response, Status};
 
 pub struct GrpcKvServices {
     client_poll: Arc<ClientPool>,
-    placement_center_storage: Arc<RaftMachineApply>,
-    rocksdb_engine_handler: Arc<RocksDBEngine>,
-    placement_cluster: Arc<RwLock<RaftGroupMetadata>>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    bounded_reader: BoundedReader,
+    kvs: Arc<RwLock<StateKvs>>,
 }
 
 impl GrpcKvServices {
     pub fn new(
         client_poll: Arc<ClientPool>,
-        placement_center_storage: Arc<RaftMachineApply>,
-        rocksdb_engine_handler: Arc<RocksDBEngine>,
-        placement_cluster: Arc<RwLock<RaftGroupMetadata>>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        commit: CommitWatcher,
+        election_timeout_ms: u64,
     ) -> Self {
+        let bounded_reader = BoundedReader::new(
+            raft_node.clone(),
+            client_poll.clone(),
+            commit,
+            election_timeout_ms,
+        );
         return GrpcKvServices {
             client_poll,
-            placement_center_storage,
-            rocksdb_engine_handler,
-            placement_cluster,
+            raft_node,
+            group_committer,
+            bounded_reader,
+            kvs,
         };
     }
 
-    pub fn is_leader(&self) -> bool {
-        return self.placement_cluster.read().unwrap().is_leader();
+    /// Address of the leader when it is another node, writes are forwarded there.
+    pub fn forward_addr(&self) -> Option<String> {
+        return leader_addr(&self.raft_node);
     }
 
-    pub fn leader_addr(&self) -> String {
-        return self.placement_cluster.read().unwrap().leader_addr();
+    /// Address of the leader when a read with `staleness` cannot be served locally.
+    async fn read_forward_addr(
+        &self,
+        staleness: &Option<StalenessBound>,
+    ) -> Result<Option<String>, Status> {
+        let bound = match staleness {
+            Some(bound) => bound,
+            None => return Ok(None),
+        };
+        match self.bounded_reader.ensure_fresh(bound).await {
+            Ok(true) => return Ok(None),
+            Ok(false) => match self.forward_addr() {
+                Some(addr) => return Ok(Some(addr)),
+                None => {
+                    return Err(Status::unavailable(
+                        "The local state is too stale and no leader is known",
+                    ));
+                }
+            },
+            Err(e) => return Err(Status::unavailable(e.to_string())),
+        }
     }
 }
 
+/// Keys owned by a domain service can only be changed through that service.
+fn check_reserved_key(key: &String) -> Result<(), Status> {
+    if is_reserved_key(key) {
+        return Err(Status::permission_denied(format!(
+            "The key {} is reserved for internal state",
+            key
+        )));
+    }
+    return Ok(());
+}
+
 #[tonic::async_trait]
 impl KvService for GrpcKvServices {
     async fn set(&self, request: Request<SetRequest>) -> Result<Response<CommonReply>, Status> {
@@ -76,9 +121,9 @@ impl KvService for GrpcKvServices {
                 RobustMQError::ParameterCannotBeNull("key or value".to_string()).to_string(),
             ));
         }
+        check_reserved_key(&req.key)?;
 
-        if !self.is_leader() {
-            let leader_addr = self.leader_addr();
+        if let Some(leader_addr) = self.forward_addr() {
             match placement_set(self.client_poll.clone(), vec![leader_addr], req).await {
                 Ok(reply) => {
                     return Ok(Response::new(reply));
@@ -89,13 +134,11 @@ impl KvService for GrpcKvServices {
             }
         }
 
-        // Raft state machine is used to store Node data
-        let data = StorageData::new(StorageDataType::KvSet, SetRequest::encode_to_vec(&req));
-        match self
-            .placement_center_storage
-            .apply_propose_message(data, "set".to_string())
-            .await
-        {
+        let data = AppRequestData::Set {
+            key: req.key,
+            value: req.value,
+        };
+        match self.group_committer.write(data).await {
             Ok(_) => return Ok(Response::new(CommonReply::default())),
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
@@ -114,9 +157,9 @@ impl KvService for GrpcKvServices {
                 RobustMQError::ParameterCannotBeNull("key".to_string()).to_string(),
             ));
         }
+        check_reserved_key(&req.key)?;
 
-        if !self.is_leader() {
-            let leader_addr = self.leader_addr();
+        if let Some(leader_addr) = self.forward_addr() {
             match placement_delete(self.client_poll.clone(), vec![leader_addr], req).await {
                 Ok(reply) => {
                     return Ok(Response::new(reply));
@@ -127,16 +170,8 @@ impl KvService for GrpcKvServices {
             }
         }
 
-        // Raft state machine is used to store Node data
-        let data = StorageData::new(
-            StorageDataType::KvDelete,
-            DeleteRequest::encode_to_vec(&req),
-        );
-        match self
-            .placement_center_storage
-            .apply_propose_message(data, "delete".to_string())
-            .await
-        {
+        let data = AppRequestData::Delete { key: req.key };
+        match self.group_committer.write(data).await {
             Ok(_) => return Ok(Response::new(CommonReply::default())),
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
@@ -153,17 +188,21 @@ impl KvService for GrpcKvServices {
             ));
         }
 
-        let kv_storage = KvStorage::new(self.rocksdb_engine_handler.clone());
-        let mut reply = GetReply::default();
-        match kv_storage.get(req.key) {
-            Ok(Some(data)) => {
-                reply.value = data;
-                return Ok(Response::new(reply));
+        if let Some(leader_addr) = self.read_forward_addr(&req.staleness).await? {
+            match placement_get(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
             }
-            Ok(None) => {}
-            Err(e) => return Err(Status::cancelled(e.to_string())),
         }
 
+        let mut reply = GetReply::default();
+        if let Some(value) = self.kvs.read().await.get(&req.key) {
+            reply.value = value.clone();
+        }
         return Ok(Response::new(reply));
     }
 
@@ -179,11 +218,143 @@ impl KvService for GrpcKvServices {
             ));
         }
 
-        let kv_storage = KvStorage::new(self.rocksdb_engine_handler.clone());
-        match kv_storage.exists(req.key) {
-            Ok(flag) => {
-                return Ok(Response::new(ExistsReply { flag }));
+        let flag = self.kvs.read().await.contains_key(&req.key);
+        return Ok(Response::new(ExistsReply { flag }));
+    }
+
+    async fn scan(&self, request: Request<ScanRequest>) -> Result<Response<ScanReply>, Status> {
+        let req = request.into_inner();
+        check_reserved_key(&req.prefix)?;
+
+        if let Some(leader_addr) = self.read_forward_addr(&req.staleness).await? {
+            match placement_scan(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let limit = if req.limit == 0 {
+            usize::MAX
+        } else {
+            req.limit as usize
+        };
+        let kvs = self.kvs.read().await;
+        let kvs = kvs
+            .range(req.prefix.clone()..)
+            .take_while(|(key, _)| key.starts_with(&req.prefix))
+            .filter(|(key, _)| !is_reserved_key(key))
+            .take(limit)
+            .map(|(key, value)| KeyValue {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        return Ok(Response::new(ScanReply { kvs }));
+    }
+
+    async fn read_index(
+        &self,
+        _: Request<ReadIndexRequest>,
+    ) -> Result<Response<ReadIndexReply>, Status> {
+        let metrics = self.raft_node.metrics().borrow().clone();
+        if metrics.current_leader != Some(metrics.id) {
+            return Err(Status::failed_precondition(
+                "The read index is only served by the leader",
+            ));
+        }
+        match local_read_index(&self.raft_node).await {
+            Ok(read_index) => return Ok(Response::new(ReadIndexReply { read_index })),
+            Err(e) => return Err(Status::unavailable(e.to_string())),
+        }
+    }
+
+    async fn incr(&self, request: Request<IncrRequest>) -> Result<Response<IncrReply>, Status> {
+        let req = request.into_inner();
+
+        if req.key.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("key".to_string()).to_string(),
+            ));
+        }
+        check_reserved_key(&req.key)?;
+        if let (Some(floor), Some(ceiling)) = (req.floor, req.ceiling) {
+            if floor > ceiling {
+                return Err(Status::invalid_argument(
+                    "floor must not be greater than ceiling",
+                ));
+            }
+        }
+
+        if let Some(leader_addr) = self.forward_addr() {
+            match placement_incr(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::Incr {
+            key: req.key.clone(),
+            delta: req.delta,
+            floor: req.floor,
+            ceiling: req.ceiling,
+        };
+        let value = match self.group_committer.write(data).await {
+            Ok(resp) => resp.value,
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        };
+        match value.map(|value| serde_json::from_str::<Counter>(&value)) {
+            Some(Ok(counter)) => {
+                return Ok(Response::new(IncrReply {
+                    value: counter.value,
+                    applied: counter.applied,
+                }));
+            }
+            Some(Err(e)) => {
+                return Err(Status::internal(e.to_string()));
+            }
+            None => {
+                return Err(Status::failed_precondition(format!(
+                    "The value of {} is not an integer",
+                    req.key
+                )));
+            }
+        }
+    }
+
+    async fn import(
+        &self,
+        request: Request<ImportRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        for kv in req.kvs.iter() {
+            check_reserved_key(&kv.key)?;
+        }
+
+        if let Some(leader_addr) = self.forward_addr() {
+            match placement_import(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
             }
+        }
+
+        let kvs = req.kvs.into_iter().map(|kv| (kv.key, kv.value)).collect();
+        match self.group_committer.write(import_request(kvs)).await {
+            Ok(_) => return Ok(Response::new(CommonReply::default())),
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
             }
@@ -0,0 +1,272 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    lease::tracker::LeaseTracker,
+    openraft::{
+        bounded_read::leader_addr,
+        group_commit::GroupCommitter,
+        route::{
+            kvs::StateKvs,
+            lock::{get_lock, DistributedLock, LockWatcher},
+            AppRequestData,
+        },
+        typeconfig::TypeConfig,
+    },
+    storage::keys::key_lock,
+};
+use clients::{
+    placement::lock::call::{placement_lock, placement_unlock},
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use openraft::Raft;
+use protocol::{
+    common::CommonReply,
+    lock::{lock_service_server::LockService, LockReply, LockRequest, UnlockRequest},
+};
+use tokio::{
+    sync::{
+        broadcast::{error::RecvError, Receiver},
+        RwLock,
+    },
+    time::timeout,
+};
+use tonic::{Request, Response, Status};
+
+pub struct GrpcLockServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    lease_tracker: Arc<LeaseTracker>,
+    lock_watcher: LockWatcher,
+}
+
+impl GrpcLockServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        lease_tracker: Arc<LeaseTracker>,
+        lock_watcher: LockWatcher,
+    ) -> Self {
+        return GrpcLockServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+            lease_tracker,
+            lock_watcher,
+        };
+    }
+
+    async fn commit(&self, data: AppRequestData) -> Result<Option<String>, Status> {
+        match self.group_committer.write(data).await {
+            Ok(resp) => return Ok(resp.value),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn commit_lock(
+        &self,
+        name: &String,
+        data: AppRequestData,
+    ) -> Result<DistributedLock, Status> {
+        match self
+            .commit(data)
+            .await?
+            .map(|value| serde_json::from_str::<DistributedLock>(&value))
+        {
+            Some(Ok(lock)) => return Ok(lock),
+            Some(Err(e)) => {
+                return Err(Status::internal(e.to_string()));
+            }
+            // Nobody queued for a lock that does not exist.
+            None => {
+                return Ok(DistributedLock {
+                    name: name.clone(),
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    /// Wait until the lock is handed to `owner`. Returns false once it gets nothing else
+    /// to wait for.
+    async fn wait_for_grant(
+        &self,
+        events: &mut Receiver<DistributedLock>,
+        name: &String,
+        owner: &String,
+    ) -> bool {
+        loop {
+            let lock = match events.recv().await {
+                Ok(lock) => lock,
+                // Missed changes are replaced by the current state.
+                Err(RecvError::Lagged(_)) => {
+                    match get_lock(&*self.kvs.read().await, &key_lock(name)) {
+                        Some(lock) => lock,
+                        None => continue,
+                    }
+                }
+                Err(RecvError::Closed) => return false,
+            };
+            if lock.name == *name && lock.held_by(owner).is_some() {
+                return true;
+            }
+        }
+    }
+
+    fn reply(&self, lock: &DistributedLock, owner: &String) -> LockReply {
+        if let Some(holder) = lock.held_by(owner) {
+            let deadline = Instant::now() + Duration::from_millis(holder.ttl_ms);
+            self.lease_tracker
+                .renew(&key_lock(&lock.name), holder.token, deadline);
+            return LockReply {
+                acquired: true,
+                token: holder.token,
+                holder: holder.owner.clone(),
+            };
+        }
+        return LockReply {
+            acquired: false,
+            token: 0,
+            holder: lock
+                .holder
+                .as_ref()
+                .map(|holder| holder.owner.clone())
+                .unwrap_or_default(),
+        };
+    }
+}
+
+#[tonic::async_trait]
+impl LockService for GrpcLockServices {
+    async fn lock(&self, request: Request<LockRequest>) -> Result<Response<LockReply>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() || req.owner.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name or owner".to_string()).to_string(),
+            ));
+        }
+        if req.ttl_ms == 0 {
+            return Err(Status::invalid_argument("ttl_ms must be greater than 0"));
+        }
+
+        // TTLs are timed and waiters are woken up by the leader.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_lock(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        // Subscribe before queueing, so a grant right after cannot be missed.
+        let mut events = self.lock_watcher.subscribe();
+        let lock = self
+            .commit_lock(
+                &req.name,
+                AppRequestData::Lock {
+                    name: req.name.clone(),
+                    owner: req.owner.clone(),
+                    ttl_ms: req.ttl_ms,
+                    wait: req.wait_ms > 0,
+                },
+            )
+            .await?;
+        if lock.held_by(&req.owner).is_some() || req.wait_ms == 0 {
+            return Ok(Response::new(self.reply(&lock, &req.owner)));
+        }
+
+        let wait = Duration::from_millis(req.wait_ms);
+        if let Ok(true) = timeout(
+            wait,
+            self.wait_for_grant(&mut events, &req.name, &req.owner),
+        )
+        .await
+        {
+            if let Some(lock) = get_lock(&*self.kvs.read().await, &key_lock(&req.name)) {
+                if lock.held_by(&req.owner).is_some() {
+                    return Ok(Response::new(self.reply(&lock, &req.owner)));
+                }
+            }
+        }
+
+        // Leave the queue. The lock may have been handed over in the meantime, then the
+        // owner keeps it.
+        let lock = self
+            .commit_lock(
+                &req.name,
+                AppRequestData::CancelLockWait {
+                    name: req.name.clone(),
+                    owner: req.owner.clone(),
+                },
+            )
+            .await?;
+        return Ok(Response::new(self.reply(&lock, &req.owner)));
+    }
+
+    async fn unlock(
+        &self,
+        request: Request<UnlockRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.name.is_empty() || req.owner.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("name or owner".to_string()).to_string(),
+            ));
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_unlock(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let value = self
+            .commit(AppRequestData::Unlock {
+                name: req.name.clone(),
+                owner: req.owner.clone(),
+                token: req.token,
+            })
+            .await?;
+        if value.is_none() {
+            return Err(Status::failed_precondition(format!(
+                "{} does not hold lock {} with token {}",
+                req.owner, req.name, req.token
+            )));
+        }
+        return Ok(Response::new(CommonReply::default()));
+    }
+}
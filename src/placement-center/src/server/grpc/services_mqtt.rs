@@ -0,0 +1,395 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::openraft::{
+    bounded_read::{leader_addr, local_read_index},
+    group_commit::GroupCommitter,
+    route::{
+        kvs::StateKvs,
+        mqtt::{
+            get_session, list_sessions, MqttLastWill, MqttSession, MqttSessionState,
+            MqttSubscription,
+        },
+        AppRequestData,
+    },
+    typeconfig::TypeConfig,
+};
+use clients::{
+    placement::mqtt::call::{
+        placement_delete_session, placement_get_session, placement_list_sessions,
+        placement_save_last_will, placement_save_session, placement_subscribe,
+        placement_unsubscribe,
+    },
+    poll::ClientPool,
+};
+use common_base::errors::RobustMQError;
+use openraft::Raft;
+use protocol::{
+    common::CommonReply,
+    mqtt::{
+        mqtt_service_server::MqttService, DeleteSessionRequest, GetSessionReply, GetSessionRequest,
+        LastWill, ListSessionsReply, ListSessionsRequest, SaveLastWillRequest, SaveSessionRequest,
+        Session, SessionState, SubscribeRequest, Subscription, UnsubscribeRequest,
+    },
+};
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+
+use super::services_offset::check_key_segment;
+
+/// Highest QoS level of MQTT.
+const MAX_QOS: u32 = 2;
+
+pub struct GrpcMqttServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+}
+
+impl GrpcMqttServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+    ) -> Self {
+        return GrpcMqttServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+        };
+    }
+
+    /// Commit the request and fail when the session it needs does not exist.
+    async fn commit_to_session(
+        &self,
+        client_id: &String,
+        data: AppRequestData,
+    ) -> Result<Response<CommonReply>, Status> {
+        match self.group_committer.write(data).await {
+            Ok(resp) => {
+                if resp.value.is_none() {
+                    return Err(Status::not_found(format!(
+                        "Session of client {} does not exist",
+                        client_id
+                    )));
+                }
+                return Ok(Response::new(CommonReply::default()));
+            }
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+}
+
+fn check_client(cluster_name: &String, client_id: &String) -> Result<(), Status> {
+    if cluster_name.is_empty() || client_id.is_empty() {
+        return Err(Status::cancelled(
+            RobustMQError::ParameterCannotBeNull("cluster_name or client_id".to_string())
+                .to_string(),
+        ));
+    }
+    check_key_segment("Cluster name", cluster_name)?;
+    return Ok(());
+}
+
+fn check_qos(qos: u32) -> Result<(), Status> {
+    if qos > MAX_QOS {
+        return Err(Status::invalid_argument(format!("Invalid QoS {}", qos)));
+    }
+    return Ok(());
+}
+
+fn session_from_proto(session: Session) -> MqttSession {
+    return MqttSession {
+        client_id: session.client_id,
+        clean_start: session.clean_start,
+        session_expiry_interval: session.session_expiry_interval,
+        broker_id: session.broker_id,
+        create_time: session.create_time,
+    };
+}
+
+fn last_will_from_proto(last_will: LastWill) -> MqttLastWill {
+    return MqttLastWill {
+        topic: last_will.topic,
+        payload: last_will.payload,
+        qos: last_will.qos,
+        retain: last_will.retain,
+        delay_interval: last_will.delay_interval,
+    };
+}
+
+fn session_state_to_proto(state: MqttSessionState) -> SessionState {
+    let session = state.session;
+    return SessionState {
+        session: Some(Session {
+            client_id: session.client_id,
+            clean_start: session.clean_start,
+            session_expiry_interval: session.session_expiry_interval,
+            broker_id: session.broker_id,
+            create_time: session.create_time,
+        }),
+        subscriptions: state
+            .subscriptions
+            .into_iter()
+            .map(|subscription| Subscription {
+                filter: subscription.filter,
+                qos: subscription.qos,
+            })
+            .collect(),
+        last_will: state.last_will.map(|last_will| LastWill {
+            topic: last_will.topic,
+            payload: last_will.payload,
+            qos: last_will.qos,
+            retain: last_will.retain,
+            delay_interval: last_will.delay_interval,
+        }),
+    };
+}
+
+#[tonic::async_trait]
+impl MqttService for GrpcMqttServices {
+    async fn save_session(
+        &self,
+        request: Request<SaveSessionRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+        let session = match &req.session {
+            Some(session) => session.clone(),
+            None => {
+                return Err(Status::cancelled(
+                    RobustMQError::ParameterCannotBeNull("session".to_string()).to_string(),
+                ));
+            }
+        };
+        check_client(&req.cluster_name, &session.client_id)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_save_session(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::SaveMqttSession {
+            cluster_name: req.cluster_name,
+            session: session_from_proto(session),
+        };
+        match self.group_committer.write(data).await {
+            Ok(_) => return Ok(Response::new(CommonReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn delete_session(
+        &self,
+        request: Request<DeleteSessionRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+        check_client(&req.cluster_name, &req.client_id)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_delete_session(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        // Deleting a session that is gone already succeeds.
+        let data = AppRequestData::DeleteMqttSession {
+            cluster_name: req.cluster_name,
+            client_id: req.client_id,
+        };
+        match self.group_committer.write(data).await {
+            Ok(_) => return Ok(Response::new(CommonReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn get_session(
+        &self,
+        request: Request<GetSessionRequest>,
+    ) -> Result<Response<GetSessionReply>, Status> {
+        let req = request.into_inner();
+        check_client(&req.cluster_name, &req.client_id)?;
+
+        // A broker taking over a session must see the subscriptions and last will the
+        // previous one committed, so the read is answered by the leader.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_get_session(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+        if let Err(e) = local_read_index(&self.raft_node).await {
+            return Err(Status::unavailable(e.to_string()));
+        }
+
+        let state = get_session(&*self.kvs.read().await, &req.cluster_name, &req.client_id);
+        return Ok(Response::new(GetSessionReply {
+            session: state.map(session_state_to_proto),
+        }));
+    }
+
+    async fn list_sessions(
+        &self,
+        request: Request<ListSessionsRequest>,
+    ) -> Result<Response<ListSessionsReply>, Status> {
+        let req = request.into_inner();
+        if req.cluster_name.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("cluster_name".to_string()).to_string(),
+            ));
+        }
+        check_key_segment("Cluster name", &req.cluster_name)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_list_sessions(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+        if let Err(e) = local_read_index(&self.raft_node).await {
+            return Err(Status::unavailable(e.to_string()));
+        }
+
+        let sessions = list_sessions(&*self.kvs.read().await, &req.cluster_name, req.broker_id);
+        return Ok(Response::new(ListSessionsReply {
+            sessions: sessions.into_iter().map(session_state_to_proto).collect(),
+        }));
+    }
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+        check_client(&req.cluster_name, &req.client_id)?;
+        for subscription in req.subscriptions.iter() {
+            if subscription.filter.is_empty() {
+                return Err(Status::invalid_argument("Topic filter cannot be empty"));
+            }
+            check_qos(subscription.qos)?;
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_subscribe(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::MqttSubscribe {
+            cluster_name: req.cluster_name,
+            client_id: req.client_id.clone(),
+            subscriptions: req
+                .subscriptions
+                .into_iter()
+                .map(|subscription| MqttSubscription {
+                    filter: subscription.filter,
+                    qos: subscription.qos,
+                })
+                .collect(),
+        };
+        return self.commit_to_session(&req.client_id, data).await;
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: Request<UnsubscribeRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+        check_client(&req.cluster_name, &req.client_id)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_unsubscribe(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::MqttUnsubscribe {
+            cluster_name: req.cluster_name,
+            client_id: req.client_id.clone(),
+            filters: req.filters,
+        };
+        return self.commit_to_session(&req.client_id, data).await;
+    }
+
+    async fn save_last_will(
+        &self,
+        request: Request<SaveLastWillRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+        check_client(&req.cluster_name, &req.client_id)?;
+        if let Some(last_will) = &req.last_will {
+            if last_will.topic.is_empty() {
+                return Err(Status::invalid_argument("Last will topic cannot be empty"));
+            }
+            check_qos(last_will.qos)?;
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_save_last_will(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let data = AppRequestData::SaveMqttLastWill {
+            cluster_name: req.cluster_name,
+            client_id: req.client_id.clone(),
+            last_will: req.last_will.map(last_will_from_proto),
+        };
+        return self.commit_to_session(&req.client_id, data).await;
+    }
+}
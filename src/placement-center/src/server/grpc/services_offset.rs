@@ -0,0 +1,188 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use crate::openraft::{
+    bounded_read::{leader_addr, local_read_index},
+    group_commit::GroupCommitter,
+    route::{
+        kvs::StateKvs,
+        offset::{fetch_offset, list_group_offsets, GroupOffset},
+        AppRequestData,
+    },
+    typeconfig::TypeConfig,
+};
+use clients::{
+    placement::offset::call::{placement_commit_offset, placement_fetch_offset},
+    poll::ClientPool,
+};
+use common_base::{errors::RobustMQError, tools::now_second};
+use openraft::Raft;
+use protocol::{
+    common::CommonReply,
+    offset::{
+        offset_service_server::OffsetService, CommitOffsetRequest, CommittedOffset,
+        FetchOffsetReply, FetchOffsetRequest,
+    },
+};
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+
+pub struct GrpcOffsetServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+}
+
+impl GrpcOffsetServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+    ) -> Self {
+        return GrpcOffsetServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+        };
+    }
+}
+
+fn committed_offset_to_proto(offset: GroupOffset) -> CommittedOffset {
+    return CommittedOffset {
+        topic: offset.topic,
+        partition: offset.partition,
+        offset: offset.offset,
+        metadata: offset.metadata,
+        commit_time: offset.commit_time,
+    };
+}
+
+/// Names that are segments of a state key, a `/` in them would let one name read or
+/// overwrite the keys of another, e.g. one group expiring the offsets of another.
+pub fn check_key_segment(name: &str, value: &String) -> Result<(), Status> {
+    if value.contains('/') {
+        return Err(Status::invalid_argument(format!(
+            "{} {} must not contain '/'",
+            name, value
+        )));
+    }
+    return Ok(());
+}
+
+#[tonic::async_trait]
+impl OffsetService for GrpcOffsetServices {
+    async fn commit_offset(
+        &self,
+        request: Request<CommitOffsetRequest>,
+    ) -> Result<Response<CommonReply>, Status> {
+        let req = request.into_inner();
+
+        if req.group.is_empty() || req.offsets.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("group or offsets".to_string()).to_string(),
+            ));
+        }
+        if req.offsets.iter().any(|offset| offset.topic.is_empty()) {
+            return Err(Status::invalid_argument("Topic cannot be empty"));
+        }
+        check_key_segment("Group", &req.group)?;
+        for offset in req.offsets.iter() {
+            check_key_segment("Topic", &offset.topic)?;
+        }
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_commit_offset(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        // The retention runs on the clock of the leader.
+        let commit_time = now_second();
+        let offsets = req
+            .offsets
+            .into_iter()
+            .map(|offset| GroupOffset {
+                group: req.group.clone(),
+                topic: offset.topic,
+                partition: offset.partition,
+                offset: offset.offset,
+                metadata: offset.metadata,
+                commit_time,
+            })
+            .collect();
+        match self
+            .group_committer
+            .write(AppRequestData::CommitOffsets { offsets })
+            .await
+        {
+            Ok(_) => return Ok(Response::new(CommonReply::default())),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+
+    async fn fetch_offset(
+        &self,
+        request: Request<FetchOffsetRequest>,
+    ) -> Result<Response<FetchOffsetReply>, Status> {
+        let req = request.into_inner();
+
+        if req.group.is_empty() {
+            return Err(Status::cancelled(
+                RobustMQError::ParameterCannotBeNull("group".to_string()).to_string(),
+            ));
+        }
+        check_key_segment("Group", &req.group)?;
+
+        // Consumers resume from the fetched offsets, so the read is answered by the
+        // leader once it has applied every committed write.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_fetch_offset(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+        if let Err(e) = local_read_index(&self.raft_node).await {
+            return Err(Status::unavailable(e.to_string()));
+        }
+
+        let kvs = self.kvs.read().await;
+        let offsets = if req.partitions.is_empty() {
+            list_group_offsets(&kvs, &req.group)
+        } else {
+            req.partitions
+                .iter()
+                .filter_map(|tp| fetch_offset(&kvs, &req.group, &tp.topic, tp.partition))
+                .collect()
+        };
+        return Ok(Response::new(FetchOffsetReply {
+            offsets: offsets.into_iter().map(committed_offset_to_proto).collect(),
+        }));
+    }
+}
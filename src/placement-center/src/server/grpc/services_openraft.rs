@@ -12,23 +12,129 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bincode::{deserialize, serialize};
-use openraft::Raft;
+use std::{collections::BTreeSet, sync::Arc};
+
+use common_base::{config::placement_center::placement_center_conf, errors::RobustMQError};
+use log::error;
+use openraft::{Raft, Vote};
 use protocol::openraft::{
-    open_raft_service_server::OpenRaftService, AppendReply, AppendRequest, SnapshotReply,
+    open_raft_service_server::OpenRaftService, AppendFrame, AppendReply, AppendReplyFrame,
+    AppendRequest, AppendStreamReply, AppendStreamRequest, RaftHeader, SnapshotReply,
     SnapshotRequest, VoteReply, VoteRequest,
 };
-use tonic::{Request, Response, Status};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
 
-use crate::openraft::typeconfig::TypeConfig;
+use crate::openraft::{
+    network::{
+        convert::{
+            append_request_from_proto, append_response_to_proto, decode_legacy, encode_legacy,
+            is_legacy_request, snapshot_request_from_proto, snapshot_response_to_proto,
+            vote_request_from_proto, vote_response_to_proto,
+        },
+        identity::{verify_header, verify_sender, ClusterIdentity},
+    },
+    raft_node::NodeId,
+    store::log_store::RecoveryWatcher,
+    typeconfig::TypeConfig,
+};
 
 pub struct GrpcOpenRaftServices {
     raft_node: Raft<TypeConfig>,
+    identity: Arc<ClusterIdentity>,
+    recovery: RecoveryWatcher,
 }
 
 impl GrpcOpenRaftServices {
-    pub fn new(raft_node: Raft<TypeConfig>) -> Self {
-        return GrpcOpenRaftServices { raft_node };
+    pub fn new(
+        raft_node: Raft<TypeConfig>,
+        identity: Arc<ClusterIdentity>,
+        recovery: RecoveryWatcher,
+    ) -> Self {
+        return GrpcOpenRaftServices {
+            raft_node,
+            identity,
+            recovery,
+        };
+    }
+
+    /// Granting a vote now could elect a candidate without the entries this node lost.
+    fn check_recovered(&self) -> Result<(), Status> {
+        if let Some(index) = self.recovery.pending() {
+            return Err(Status::unavailable(format!(
+                "Node lost committed entries in a log recovery and does not vote until index {} is replicated again",
+                index
+            )));
+        }
+        return Ok(());
+    }
+
+    async fn legacy_vote(&self, value: &[u8]) -> Result<Response<VoteReply>, Status> {
+        let req: openraft::raft::VoteRequest<TypeConfig> = match decode_legacy(value) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::invalid_argument(e.to_string()));
+            }
+        };
+        legacy_sender(&self.raft_node, &req.vote)?;
+        self.check_recovered()?;
+
+        let res = match self.raft_node.vote(req).await {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        };
+        let reply = VoteReply {
+            legacy_value: legacy_reply(&res)?,
+            ..Default::default()
+        };
+        return Ok(Response::new(reply));
+    }
+
+    async fn legacy_append(&self, value: &[u8]) -> Result<Response<AppendReply>, Status> {
+        let req: openraft::raft::AppendEntriesRequest<TypeConfig> = match decode_legacy(value) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::invalid_argument(e.to_string()));
+            }
+        };
+        legacy_sender(&self.raft_node, &req.vote)?;
+
+        let res = match self.raft_node.append_entries(req).await {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        };
+        let reply = AppendReply {
+            legacy_value: legacy_reply(&res)?,
+            ..Default::default()
+        };
+        return Ok(Response::new(reply));
+    }
+
+    async fn legacy_snapshot(&self, value: &[u8]) -> Result<Response<SnapshotReply>, Status> {
+        let req: openraft::raft::InstallSnapshotRequest<TypeConfig> = match decode_legacy(value) {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::invalid_argument(e.to_string()));
+            }
+        };
+        legacy_sender(&self.raft_node, &req.vote)?;
+
+        let res = match self.raft_node.install_snapshot(req).await {
+            Ok(data) => data,
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        };
+        let reply = SnapshotReply {
+            legacy_value: legacy_reply(&res)?,
+            ..Default::default()
+        };
+        return Ok(Response::new(reply));
     }
 }
 
@@ -36,22 +142,29 @@ impl GrpcOpenRaftServices {
 impl OpenRaftService for GrpcOpenRaftServices {
     async fn vote(&self, request: Request<VoteRequest>) -> Result<Response<VoteReply>, Status> {
         let req = request.into_inner();
-        let vote_data = deserialize(&req.value).unwrap();
-        let res = match self.raft_node.vote(vote_data).await {
+        if is_legacy_request(&req.legacy_value, req.header.as_ref()) {
+            return self.legacy_vote(&req.legacy_value).await;
+        }
+        let header = check_header(&self.raft_node, &self.identity, req.header.as_ref())?;
+        let req = match vote_request_from_proto(req) {
             Ok(data) => data,
             Err(e) => {
-                return Err(Status::cancelled(e.to_string()));
+                return Err(Status::invalid_argument(e.to_string()));
             }
         };
+        if let Err(e) = verify_sender(&header, &req.vote) {
+            return Err(Status::permission_denied(e.to_string()));
+        }
+        self.check_recovered()?;
 
-        let mut reply = VoteReply::default();
-        reply.value = match serialize(&res) {
+        let res = match self.raft_node.vote(req).await {
             Ok(data) => data,
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
             }
         };
-        
+        let mut reply = vote_response_to_proto(&res);
+        reply.cluster_id = self.identity.cluster_id().unwrap_or_default();
         return Ok(Response::new(reply));
     }
 
@@ -60,20 +173,28 @@ impl OpenRaftService for GrpcOpenRaftServices {
         request: Request<AppendRequest>,
     ) -> Result<Response<AppendReply>, Status> {
         let req = request.into_inner();
-        let vote_data = deserialize(&req.value).unwrap();
-        let res = match self.raft_node.append_entries(vote_data).await {
+        if is_legacy_request(&req.legacy_value, req.header.as_ref()) {
+            return self.legacy_append(&req.legacy_value).await;
+        }
+        let header = check_header(&self.raft_node, &self.identity, req.header.as_ref())?;
+        let req = match append_request_from_proto(req) {
             Ok(data) => data,
             Err(e) => {
-                return Err(Status::cancelled(e.to_string()));
+                return Err(Status::invalid_argument(e.to_string()));
             }
         };
-        let mut reply = AppendReply::default();
-        reply.value = match serialize(&res) {
+        if let Err(e) = verify_sender(&header, &req.vote) {
+            return Err(Status::permission_denied(e.to_string()));
+        }
+
+        let res = match self.raft_node.append_entries(req).await {
             Ok(data) => data,
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
             }
         };
+        let mut reply = append_response_to_proto(&res);
+        reply.cluster_id = self.identity.cluster_id().unwrap_or_default();
         return Ok(Response::new(reply));
     }
 
@@ -82,21 +203,187 @@ impl OpenRaftService for GrpcOpenRaftServices {
         request: Request<SnapshotRequest>,
     ) -> Result<Response<SnapshotReply>, Status> {
         let req = request.into_inner();
-        let vote_data = deserialize(&req.value).unwrap();
-        let res = match self.raft_node.install_snapshot(vote_data).await {
+        if is_legacy_request(&req.legacy_value, req.header.as_ref()) {
+            return self.legacy_snapshot(&req.legacy_value).await;
+        }
+        let header = check_header(&self.raft_node, &self.identity, req.header.as_ref())?;
+        let req = match snapshot_request_from_proto(req) {
             Ok(data) => data,
             Err(e) => {
-                return Err(Status::cancelled(e.to_string()));
+                return Err(Status::invalid_argument(e.to_string()));
             }
         };
+        if let Err(e) = verify_sender(&header, &req.vote) {
+            return Err(Status::permission_denied(e.to_string()));
+        }
 
-        let mut reply = SnapshotReply::default();
-        reply.value = match serialize(&res) {
+        let res = match self.raft_node.install_snapshot(req).await {
             Ok(data) => data,
             Err(e) => {
                 return Err(Status::cancelled(e.to_string()));
             }
         };
+        let mut reply = snapshot_response_to_proto(&res);
+        reply.cluster_id = self.identity.cluster_id().unwrap_or_default();
         return Ok(Response::new(reply));
     }
+
+    type AppendStreamStream = ReceiverStream<Result<AppendStreamReply, Status>>;
+
+    async fn append_stream(
+        &self,
+        request: Request<Streaming<AppendStreamRequest>>,
+    ) -> Result<Response<Self::AppendStreamStream>, Status> {
+        let mut inbound = request.into_inner();
+        let (reply_sx, reply_rx) = mpsc::channel(1024);
+        let raft_node = self.raft_node.clone();
+        let identity = self.identity.clone();
+
+        // Frames are applied one by one in arrival order, which is the order the leader
+        // sent them in.
+        tokio::spawn(async move {
+            loop {
+                let req = match inbound.message().await {
+                    Ok(Some(req)) => req,
+                    Ok(None) => break,
+                    Err(e) => {
+                        error!("Append stream failed: {}", e);
+                        break;
+                    }
+                };
+
+                // A message from an unexpected sender ends the stream.
+                let header = match check_header(&raft_node, &identity, req.header.as_ref()) {
+                    Ok(header) => header,
+                    Err(status) => {
+                        error!("Append stream rejected: {}", status.message());
+                        let _ = reply_sx.send(Err(status)).await;
+                        break;
+                    }
+                };
+
+                let mut reply = AppendStreamReply {
+                    cluster_id: identity.cluster_id().unwrap_or_default(),
+                    ..Default::default()
+                };
+                for frame in req.frames {
+                    reply
+                        .frames
+                        .push(append_frame(&raft_node, &header, frame).await);
+                }
+                if reply_sx.send(Ok(reply)).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        return Ok(Response::new(ReceiverStream::new(reply_rx)));
+    }
+}
+
+/// Reject requests without a header or with a header naming another cluster, another
+/// node or an unknown sender.
+fn check_header(
+    raft_node: &Raft<TypeConfig>,
+    identity: &ClusterIdentity,
+    header: Option<&RaftHeader>,
+) -> Result<RaftHeader, Status> {
+    let header = match header {
+        Some(header) => header,
+        None => {
+            return Err(Status::invalid_argument(
+                RobustMQError::ParameterCannotBeNull("header".to_string()).to_string(),
+            ));
+        }
+    };
+
+    if let Err(e) = verify_header(header, placement_center_conf(), &members(raft_node)) {
+        return Err(Status::permission_denied(e.to_string()));
+    }
+    if let Err(e) = identity.check_peer(header.from_node_id, &header.cluster_id) {
+        return Err(Status::permission_denied(e.to_string()));
+    }
+    return Ok(header.clone());
+}
+
+/// Requests of nodes built before the typed fields carry no header. The sender is the node
+/// named by the vote, it must be a configured node or a member. Such nodes have no cluster
+/// id, so none is checked.
+fn legacy_sender(raft_node: &Raft<TypeConfig>, vote: &Vote<NodeId>) -> Result<(), Status> {
+    let conf = placement_center_conf();
+    let from_node_id = match vote.leader_id.voted_for {
+        Some(node_id) => node_id,
+        None => {
+            return Err(Status::permission_denied(
+                "Raft request of an older node names no sender",
+            ));
+        }
+    };
+    let header = RaftHeader {
+        cluster_name: conf.cluster_name.clone(),
+        from_node_id,
+        to_node_id: conf.node_id,
+        cluster_id: String::new(),
+    };
+    if let Err(e) = verify_header(&header, conf, &members(raft_node)) {
+        return Err(Status::permission_denied(e.to_string()));
+    }
+    return Ok(());
+}
+
+fn legacy_reply<T: serde::Serialize>(res: &T) -> Result<Vec<u8>, Status> {
+    match encode_legacy(res) {
+        Ok(data) => return Ok(data),
+        Err(e) => return Err(Status::cancelled(e.to_string())),
+    }
+}
+
+fn members(raft_node: &Raft<TypeConfig>) -> BTreeSet<NodeId> {
+    return raft_node
+        .metrics()
+        .borrow()
+        .membership_config
+        .membership()
+        .nodes()
+        .map(|(node_id, _)| *node_id)
+        .collect();
+}
+
+async fn append_frame(
+    raft_node: &Raft<TypeConfig>,
+    header: &RaftHeader,
+    frame: AppendFrame,
+) -> AppendReplyFrame {
+    let mut reply = AppendReplyFrame {
+        id: frame.id,
+        ..Default::default()
+    };
+
+    let req = match frame.request {
+        Some(request) => request,
+        None => {
+            reply.error = RobustMQError::ParameterCannotBeNull("request".to_string()).to_string();
+            return reply;
+        }
+    };
+    let req = match append_request_from_proto(req) {
+        Ok(data) => data,
+        Err(e) => {
+            reply.error = e.to_string();
+            return reply;
+        }
+    };
+    if let Err(e) = verify_sender(header, &req.vote) {
+        reply.error = e.to_string();
+        return reply;
+    }
+    let res = match raft_node.append_entries(req).await {
+        Ok(data) => data,
+        Err(e) => {
+            reply.error = e.to_string();
+            return reply;
+        }
+    };
+    reply.reply = Some(append_response_to_proto(&res));
+    return reply;
 }
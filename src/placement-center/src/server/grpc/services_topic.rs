@@ -0,0 +1,388 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    openraft::{
+        bounded_read::{leader_addr, local_read_index},
+        group_commit::GroupCommitter,
+        route::{
+            cluster::list_nodes,
+            kvs::StateKvs,
+            topic::{decode_assignment, get_topic, list_topics},
+            AppRequestData,
+        },
+        typeconfig::TypeConfig,
+    },
+    placement::{
+        assign::{assign_partition, assign_topic, node_loads, remove_load},
+        topic::{PartitionAssignment, PlacementTopic},
+    },
+    raft::node::{BrokerNode, NodeState},
+    storage::keys::key_topic,
+};
+use clients::{
+    placement::topic::call::{
+        placement_create_topic, placement_describe_topic, placement_reassign_partition,
+    },
+    poll::ClientPool,
+};
+use common_base::{
+    config::placement_center::TopicLimits, errors::RobustMQError, tools::now_second,
+};
+use openraft::Raft;
+use protocol::{
+    common::ClusterType,
+    topic::{
+        topic_service_server::TopicService, CreateTopicReply, CreateTopicRequest,
+        DescribeTopicReply, DescribeTopicRequest, PartitionAssignment as PartitionAssignmentInfo,
+        ReassignPartitionReply, ReassignPartitionRequest, TopicInfo,
+    },
+};
+use tokio::sync::RwLock;
+use tonic::{Request, Response, Status};
+
+use super::{services_cluster::cluster_type_name, services_offset::check_key_segment};
+
+pub struct GrpcTopicServices {
+    client_poll: Arc<ClientPool>,
+    raft_node: Raft<TypeConfig>,
+    group_committer: GroupCommitter,
+    kvs: Arc<RwLock<StateKvs>>,
+    limits: TopicLimits,
+}
+
+impl GrpcTopicServices {
+    pub fn new(
+        client_poll: Arc<ClientPool>,
+        raft_node: Raft<TypeConfig>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        limits: TopicLimits,
+    ) -> Self {
+        return GrpcTopicServices {
+            client_poll,
+            raft_node,
+            group_committer,
+            kvs,
+            limits,
+        };
+    }
+
+    async fn commit(&self, data: AppRequestData) -> Result<Option<String>, Status> {
+        match self.group_committer.write(data).await {
+            Ok(resp) => return Ok(resp.value),
+            Err(e) => {
+                return Err(Status::cancelled(e.to_string()));
+            }
+        }
+    }
+}
+
+/// Broker nodes that can take new replicas. Nodes that are shutting down are left out.
+fn placeable_brokers(
+    kvs: &BTreeMap<String, String>,
+    cluster_type: &String,
+    cluster_name: &String,
+) -> Vec<BrokerNode> {
+    return list_nodes(kvs, cluster_type, cluster_name)
+        .into_iter()
+        .filter(|node| node.state != NodeState::Stoping && node.state != NodeState::Stop)
+        .collect();
+}
+
+fn assignment_to_proto(assignment: PartitionAssignment) -> PartitionAssignmentInfo {
+    return PartitionAssignmentInfo {
+        partition: assignment.partition,
+        replicas: assignment.replicas,
+    };
+}
+
+fn topic_to_proto(topic: PlacementTopic) -> TopicInfo {
+    let cluster_type = ClusterType::from_str_name(&topic.cluster_type)
+        .map(|cluster_type| cluster_type as i32)
+        .unwrap_or_default();
+    return TopicInfo {
+        cluster_type,
+        cluster_name: topic.cluster_name,
+        topic_name: topic.topic_name,
+        partition_num: topic.partition_num,
+        replication_factor: topic.replication_factor,
+        partitions: topic
+            .partitions
+            .into_iter()
+            .map(assignment_to_proto)
+            .collect(),
+        create_time: topic.create_time as u64,
+    };
+}
+
+fn check_topic_name(cluster_name: &String, topic_name: &String) -> Result<(), Status> {
+    if cluster_name.is_empty() || topic_name.is_empty() {
+        return Err(Status::cancelled(
+            RobustMQError::ParameterCannotBeNull("cluster_name or topic_name".to_string())
+                .to_string(),
+        ));
+    }
+    check_key_segment("Cluster name", cluster_name)?;
+    check_key_segment("Topic", topic_name)?;
+    return Ok(());
+}
+
+#[tonic::async_trait]
+impl TopicService for GrpcTopicServices {
+    async fn create_topic(
+        &self,
+        request: Request<CreateTopicRequest>,
+    ) -> Result<Response<CreateTopicReply>, Status> {
+        let req = request.into_inner();
+        check_topic_name(&req.cluster_name, &req.topic_name)?;
+        if req.partition_num == 0 || req.replication_factor == 0 {
+            return Err(Status::invalid_argument(
+                "partition_num and replication_factor must be greater than 0",
+            ));
+        }
+        if req.partition_num > self.limits.max_partition_num
+            || req.replication_factor > self.limits.max_replication_factor
+        {
+            return Err(Status::invalid_argument(format!(
+                "partition_num must not exceed {} and replication_factor must not exceed {}",
+                self.limits.max_partition_num, self.limits.max_replication_factor
+            )));
+        }
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        // The assignment depends on the load of the whole cluster, only the leader picks it.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_create_topic(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let topic = {
+            let kvs = self.kvs.read().await;
+            let key = key_topic(&cluster_type, &req.cluster_name, &req.topic_name);
+            if kvs.contains_key(&key) {
+                return Err(Status::already_exists(format!(
+                    "Topic {} already exists",
+                    req.topic_name
+                )));
+            }
+            let brokers = placeable_brokers(&kvs, &cluster_type, &req.cluster_name);
+            let mut loads = node_loads(&list_topics(&kvs, &cluster_type, &req.cluster_name));
+            let partitions = match assign_topic(
+                &brokers,
+                &mut loads,
+                req.partition_num,
+                req.replication_factor,
+            ) {
+                Ok(partitions) => partitions,
+                Err(e) => {
+                    return Err(Status::failed_precondition(e.to_string()));
+                }
+            };
+            PlacementTopic {
+                cluster_type,
+                cluster_name: req.cluster_name,
+                topic_name: req.topic_name,
+                partition_num: req.partition_num,
+                replication_factor: req.replication_factor,
+                partitions,
+                create_time: now_second() as u128,
+            }
+        };
+
+        let topic_name = topic.topic_name.clone();
+        match self.commit(AppRequestData::CreateTopic { topic }).await? {
+            Some(value) => match serde_json::from_str::<PlacementTopic>(&value) {
+                Ok(topic) => {
+                    return Ok(Response::new(CreateTopicReply {
+                        topic: Some(topic_to_proto(topic)),
+                    }));
+                }
+                Err(e) => {
+                    return Err(Status::internal(e.to_string()));
+                }
+            },
+            None => {
+                return Err(Status::already_exists(format!(
+                    "Topic {} already exists",
+                    topic_name
+                )));
+            }
+        }
+    }
+
+    async fn describe_topic(
+        &self,
+        request: Request<DescribeTopicRequest>,
+    ) -> Result<Response<DescribeTopicReply>, Status> {
+        let req = request.into_inner();
+        check_topic_name(&req.cluster_name, &req.topic_name)?;
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        // Brokers route to the replicas of the assignment, a follower could still hand
+        // out one that was already reassigned.
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_describe_topic(self.client_poll.clone(), vec![leader_addr], req).await {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+        if let Err(e) = local_read_index(&self.raft_node).await {
+            return Err(Status::unavailable(e.to_string()));
+        }
+
+        let key = key_topic(&cluster_type, &req.cluster_name, &req.topic_name);
+        match get_topic(&*self.kvs.read().await, &key) {
+            Some(topic) => {
+                return Ok(Response::new(DescribeTopicReply {
+                    topic: Some(topic_to_proto(topic)),
+                }));
+            }
+            None => {
+                return Err(Status::not_found(format!(
+                    "Topic {} does not exist",
+                    req.topic_name
+                )));
+            }
+        }
+    }
+
+    async fn reassign_partition(
+        &self,
+        request: Request<ReassignPartitionRequest>,
+    ) -> Result<Response<ReassignPartitionReply>, Status> {
+        let req = request.into_inner();
+        check_topic_name(&req.cluster_name, &req.topic_name)?;
+        let cluster_type = cluster_type_name(req.cluster_type)?;
+
+        if let Some(leader_addr) = leader_addr(&self.raft_node) {
+            match placement_reassign_partition(self.client_poll.clone(), vec![leader_addr], req)
+                .await
+            {
+                Ok(reply) => {
+                    return Ok(Response::new(reply));
+                }
+                Err(e) => {
+                    return Err(Status::cancelled(e.to_string()));
+                }
+            }
+        }
+
+        let replicas = {
+            let kvs = self.kvs.read().await;
+            let key = key_topic(&cluster_type, &req.cluster_name, &req.topic_name);
+            let topic = match get_topic(&kvs, &key) {
+                Some(topic) => topic,
+                None => {
+                    return Err(Status::not_found(format!(
+                        "Topic {} does not exist",
+                        req.topic_name
+                    )));
+                }
+            };
+            let current = match topic.partition(req.partition) {
+                Some(assignment) => assignment.replicas.clone(),
+                None => {
+                    return Err(Status::invalid_argument(format!(
+                        "Topic {} has no partition {}",
+                        req.topic_name, req.partition
+                    )));
+                }
+            };
+            let brokers = placeable_brokers(&kvs, &cluster_type, &req.cluster_name);
+
+            if req.replicas.is_empty() {
+                // Place the partition again as if it were new, its current replicas do
+                // not count towards the load.
+                let mut loads = node_loads(&list_topics(&kvs, &cluster_type, &req.cluster_name));
+                remove_load(&mut loads, &current);
+                match assign_partition(&brokers, &mut loads, topic.replication_factor) {
+                    Ok(replicas) => replicas,
+                    Err(e) => {
+                        return Err(Status::failed_precondition(e.to_string()));
+                    }
+                }
+            } else {
+                check_replicas(&req.replicas, &brokers, topic.replication_factor)?;
+                req.replicas
+            }
+        };
+
+        let value = self
+            .commit(AppRequestData::ReassignPartition {
+                cluster_type,
+                cluster_name: req.cluster_name,
+                topic_name: req.topic_name.clone(),
+                partition: req.partition,
+                replicas,
+            })
+            .await?;
+        match value.as_ref().and_then(decode_assignment) {
+            Some(assignment) => {
+                return Ok(Response::new(ReassignPartitionReply {
+                    partition: Some(assignment_to_proto(assignment)),
+                }));
+            }
+            None => {
+                return Err(Status::not_found(format!(
+                    "Topic {} has no partition {}",
+                    req.topic_name, req.partition
+                )));
+            }
+        }
+    }
+}
+
+/// Replicas given by the caller must be distinct broker nodes that can take replicas,
+/// as many as the replication factor of the topic.
+fn check_replicas(
+    replicas: &Vec<u64>,
+    brokers: &Vec<BrokerNode>,
+    replication_factor: u32,
+) -> Result<(), Status> {
+    if replicas.len() != replication_factor as usize {
+        return Err(Status::invalid_argument(format!(
+            "Expected {} replicas, got {}",
+            replication_factor,
+            replicas.len()
+        )));
+    }
+    for (i, node_id) in replicas.iter().enumerate() {
+        if replicas[..i].contains(node_id) {
+            return Err(Status::invalid_argument(format!(
+                "Node {} is listed more than once",
+                node_id
+            )));
+        }
+        if !brokers.iter().any(|broker| broker.node_id == *node_id) {
+            return Err(Status::invalid_argument(format!(
+                "Node {} is not a registered broker node",
+                node_id
+            )));
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,58 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Query, State};
+use common_base::{
+    config::placement_center::placement_center_conf,
+    http_response::{error_response, success_response},
+    tools::now_second,
+};
+use serde::Deserialize;
+
+use super::server::HttpServerState;
+use crate::storage::backup::{create_backup, BackupMeta};
+
+#[derive(Deserialize)]
+pub struct BackupParams {
+    pub dir: String,
+}
+
+pub async fn backup(
+    State(state): State<HttpServerState>,
+    Query(params): Query<BackupParams>,
+) -> String {
+    let conf = placement_center_conf();
+    let metrics = state.raft_node.metrics().borrow().clone();
+    let meta = BackupMeta {
+        cluster_name: conf.cluster_name.clone(),
+        node_id: conf.node_id,
+        create_time: now_second(),
+        last_applied_log_id: metrics.last_applied,
+        last_membership: metrics.membership_config.as_ref().clone(),
+    };
+
+    match create_backup(
+        &params.dir,
+        &state.rocksdb_engine_handler.db,
+        &state.engine_db,
+        meta,
+    ) {
+        Ok(data) => {
+            return success_response(data);
+        }
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    }
+}
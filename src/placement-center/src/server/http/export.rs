@@ -0,0 +1,100 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::{Query, State};
+use common_base::{
+    config::placement_center::placement_center_conf,
+    http_response::{error_response, success_response},
+};
+use serde::Deserialize;
+
+use super::server::HttpServerState;
+use crate::storage::export::{export_kv, import_kv};
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    pub path: String,
+}
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    pub path: String,
+    #[serde(default = "default_import_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_import_interval_ms")]
+    pub interval_ms: u64,
+}
+
+pub fn default_import_batch_size() -> usize {
+    100
+}
+
+pub fn default_import_interval_ms() -> u64 {
+    100
+}
+
+pub async fn export(
+    State(state): State<HttpServerState>,
+    Query(params): Query<ExportParams>,
+) -> String {
+    let conf = placement_center_conf();
+    // Copy the state under the read lock so writing the file does not block the state
+    // machine. The state may already include a few entries applied after `revision`.
+    let (kvs, revision) = {
+        let kvs = state.kvs.read().await;
+        let revision = match state.raft_node.metrics().borrow().last_applied {
+            Some(log_id) => log_id.index,
+            None => 0,
+        };
+        (kvs.clone(), revision)
+    };
+    let cluster_name = conf.cluster_name.clone();
+    let path = params.path;
+    let res = tokio::task::spawn_blocking(move || {
+        return export_kv(&kvs, revision, &cluster_name, &path);
+    })
+    .await;
+    match res {
+        Ok(Ok(data)) => {
+            return success_response(data);
+        }
+        Ok(Err(e)) => {
+            return error_response(e.to_string());
+        }
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    }
+}
+
+pub async fn import(
+    State(state): State<HttpServerState>,
+    Query(params): Query<ImportParams>,
+) -> String {
+    match import_kv(
+        &state.group_committer,
+        &params.path,
+        params.batch_size,
+        params.interval_ms,
+    )
+    .await
+    {
+        Ok(data) => {
+            return success_response(data);
+        }
+        Err(e) => {
+            return error_response(e.to_string());
+        }
+    }
+}
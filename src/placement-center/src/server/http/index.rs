@@ -28,9 +28,26 @@
  * limitations under the License.
  */
 use super::server::HttpServerState;
+use crate::openraft::status;
 use axum::extract::State;
-use common_base::http_response::success_response;
-pub async fn index(State(_): State<HttpServerState>) -> String {
-    
-    return success_response("{}");
+use common_base::http_response::{error_response, success_response};
+
+pub async fn index(State(state): State<HttpServerState>) -> String {
+    return cluster_status(State(state)).await;
+}
+
+/// Every member of the placement center cluster as seen by the leader, see
+/// [`status::ClusterStatus`].
+pub async fn cluster_status(State(state): State<HttpServerState>) -> String {
+    match status::cluster_status(
+        &state.raft_node,
+        &state.client_poll,
+        &state.placement_cache,
+        &state.storage_metrics,
+    )
+    .await
+    {
+        Ok(status) => return success_response(status),
+        Err(e) => return error_response(e.to_string()),
+    }
 }
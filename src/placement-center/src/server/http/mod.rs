@@ -12,9 +12,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod backup;
+pub mod export;
 pub mod index;
 pub mod openraft;
 pub mod server;
+pub mod storage;
 
 pub(crate) fn v1_path(path: &str) -> String {
     return format!("/v1{}", path);
@@ -4,7 +4,9 @@ use axum::extract::State;
 use common_base::http_response::{error_response, success_response};
 use openraft::{error::Infallible, RaftMetrics};
 
-use crate::openraft::{raft_node::Node, route::AppRequestData, typeconfig::TypeConfig};
+use crate::openraft::{
+    learner::replication_status, raft_node::Node, route::AppRequestData, typeconfig::TypeConfig,
+};
 
 use super::server::HttpServerState;
 
@@ -63,6 +65,12 @@ pub async fn metrics(State(state): State<HttpServerState>) -> String {
     return success_response(res);
 }
 
+/// Replication progress of this node, and on the leader the lag of every follower and
+/// learner.
+pub async fn replication(State(state): State<HttpServerState>) -> String {
+    return success_response(replication_status(&state.raft_node));
+}
+
 pub async fn set(State(state): State<HttpServerState>) -> String {
     let data = AppRequestData::Set {
         key: "k1".to_string(),
@@ -12,39 +12,84 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::openraft::group_commit::GroupCommitter;
+use crate::openraft::route::kvs::StateKvs;
 use crate::openraft::typeconfig::TypeConfig;
+use crate::raft::metadata::RaftGroupMetadata;
+use crate::storage::metrics::StorageMetricsRegistry;
+use crate::storage::rocksdb::RocksDBEngine;
 
-use super::openraft::{add_leadrner, change_membership, init, kv_get, metrics, set};
+use super::backup::backup;
+use super::export::{export, import};
+use super::openraft::{
+    add_leadrner, change_membership, init, kv_get, metrics, replication, set,
+};
 use super::path_list;
-use super::{index::index, v1_path};
+use super::storage::{storage_metrics, storage_properties};
+use super::{
+    index::{cluster_status, index},
+    v1_path,
+};
 use axum::routing::{get, post};
 use axum::Router;
+use clients::poll::ClientPool;
 use common_base::config::placement_center::placement_center_conf;
 use log::info;
 use openraft::Raft;
-use std::collections::BTreeMap;
+use rocksdb::DB;
 use std::net::SocketAddr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock as StdRwLock};
 use tokio::sync::RwLock;
 use tokio::{select, sync::broadcast};
 
 pub const ROUTE_ROOT: &str = "/index";
+pub const ROUTE_CLUSTER_STATUS: &str = "/cluster/status";
 pub const ROUTE_ADD_LEARNER: &str = "/add-learner";
 pub const ROUTE_CHANGE_MEMBERSHIP: &str = "/change-membership";
 pub const ROUTE_INIT: &str = "/init";
 pub const ROUTE_METRICS: &str = "/metrics";
+pub const ROUTE_REPLICATION: &str = "/replication";
 pub const ROUTE_SET: &str = "/set";
 pub const ROUTE_GET: &str = "/get";
+pub const ROUTE_BACKUP: &str = "/backup";
+pub const ROUTE_EXPORT: &str = "/export";
+pub const ROUTE_IMPORT: &str = "/import";
+pub const ROUTE_STORAGE: &str = "/storage";
+pub const ROUTE_STORAGE_METRICS: &str = "/storage/metrics";
 
 #[derive(Clone)]
 pub struct HttpServerState {
     pub raft_node: Raft<TypeConfig>,
-    pub kvs: Arc<RwLock<BTreeMap<String, String>>>,
+    pub client_poll: Arc<ClientPool>,
+    pub group_committer: GroupCommitter,
+    pub kvs: Arc<RwLock<StateKvs>>,
+    pub rocksdb_engine_handler: Arc<RocksDBEngine>,
+    pub engine_db: Arc<DB>,
+    pub placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+    pub storage_metrics: Arc<StorageMetricsRegistry>,
 }
 
 impl HttpServerState {
-    pub fn new(raft_node: Raft<TypeConfig>, kvs: Arc<RwLock<BTreeMap<String, String>>>) -> Self {
-        return Self { raft_node, kvs };
+    pub fn new(
+        raft_node: Raft<TypeConfig>,
+        client_poll: Arc<ClientPool>,
+        group_committer: GroupCommitter,
+        kvs: Arc<RwLock<StateKvs>>,
+        rocksdb_engine_handler: Arc<RocksDBEngine>,
+        engine_db: Arc<DB>,
+        placement_cache: Arc<StdRwLock<RaftGroupMetadata>>,
+        storage_metrics: Arc<StorageMetricsRegistry>,
+    ) -> Self {
+        return Self {
+            raft_node,
+            client_poll,
+            group_committer,
+            kvs,
+            rocksdb_engine_handler,
+            engine_db,
+            placement_cache,
+            storage_metrics,
+        };
     }
 }
 
@@ -96,12 +141,19 @@ pub async fn start_http_server(state: HttpServerState, stop_sx: broadcast::Sende
 fn routes(state: HttpServerState) -> Router {
     let common = Router::new()
         .route(&v1_path(&path_list(ROUTE_ROOT)), get(index))
+        .route(&v1_path(ROUTE_CLUSTER_STATUS), get(cluster_status))
         .route(&v1_path(ROUTE_ADD_LEARNER), post(add_leadrner))
         .route(&v1_path(ROUTE_CHANGE_MEMBERSHIP), post(change_membership))
         .route(&v1_path(ROUTE_INIT), post(init))
         .route(&v1_path(ROUTE_METRICS), get(metrics))
+        .route(&v1_path(ROUTE_REPLICATION), get(replication))
         .route(&v1_path(ROUTE_SET), get(set))
-        .route(&v1_path(ROUTE_GET), get(kv_get));
+        .route(&v1_path(ROUTE_GET), get(kv_get))
+        .route(&v1_path(ROUTE_BACKUP), post(backup))
+        .route(&v1_path(ROUTE_EXPORT), post(export))
+        .route(&v1_path(ROUTE_IMPORT), post(import))
+        .route(&v1_path(ROUTE_STORAGE), get(storage_properties))
+        .route(&v1_path(ROUTE_STORAGE_METRICS), get(storage_metrics));
 
     let app = Router::new().merge(common);
     return app.with_state(state);
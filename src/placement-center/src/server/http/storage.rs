@@ -0,0 +1,28 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use axum::extract::State;
+use common_base::http_response::success_response;
+
+use super::server::HttpServerState;
+
+/// RocksDB properties of every database opened by the node, per column family.
+pub async fn storage_properties(State(state): State<HttpServerState>) -> String {
+    return success_response(state.storage_metrics.collect());
+}
+
+/// The same properties in Prometheus text format.
+pub async fn storage_metrics(State(state): State<HttpServerState>) -> String {
+    return state.storage_metrics.render_prometheus();
+}
@@ -0,0 +1,345 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{collections::BTreeMap, collections::BTreeSet, fs, path::Path};
+
+use common_base::{
+    config::placement_center::PlacementCenterConfig,
+    errors::RobustMQError,
+    tools::{copy_dir, create_fold, file_exists, read_file, unique_id},
+};
+use log::info;
+use openraft::{
+    storage::RaftStateMachine, LogId, Membership, RaftLogReader, RaftSnapshotBuilder,
+    StoredMembership, Vote,
+};
+use rocksdb::{checkpoint::Checkpoint, DB};
+use serde::{Deserialize, Serialize};
+
+use crate::openraft::{
+    network::identity::{write_cluster_id, StoredClusterId},
+    raft_node::{Node, NodeId},
+    store::new_storage,
+    typeconfig::TypeConfig,
+};
+
+pub const BACKUP_META_FILE: &str = "backup_meta.json";
+pub const STORAGE_ROCKSDB_DIR: &str = "_storage_rocksdb";
+pub const ENGINE_STORAGE_DIR: &str = "_engine_storage";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BackupMeta {
+    pub cluster_name: String,
+    pub node_id: NodeId,
+    pub create_time: u64,
+    pub last_applied_log_id: Option<LogId<NodeId>>,
+    pub last_membership: StoredMembership<TypeConfig>,
+}
+
+/// Take an online backup of a running node. Both RocksDB instances are captured
+/// with checkpoints, so the node keeps serving while the backup is written.
+pub fn create_backup(
+    backup_dir: &String,
+    storage_db: &DB,
+    engine_db: &DB,
+    meta: BackupMeta,
+) -> Result<BackupMeta, RobustMQError> {
+    if file_exists(backup_dir) {
+        return Err(RobustMQError::CommmonError(format!(
+            "Backup directory {} already exists",
+            backup_dir
+        )));
+    }
+    create_fold(backup_dir)?;
+
+    let root = Path::new(backup_dir);
+    Checkpoint::new(storage_db)?.create_checkpoint(root.join(STORAGE_ROCKSDB_DIR))?;
+    Checkpoint::new(engine_db)?.create_checkpoint(root.join(ENGINE_STORAGE_DIR))?;
+
+    fs::write(root.join(BACKUP_META_FILE), serde_json::to_vec(&meta)?)?;
+    info!(
+        "Backup was written to {}, last applied log id:{:?}",
+        backup_dir, meta.last_applied_log_id
+    );
+    return Ok(meta);
+}
+
+pub fn read_backup_meta(backup_dir: &String) -> Result<BackupMeta, RobustMQError> {
+    let path = Path::new(backup_dir).join(BACKUP_META_FILE);
+    let content = read_file(&path.to_string_lossy().to_string())?;
+    return Ok(serde_json::from_str::<BackupMeta>(&content)?);
+}
+
+/// Rebuild the data directory of a stopped node from a backup. When `seed` is set,
+/// the restored state is rewritten into a brand-new single-node cluster made of `seed`.
+pub async fn restore_backup(
+    backup_dir: &String,
+    config: &PlacementCenterConfig,
+    seed: Option<Node>,
+) -> Result<BackupMeta, RobustMQError> {
+    let meta = read_backup_meta(backup_dir)?;
+    let data_path = &config.data_path;
+
+    for dir in [STORAGE_ROCKSDB_DIR, ENGINE_STORAGE_DIR] {
+        let target = Path::new(data_path).join(dir);
+        if target.exists() {
+            return Err(RobustMQError::CommmonError(format!(
+                "{} already exists, move it away before restoring",
+                target.to_string_lossy()
+            )));
+        }
+        copy_dir(&Path::new(backup_dir).join(dir), &target)?;
+    }
+
+    if let Some(node) = seed {
+        seed_single_node(config, &meta, node).await?;
+    }
+
+    info!(
+        "Backup {} was restored to {}, last applied log id:{:?}",
+        backup_dir, data_path, meta.last_applied_log_id
+    );
+    return Ok(meta);
+}
+
+async fn seed_single_node(
+    config: &PlacementCenterConfig,
+    meta: &BackupMeta,
+    node: Node,
+) -> Result<(), RobustMQError> {
+    let last_applied = match meta.last_applied_log_id {
+        Some(log_id) => log_id,
+        None => {
+            return Err(RobustMQError::CommmonError(
+                "The backup has no applied log and cannot seed a cluster".to_string(),
+            ));
+        }
+    };
+
+    let path = Path::new(&config.data_path).join(ENGINE_STORAGE_DIR);
+    let (mut log_store, mut sm_store) = new_storage(&path, &config.rocksdb).await;
+
+    // Bring the state machine up to the applied log id recorded in the backup.
+    let start = match sm_store.data.last_applied_log_id {
+        Some(log_id) => log_id.index + 1,
+        None => 0,
+    };
+    let entries = log_store
+        .try_get_log_entries(start..=last_applied.index)
+        .await
+        .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+    sm_store
+        .apply(entries)
+        .await
+        .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+
+    let mut voters = BTreeSet::new();
+    voters.insert(node.node_id);
+    let mut nodes = BTreeMap::new();
+    nodes.insert(node.node_id, node.clone());
+    sm_store.data.last_membership =
+        StoredMembership::new(Some(last_applied), Membership::new(vec![voters], nodes));
+
+    sm_store
+        .build_snapshot()
+        .await
+        .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+
+    // Logs of the old cluster are covered by the new snapshot, and the vote keeps the
+    // old term so the next election does not go backwards.
+    let vote = Vote::new(last_applied.leader_id.term, node.node_id);
+    log_store
+        .reset_to_(last_applied, &vote)
+        .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+
+    // The seed starts a new cluster, nodes of the old one must not be able to join it.
+    let stored = StoredClusterId {
+        cluster_name: config.cluster_name.clone(),
+        cluster_id: match &config.cluster_id {
+            Some(cluster_id) => cluster_id.clone(),
+            None => unique_id(),
+        },
+    };
+    write_cluster_id(&log_store.db, &stored)?;
+
+    info!(
+        "Node {} was seeded as a single-node cluster {} at log id {}",
+        node.node_id, stored.cluster_id, last_applied
+    );
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use super::{create_backup, read_backup_meta, restore_backup, BackupMeta, ENGINE_STORAGE_DIR};
+    use crate::{
+        openraft::{
+            network::identity::{read_cluster_id, write_cluster_id, StoredClusterId},
+            raft_node::Node,
+            route::AppRequestData,
+            store::new_storage,
+        },
+        storage::rocksdb::RocksDBEngine,
+    };
+    use common_base::{config::placement_center::PlacementCenterConfig, tools::now_second};
+    use openraft::{
+        storage::{IOFlushed, RaftLogStorage},
+        CommittedLeaderId, Entry, EntryPayload, LogId, Membership, RaftLogReader, StoredMembership,
+    };
+
+    #[tokio::test]
+    async fn backup_and_restore() {
+        let root = format!("/tmp/tmp_test_backup/{}", now_second());
+        let mut config = PlacementCenterConfig::default();
+        config.data_path = format!("{}/source", root);
+
+        let engine = RocksDBEngine::new(&config);
+        let key = "/backup/k1";
+        engine
+            .write(engine.cf_cluster(), key, &"v1".to_string())
+            .unwrap();
+        let (log_store, _) = new_storage(
+            format!("{}/{}", config.data_path, ENGINE_STORAGE_DIR),
+            &config.rocksdb,
+        )
+        .await;
+
+        let backup_dir = format!("{}/backup", root);
+        let meta = BackupMeta {
+            cluster_name: "placement-test".to_string(),
+            node_id: 1,
+            create_time: now_second(),
+            last_applied_log_id: None,
+            last_membership: Default::default(),
+        };
+        create_backup(&backup_dir, &engine.db, &log_store.db, meta).unwrap();
+        assert!(create_backup(
+            &backup_dir,
+            &engine.db,
+            &log_store.db,
+            read_backup_meta(&backup_dir).unwrap()
+        )
+        .is_err());
+
+        config.data_path = format!("{}/restore", root);
+        let restored_meta = restore_backup(&backup_dir, &config, None).await.unwrap();
+        assert_eq!(restored_meta.node_id, 1);
+
+        let restored = RocksDBEngine::new(&config);
+        let value = restored.read::<String>(restored.cf_cluster(), key).unwrap();
+        assert_eq!(value, Some("v1".to_string()));
+    }
+
+    #[tokio::test]
+    async fn seed_single_node_from_multi_node_backup() {
+        let root = format!("/tmp/tmp_test_backup_seed/{}", now_second());
+        let mut config = PlacementCenterConfig::default();
+        config.data_path = format!("{}/source", root);
+
+        let engine = RocksDBEngine::new(&config);
+        let (mut log_store, _) = new_storage(
+            format!("{}/{}", config.data_path, ENGINE_STORAGE_DIR),
+            &config.rocksdb,
+        )
+        .await;
+
+        // A three voter cluster that has committed two writes.
+        let nodes: BTreeMap<u64, Node> = (1..=3)
+            .map(|node_id| {
+                let node = Node {
+                    node_id,
+                    rpc_addr: format!("127.0.0.1:{}", 1228 + node_id),
+                };
+                (node_id, node)
+            })
+            .collect();
+        let voters: BTreeSet<u64> = nodes.keys().copied().collect();
+        let membership = Membership::new(vec![voters], nodes);
+        let log_id = |index| LogId::new(CommittedLeaderId::new(2, 1), index);
+        let mut entries = vec![Entry {
+            log_id: log_id(1),
+            payload: EntryPayload::Membership(membership.clone()),
+        }];
+        for index in 2..=3 {
+            entries.push(Entry {
+                log_id: log_id(index),
+                payload: EntryPayload::Normal(AppRequestData::Set {
+                    key: format!("/seed/k{}", index),
+                    value: format!("v{}", index),
+                }),
+            });
+        }
+        log_store.append(entries, IOFlushed::noop()).await.unwrap();
+        log_store.save_committed(Some(log_id(3))).await.unwrap();
+        let old_cluster_id = StoredClusterId {
+            cluster_name: "placement-test".to_string(),
+            cluster_id: "old-cluster-id".to_string(),
+        };
+        write_cluster_id(&log_store.db, &old_cluster_id).unwrap();
+
+        let backup_dir = format!("{}/backup", root);
+        let meta = BackupMeta {
+            cluster_name: "placement-test".to_string(),
+            node_id: 1,
+            create_time: now_second(),
+            last_applied_log_id: Some(log_id(3)),
+            last_membership: StoredMembership::new(Some(log_id(1)), membership),
+        };
+        create_backup(&backup_dir, &engine.db, &log_store.db, meta).unwrap();
+
+        config.data_path = format!("{}/restore", root);
+        let seed = Node {
+            node_id: 1,
+            rpc_addr: "127.0.0.1:2228".to_string(),
+        };
+        restore_backup(&backup_dir, &config, Some(seed.clone()))
+            .await
+            .unwrap();
+
+        let (mut log_store, sm_store) = new_storage(
+            format!("{}/{}", config.data_path, ENGINE_STORAGE_DIR),
+            &config.rocksdb,
+        )
+        .await;
+
+        // The seed is the only voter and the only known node.
+        let membership = sm_store.data.last_membership.membership();
+        assert_eq!(membership.get_joint_config(), &vec![BTreeSet::from([1u64])]);
+        let nodes: Vec<(u64, Node)> = membership
+            .nodes()
+            .map(|(node_id, node)| (*node_id, node.clone()))
+            .collect();
+        assert_eq!(nodes, vec![(1, seed)]);
+
+        // The data written by the old cluster survived.
+        assert_eq!(sm_store.data.last_applied_log_id, Some(log_id(3)));
+        let kvs = sm_store.data.kvs.read().await;
+        assert_eq!(kvs.get("/seed/k2"), Some(&"v2".to_string()));
+        assert_eq!(kvs.get("/seed/k3"), Some(&"v3".to_string()));
+
+        // The old logs are covered by the snapshot, and the term does not go backwards.
+        let state = log_store.get_log_state().await.unwrap();
+        assert_eq!(state.last_purged_log_id, Some(log_id(3)));
+        assert!(log_store.try_get_log_entries(0..).await.unwrap().is_empty());
+        let vote = log_store.read_vote().await.unwrap().unwrap();
+        assert_eq!(vote.leader_id.term, 2);
+
+        // Nodes of the old cluster are refused by the new one.
+        let cluster_id = read_cluster_id(&log_store.db).unwrap().unwrap();
+        assert_eq!(cluster_id.cluster_name, config.cluster_name);
+        assert_ne!(cluster_id.cluster_id, old_cluster_id.cluster_id);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use common_base::{
+    config::placement_center::PlacementCenterConfig, errors::RobustMQError, tools::now_second,
+};
+use log::info;
+use openraft::{
+    storage::{RaftLogStorage, RaftStateMachine},
+    RaftLogReader,
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::sleep;
+
+use super::backup::ENGINE_STORAGE_DIR;
+use crate::openraft::{
+    group_commit::GroupCommitter,
+    route::{kvs::StateKvs, AppRequestData},
+    store::new_storage,
+};
+
+/// Version 2 added the revision of every record.
+pub const EXPORT_FORMAT_VERSION: u32 = 2;
+
+/// First line of an export file.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ExportHeader {
+    pub version: u32,
+    pub cluster_name: String,
+    /// Raft log index the exported state machine had applied.
+    pub revision: u64,
+    pub record_num: u64,
+    pub create_time: u64,
+}
+
+/// One line per key following the header.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportRecord {
+    pub key: String,
+    pub value: String,
+    /// Raft log index the key was last written at, 0 in version 1 files.
+    #[serde(default)]
+    pub revision: u64,
+    pub create_time: u64,
+}
+
+/// Write `kvs`, the state machine at log index `revision`, to an export file.
+pub fn export_kv(
+    kvs: &StateKvs,
+    revision: u64,
+    cluster_name: &String,
+    path: &String,
+) -> Result<ExportHeader, RobustMQError> {
+    let create_time = now_second();
+    let header = ExportHeader {
+        version: EXPORT_FORMAT_VERSION,
+        cluster_name: cluster_name.clone(),
+        revision,
+        record_num: kvs.len() as u64,
+        create_time,
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(&serde_json::to_vec(&header)?)?;
+    writer.write_all(b"\n")?;
+    for (key, value) in kvs.iter() {
+        let record = ExportRecord {
+            key: key.clone(),
+            value: value.clone(),
+            revision: kvs.revision(key),
+            create_time,
+        };
+        writer.write_all(&serde_json::to_vec(&record)?)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()?;
+
+    info!(
+        "Exported {} keys at revision {} to {}",
+        header.record_num, header.revision, path
+    );
+    return Ok(header);
+}
+
+/// State machine of a stopped node, brought up to its committed log index. Returns the
+/// keys and the log index they were read at.
+pub async fn read_stopped_state(
+    config: &PlacementCenterConfig,
+) -> Result<(StateKvs, u64), RobustMQError> {
+    let path = Path::new(&config.data_path).join(ENGINE_STORAGE_DIR);
+    let (mut log_store, mut sm_store) = new_storage(&path, &config.rocksdb).await;
+
+    let committed = log_store
+        .read_committed()
+        .await
+        .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+    let applied = sm_store.data.last_applied_log_id.map(|log_id| log_id.index);
+    if let Some(committed) = committed {
+        let start = match applied {
+            Some(index) => index + 1,
+            None => 0,
+        };
+        if start <= committed.index {
+            let entries = log_store
+                .try_get_log_entries(start..=committed.index)
+                .await
+                .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+            sm_store
+                .apply(entries)
+                .await
+                .map_err(|e| RobustMQError::CommmonError(e.to_string()))?;
+        }
+    }
+
+    let revision = match sm_store.data.last_applied_log_id {
+        Some(log_id) => log_id.index,
+        None => 0,
+    };
+    let kvs = sm_store.data.kvs.read().await.clone();
+    return Ok((kvs, revision));
+}
+
+pub fn read_export(path: &String) -> Result<(ExportHeader, Vec<ExportRecord>), RobustMQError> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+    let header = match lines.next() {
+        Some(line) => serde_json::from_str::<ExportHeader>(&line?)?,
+        None => {
+            return Err(RobustMQError::CommmonError(format!(
+                "Export file {} is empty",
+                path
+            )));
+        }
+    };
+
+    if header.version > EXPORT_FORMAT_VERSION {
+        return Err(RobustMQError::CommmonError(format!(
+            "Export file version {} is newer than the supported version {}",
+            header.version, EXPORT_FORMAT_VERSION
+        )));
+    }
+
+    let mut records = Vec::new();
+    for line in lines {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str::<ExportRecord>(&line)?);
+    }
+    return Ok((header, records));
+}
+
+/// Keys of an export as a single request that sets every one of them.
+pub fn import_request(kvs: Vec<(String, String)>) -> AppRequestData {
+    let requests = kvs
+        .into_iter()
+        .map(|(key, value)| AppRequestData::Set { key, value })
+        .collect();
+    return AppRequestData::Batch { requests };
+}
+
+/// Replay an export file through Raft, `batch_size` keys per log entry with a pause of
+/// `interval_ms` between batches so the import does not starve regular traffic.
+pub async fn import_kv(
+    group_committer: &GroupCommitter,
+    path: &String,
+    batch_size: usize,
+    interval_ms: u64,
+) -> Result<ExportHeader, RobustMQError> {
+    let (header, records) = read_export(path)?;
+    for batch in records.chunks(batch_size.max(1)) {
+        let kvs = batch
+            .iter()
+            .map(|record| (record.key.clone(), record.value.clone()))
+            .collect();
+        group_committer.write(import_request(kvs)).await?;
+        sleep(Duration::from_millis(interval_ms)).await;
+    }
+
+    info!(
+        "Imported {} keys of revision {} from {}",
+        header.record_num, header.revision, path
+    );
+    return Ok(header);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_kv, import_kv, read_export, EXPORT_FORMAT_VERSION};
+    use crate::openraft::{
+        group_commit::GroupCommitter,
+        route::{kvs::StateKvs, AppRequestData},
+    };
+    use common_base::{
+        config::placement_center::GroupCommit,
+        tools::{create_fold, now_second},
+    };
+    use std::{
+        collections::BTreeMap,
+        sync::{Arc, Mutex},
+    };
+
+    #[tokio::test]
+    async fn export_and_import() {
+        let data_path = format!("/tmp/tmp_test_export/{}", now_second());
+        create_fold(&data_path).unwrap();
+
+        let mut kvs = StateKvs::restore(BTreeMap::new(), BTreeMap::new(), 3);
+        kvs.set_revision(5);
+        kvs.insert("/k2".to_string(), "v2".to_string());
+        kvs.set_revision(6);
+        kvs.insert("/k1".to_string(), "v1".to_string());
+        kvs.set_revision(7);
+        kvs.insert("/lock/l1".to_string(), "{}".to_string());
+
+        let path = format!("{}/export.jsonl", data_path);
+        let header = export_kv(&kvs, 7, &"placement-test".to_string(), &path).unwrap();
+        assert_eq!(header.record_num, 3);
+
+        let (header, records) = read_export(&path).unwrap();
+        assert_eq!(header.version, EXPORT_FORMAT_VERSION);
+        assert_eq!(header.revision, 7);
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].key, "/k1");
+        assert_eq!(records[0].value, "v1");
+        assert_eq!(records[0].revision, 6);
+        assert_eq!(records[1].key, "/k2");
+        assert_eq!(records[1].revision, 5);
+        assert_eq!(records[2].revision, 7);
+
+        // Every batch of the import is committed as one entry, internal keys included.
+        let imported = Arc::new(Mutex::new(BTreeMap::new()));
+        let proposals = Arc::new(Mutex::new(Vec::new()));
+        let tmp_imported = imported.clone();
+        let tmp_proposals = proposals.clone();
+        let committer = GroupCommitter::start(&GroupCommit::default(), move |data| {
+            let imported = tmp_imported.clone();
+            let proposals = tmp_proposals.clone();
+            async move {
+                if let AppRequestData::Batch { requests } = data {
+                    proposals.lock().unwrap().push(requests.len());
+                    for req in requests {
+                        if let AppRequestData::Set { key, value } = req {
+                            imported.lock().unwrap().insert(key, value);
+                        }
+                    }
+                }
+                Ok(Default::default())
+            }
+        });
+        import_kv(&committer, &path, 2, 0).await.unwrap();
+        assert_eq!(*proposals.lock().unwrap(), vec![2, 1]);
+        assert_eq!(*imported.lock().unwrap(), *kvs.kvs());
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Read-only access to the data directory of a stopped node, used by the
+//! `placement-center-tool` binary to look at Raft state without starting the server.
+
+use std::{collections::BTreeMap, path::Path};
+
+use common_base::errors::RobustMQError;
+use openraft::{Entry, EntryPayload, LogId, SnapshotMeta, Vote};
+use rocksdb::{Direction, IteratorMode, Options, DB};
+use serde::Serialize;
+
+use super::backup::ENGINE_STORAGE_DIR;
+use crate::openraft::{
+    network::identity::{StoredClusterId, CLUSTER_ID_KEY},
+    raft_node::NodeId,
+    route::{kvs::StateKvs, StateWatchers},
+    store::{
+        bin_to_id,
+        codec::{decode_entry, decode_value},
+        id_to_bin,
+        state_machine_store::apply_request,
+        StoredSnapshot, CF_RAFT_LOGS, CF_RAFT_STORE,
+    },
+    typeconfig::TypeConfig,
+};
+
+#[derive(Serialize, Debug, Clone)]
+pub struct RaftStoreState {
+    pub cluster_id: Option<StoredClusterId>,
+    pub vote: Option<Vote<NodeId>>,
+    pub committed: Option<LogId<NodeId>>,
+    pub last_purged: Option<LogId<NodeId>>,
+    pub first_log_index: Option<u64>,
+    pub last_log_index: Option<u64>,
+}
+
+/// A log entry as found on disk. Entries that cannot be decoded are kept with the
+/// decode error so a damaged range can still be listed.
+#[derive(Debug)]
+pub struct InspectedEntry {
+    pub index: u64,
+    pub entry: Result<Entry<TypeConfig>, String>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct SnapshotInfo {
+    pub meta: SnapshotMeta<TypeConfig>,
+    pub data_size: usize,
+    pub key_num: usize,
+}
+
+pub struct DataDirInspector {
+    engine_db: DB,
+}
+
+impl DataDirInspector {
+    /// Open the Raft database of `data_path` read-only.
+    pub fn open(data_path: &String) -> Result<Self, RobustMQError> {
+        let opts = Options::default();
+        let engine_path = Path::new(data_path).join(ENGINE_STORAGE_DIR);
+        if !engine_path.exists() {
+            return Err(RobustMQError::CommmonError(format!(
+                "{} does not exist",
+                engine_path.to_string_lossy()
+            )));
+        }
+        let engine_db =
+            DB::open_cf_for_read_only(&opts, engine_path, [CF_RAFT_STORE, CF_RAFT_LOGS], false)?;
+        return Ok(DataDirInspector { engine_db });
+    }
+
+    pub fn raft_store_state(&self) -> Result<RaftStoreState, RobustMQError> {
+        let logs = self.logs()?;
+        let first_log_index = match self.engine_db.iterator_cf(logs, IteratorMode::Start).next() {
+            Some(res) => Some(bin_to_id(&res?.0)),
+            None => None,
+        };
+        let last_log_index = match self.engine_db.iterator_cf(logs, IteratorMode::End).next() {
+            Some(res) => Some(bin_to_id(&res?.0)),
+            None => None,
+        };
+
+        return Ok(RaftStoreState {
+            cluster_id: self.read_store_value(CLUSTER_ID_KEY)?,
+            vote: self.read_store_value(b"vote")?,
+            committed: self
+                .read_store_value::<Option<LogId<NodeId>>>(b"committed")?
+                .flatten(),
+            last_purged: self.read_store_value(b"last_purged_log_id")?,
+            first_log_index,
+            last_log_index,
+        });
+    }
+
+    /// Entries with an index in `[start, end]`.
+    pub fn log_entries(&self, start: u64, end: u64) -> Result<Vec<InspectedEntry>, RobustMQError> {
+        let from = id_to_bin(start);
+        let mut result = Vec::new();
+        for res in self
+            .engine_db
+            .iterator_cf(self.logs()?, IteratorMode::From(&from, Direction::Forward))
+        {
+            let (key, value) = res?;
+            let index = bin_to_id(&key);
+            if index > end {
+                break;
+            }
+            let entry = decode_entry(index, &value).map_err(|e| e.to_string());
+            result.push(InspectedEntry { index, entry });
+        }
+        return Ok(result);
+    }
+
+    pub fn snapshot(&self) -> Result<Option<SnapshotInfo>, RobustMQError> {
+        let snapshot: StoredSnapshot = match self.read_store_value(b"snapshot")? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        let key_num = match serde_json::from_slice::<BTreeMap<String, String>>(&snapshot.data) {
+            Ok(kvs) => kvs.len(),
+            Err(_) => 0,
+        };
+        return Ok(Some(SnapshotInfo {
+            meta: snapshot.meta,
+            data_size: snapshot.data.len(),
+            key_num,
+        }));
+    }
+
+    /// Keys of the KV state machine as captured by the stored snapshot.
+    pub fn snapshot_keys(&self, prefix: &str) -> Result<Vec<String>, RobustMQError> {
+        let snapshot: StoredSnapshot = match self.read_store_value(b"snapshot")? {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+        let kvs = serde_json::from_slice::<BTreeMap<String, String>>(&snapshot.data)?;
+        return Ok(kvs
+            .into_keys()
+            .filter(|key| key.starts_with(prefix))
+            .collect());
+    }
+
+    /// Keys of the KV state machine at the committed index: the stored snapshot with the
+    /// committed entries after it applied on top, the way the node rebuilds it on start.
+    pub fn state_keys(&self, prefix: &str) -> Result<Vec<String>, RobustMQError> {
+        let snapshot: Option<StoredSnapshot> = self.read_store_value(b"snapshot")?;
+        let (mut kvs, start) = match snapshot {
+            Some(snapshot) => {
+                let kvs = serde_json::from_slice::<BTreeMap<String, String>>(&snapshot.data)?;
+                let start = match snapshot.meta.last_log_id {
+                    Some(log_id) => log_id.index + 1,
+                    None => 0,
+                };
+                (
+                    StateKvs::restore(kvs, snapshot.revisions, start.saturating_sub(1)),
+                    start,
+                )
+            }
+            None => (StateKvs::default(), 0),
+        };
+
+        let committed = self
+            .read_store_value::<Option<LogId<NodeId>>>(b"committed")?
+            .flatten();
+        if let Some(committed) = committed {
+            // Nobody listens, the changes only matter to a running node.
+            let watchers = StateWatchers::new();
+            for raw in self.log_entries(start, committed.index)? {
+                let entry = match raw.entry {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        return Err(RobustMQError::CommmonError(format!(
+                            "Log entry {} cannot be decoded, error message: {}",
+                            raw.index, e
+                        )));
+                    }
+                };
+                if let EntryPayload::Normal(req) = entry.payload {
+                    kvs.set_revision(raw.index);
+                    apply_request(&mut kvs, &watchers, req);
+                }
+            }
+        }
+
+        return Ok(kvs
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect());
+    }
+
+    fn read_store_value<T: serde::de::DeserializeOwned>(
+        &self,
+        key: &[u8],
+    ) -> Result<Option<T>, RobustMQError> {
+        let cf = match self.engine_db.cf_handle(CF_RAFT_STORE) {
+            Some(cf) => cf,
+            None => {
+                return Err(RobustMQError::CommmonError(format!(
+                    "Column family {} does not exist",
+                    CF_RAFT_STORE
+                )));
+            }
+        };
+        return match self.engine_db.get_cf(cf, key)? {
+            Some(data) => match decode_value::<T>(&data) {
+                Ok(value) => Ok(Some(value)),
+                Err(e) => Err(RobustMQError::CommmonError(e)),
+            },
+            None => Ok(None),
+        };
+    }
+
+    fn logs(&self) -> Result<&rocksdb::ColumnFamily, RobustMQError> {
+        return match self.engine_db.cf_handle(CF_RAFT_LOGS) {
+            Some(cf) => Ok(cf),
+            None => Err(RobustMQError::CommmonError(format!(
+                "Column family {} does not exist",
+                CF_RAFT_LOGS
+            ))),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DataDirInspector;
+    use crate::{
+        openraft::{route::AppRequestData, store::new_storage},
+        storage::backup::ENGINE_STORAGE_DIR,
+    };
+    use common_base::{config::placement_center::PlacementCenterConfig, tools::now_second};
+    use openraft::{storage::RaftLogStorage, CommittedLeaderId, Entry, EntryPayload, LogId, Vote};
+
+    #[tokio::test]
+    async fn inspect_data_dir() {
+        let mut config = PlacementCenterConfig::default();
+        config.data_path = format!("/tmp/tmp_test_inspect/{}", now_second());
+
+        {
+            let (mut log_store, _) = new_storage(
+                format!("{}/{}", config.data_path, ENGINE_STORAGE_DIR),
+                &config.rocksdb,
+            )
+            .await;
+            log_store.save_vote(&Vote::new(1, 1)).await.unwrap();
+            let log_id = |index| LogId::new(CommittedLeaderId::new(1, 1), index);
+            log_store.save_committed(Some(log_id(2))).await.unwrap();
+            let requests = vec![
+                AppRequestData::Set {
+                    key: "k1".to_string(),
+                    value: "v1".to_string(),
+                },
+                AppRequestData::Set {
+                    key: "k2".to_string(),
+                    value: "v2".to_string(),
+                },
+                // Not committed yet, the key is still listed.
+                AppRequestData::Delete {
+                    key: "k1".to_string(),
+                },
+            ];
+            let entries = requests
+                .into_iter()
+                .enumerate()
+                .map(|(i, req)| Entry {
+                    log_id: log_id(i as u64 + 1),
+                    payload: EntryPayload::Normal(req),
+                })
+                .collect::<Vec<_>>();
+            log_store
+                .append(entries, openraft::storage::IOFlushed::noop())
+                .await
+                .unwrap();
+        }
+
+        let inspector = DataDirInspector::open(&config.data_path).unwrap();
+        let state = inspector.raft_store_state().unwrap();
+        assert!(state.cluster_id.is_none());
+        assert_eq!(state.vote, Some(Vote::new(1, 1)));
+        assert_eq!(state.committed.map(|id| id.index), Some(2));
+        assert!(state.last_purged.is_none());
+        assert_eq!(state.first_log_index, Some(1));
+        assert_eq!(state.last_log_index, Some(3));
+
+        let entries = inspector.log_entries(0, 10).unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].entry.is_ok());
+
+        assert!(inspector.snapshot().unwrap().is_none());
+        assert_eq!(
+            inspector.state_keys("k").unwrap(),
+            vec!["k1".to_string(), "k2".to_string()]
+        );
+        assert_eq!(inspector.state_keys("k2").unwrap(), vec!["k2".to_string()]);
+    }
+}
@@ -54,12 +54,12 @@ pub fn key_cluster_prefix_by_type(cluster_type: &String) -> String {
     return format!("/clusters/{}", cluster_type);
 }
 
-pub fn key_node(cluster_name: &String, node_id: u64) -> String {
-    return format!("/clusters/node/{}/{}", cluster_name, node_id);
+pub fn key_node(cluster_type: &String, cluster_name: &String, node_id: u64) -> String {
+    return format!("/clusters/node/{}/{}/{}", cluster_type, cluster_name, node_id);
 }
 
-pub fn key_node_prefix(cluster_name: &String) -> String {
-    return format!("/clusters/node/{}", cluster_name);
+pub fn key_node_prefix(cluster_type: &String, cluster_name: &String) -> String {
+    return format!("/clusters/node/{}/{}/", cluster_type, cluster_name);
 }
 
 pub fn key_node_prefix_all() -> String {
@@ -74,6 +74,76 @@ pub fn key_resource_idempotent(cluster_name: &String, produce_id: &String, seq_n
     return format!("/idempotent/{}/{}/{}", cluster_name, produce_id, seq_num);
 }
 
+/** ===========Placement========== */
+pub fn key_topic(cluster_type: &String, cluster_name: &String, topic_name: &String) -> String {
+    return format!(
+        "/placement/topic/{}/{}/{}",
+        cluster_type, cluster_name, topic_name
+    );
+}
+
+pub fn key_topic_prefix(cluster_type: &String, cluster_name: &String) -> String {
+    return format!("/placement/topic/{}/{}/", cluster_type, cluster_name);
+}
+
+/** ===========Election========== */
+pub fn key_election(name: &String) -> String {
+    return format!("/election/{}", name);
+}
+
+pub fn key_election_prefix() -> String {
+    return format!("/election/");
+}
+
+/** ===========Lock========== */
+pub fn key_lock(name: &String) -> String {
+    return format!("/lock/{}", name);
+}
+
+pub fn key_lock_prefix() -> String {
+    return format!("/lock/");
+}
+
+/** ===========Sequence========== */
+pub fn key_sequence(name: &String) -> String {
+    return format!("/sequence/{}", name);
+}
+
+/** ===========Offset========== */
+pub fn key_offset(group: &String, topic: &String, partition: u32) -> String {
+    return format!("/offset/{}/{}/{}", group, topic, partition);
+}
+
+pub fn key_offset_group_prefix(group: &String) -> String {
+    return format!("/offset/{}/", group);
+}
+
+pub fn key_offset_prefix() -> String {
+    return format!("/offset/");
+}
+
+/** ===========Reserved========== */
+/// Prefixes of the state owned by the election, lock, sequence, offset, MQTT, cluster and
+/// topic services. The generic KV service must not touch them, a raw write could forge a
+/// fencing token, move a sequence backwards or register a node behind the heartbeat.
+pub fn key_reserved_prefixes() -> Vec<&'static str> {
+    return vec![
+        "/sequence/",
+        "/lock/",
+        "/election/",
+        "/offset/",
+        "/mqtt/",
+        "/clusters/node/",
+        "/placement/topic/",
+    ];
+}
+
+pub fn is_reserved_key(key: &str) -> bool {
+    return key_reserved_prefixes()
+        .iter()
+        .any(|prefix| key.starts_with(prefix));
+}
+
 /** ===========Journal========== */
 pub fn key_shard(cluster_name: &String, shard_name: &String) -> String {
     return format!("/journal/shard/{}/{}", cluster_name, shard_name);
@@ -123,14 +193,18 @@ pub fn storage_key_mqtt_session(cluster_name: &String, client_id: &String) -> St
 }
 
 pub fn storage_key_mqtt_session_cluster_prefix(cluster_name: &String) -> String {
-    return format!("/mqtt/session/{}", cluster_name);
+    return format!("/mqtt/session/{}/", cluster_name);
+}
+
+pub fn storage_key_mqtt_subscription(cluster_name: &String, client_id: &String) -> String {
+    return format!("/mqtt/subscription/{}/{}", cluster_name, client_id);
 }
 
 pub fn storage_key_mqtt_last_will(cluster_name: &String, client_id: &String) -> String {
     return format!("/mqtt/lastwill/{}/{}", cluster_name, client_id);
 }
 pub fn storage_key_mqtt_last_will_prefix(cluster_name: &String) -> String {
-    return format!("/mqtt/lastwill/{}", cluster_name);
+    return format!("/mqtt/lastwill/{}/", cluster_name);
 }
 
 pub fn storage_key_mqtt_node_sub_group_leader(cluster_name: &String) -> String {
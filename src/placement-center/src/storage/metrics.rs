@@ -0,0 +1,252 @@
+// Copyright 2023 RobustMQ Team
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::{fmt::Write, sync::Arc};
+
+use dashmap::DashMap;
+use rocksdb::DB;
+use serde::{Deserialize, Serialize};
+
+const METRICS_PREFIX: &str = "placement_center_rocksdb";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ColumnFamilyMetrics {
+    pub column_family: String,
+    pub estimate_num_keys: u64,
+    pub total_sst_files_size: u64,
+    pub live_sst_files_size: u64,
+    pub cur_size_all_mem_tables: u64,
+    pub size_all_mem_tables: u64,
+    pub estimate_pending_compaction_bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DBMetrics {
+    pub db: String,
+    pub num_running_compactions: u64,
+    pub is_write_stopped: u64,
+    pub actual_delayed_write_rate: u64,
+    pub stall_micros: u64,
+    pub block_cache_usage: u64,
+    pub block_cache_hit: u64,
+    pub block_cache_miss: u64,
+    pub block_cache_hit_rate: f64,
+    pub column_families: Vec<ColumnFamilyMetrics>,
+}
+
+struct MonitoredDB {
+    db: Arc<DB>,
+    column_families: Vec<String>,
+}
+
+/// Every RocksDB instance opened by the node registers here, so its properties
+/// can be read through the admin API and scraped in Prometheus text format.
+#[derive(Default)]
+pub struct StorageMetricsRegistry {
+    dbs: DashMap<String, MonitoredDB>,
+}
+
+impl StorageMetricsRegistry {
+    pub fn new() -> Self {
+        return StorageMetricsRegistry::default();
+    }
+
+    pub fn register(&self, name: &str, db: Arc<DB>, column_families: Vec<String>) {
+        self.dbs.insert(
+            name.to_string(),
+            MonitoredDB {
+                db,
+                column_families,
+            },
+        );
+    }
+
+    pub fn collect(&self) -> Vec<DBMetrics> {
+        let mut result: Vec<DBMetrics> = self
+            .dbs
+            .iter()
+            .map(|raw| {
+                let monitored = raw.value();
+                collect_db_metrics(raw.key(), &monitored.db, &monitored.column_families)
+            })
+            .collect();
+        result.sort_by(|a, b| a.db.cmp(&b.db));
+        return result;
+    }
+
+    pub fn render_prometheus(&self) -> String {
+        let mut out = String::new();
+        for db in self.collect() {
+            let labels = format!("db=\"{}\"", db.db);
+            let db_gauges = [
+                ("num_running_compactions", db.num_running_compactions as f64),
+                ("is_write_stopped", db.is_write_stopped as f64),
+                (
+                    "actual_delayed_write_rate",
+                    db.actual_delayed_write_rate as f64,
+                ),
+                ("stall_micros", db.stall_micros as f64),
+                ("block_cache_usage", db.block_cache_usage as f64),
+                ("block_cache_hit", db.block_cache_hit as f64),
+                ("block_cache_miss", db.block_cache_miss as f64),
+                ("block_cache_hit_rate", db.block_cache_hit_rate),
+            ];
+            for (name, value) in db_gauges {
+                let _ = writeln!(out, "{}_{}{{{}}} {}", METRICS_PREFIX, name, labels, value);
+            }
+
+            for cf in db.column_families {
+                let labels = format!("db=\"{}\",cf=\"{}\"", db.db, cf.column_family);
+                let cf_gauges = [
+                    ("estimate_num_keys", cf.estimate_num_keys),
+                    ("total_sst_files_size", cf.total_sst_files_size),
+                    ("live_sst_files_size", cf.live_sst_files_size),
+                    ("cur_size_all_mem_tables", cf.cur_size_all_mem_tables),
+                    ("size_all_mem_tables", cf.size_all_mem_tables),
+                    (
+                        "estimate_pending_compaction_bytes",
+                        cf.estimate_pending_compaction_bytes,
+                    ),
+                ];
+                for (name, value) in cf_gauges {
+                    let _ = writeln!(out, "{}_{}{{{}}} {}", METRICS_PREFIX, name, labels, value);
+                }
+            }
+        }
+        return out;
+    }
+}
+
+pub fn collect_db_metrics(name: &str, db: &DB, column_families: &Vec<String>) -> DBMetrics {
+    let int_property = |property: &str| -> u64 {
+        match db.property_int_value(property) {
+            Ok(Some(value)) => value,
+            _ => 0,
+        }
+    };
+
+    // Tickers are only present in the statistics dump, which requires statistics to be
+    // enabled in the database options.
+    let statistics = match db.property_value("rocksdb.options-statistics") {
+        Ok(Some(value)) => value,
+        _ => String::new(),
+    };
+    let block_cache_hit = parse_ticker(&statistics, "rocksdb.block.cache.hit");
+    let block_cache_miss = parse_ticker(&statistics, "rocksdb.block.cache.miss");
+    let block_cache_hit_rate = if block_cache_hit + block_cache_miss == 0 {
+        0.0
+    } else {
+        block_cache_hit as f64 / (block_cache_hit + block_cache_miss) as f64
+    };
+
+    let mut cf_metrics = Vec::new();
+    for cf_name in column_families {
+        let cf = match db.cf_handle(cf_name) {
+            Some(cf) => cf,
+            None => continue,
+        };
+        let cf_property = |property: &str| -> u64 {
+            match db.property_int_value_cf(cf, property) {
+                Ok(Some(value)) => value,
+                _ => 0,
+            }
+        };
+        cf_metrics.push(ColumnFamilyMetrics {
+            column_family: cf_name.clone(),
+            estimate_num_keys: cf_property("rocksdb.estimate-num-keys"),
+            total_sst_files_size: cf_property("rocksdb.total-sst-files-size"),
+            live_sst_files_size: cf_property("rocksdb.live-sst-files-size"),
+            cur_size_all_mem_tables: cf_property("rocksdb.cur-size-all-mem-tables"),
+            size_all_mem_tables: cf_property("rocksdb.size-all-mem-tables"),
+            estimate_pending_compaction_bytes: cf_property(
+                "rocksdb.estimate-pending-compaction-bytes",
+            ),
+        });
+    }
+
+    return DBMetrics {
+        db: name.to_string(),
+        num_running_compactions: int_property("rocksdb.num-running-compactions"),
+        is_write_stopped: int_property("rocksdb.is-write-stopped"),
+        actual_delayed_write_rate: int_property("rocksdb.actual-delayed-write-rate"),
+        stall_micros: parse_ticker(&statistics, "rocksdb.stall.micros"),
+        block_cache_usage: int_property("rocksdb.block-cache-usage"),
+        block_cache_hit,
+        block_cache_miss,
+        block_cache_hit_rate,
+        column_families: cf_metrics,
+    };
+}
+
+/// Read a ticker such as `rocksdb.block.cache.hit COUNT : 42` from the statistics dump.
+fn parse_ticker(statistics: &str, ticker: &str) -> u64 {
+    for line in statistics.lines() {
+        let mut parts = line.split_whitespace();
+        if parts.next() != Some(ticker) || parts.next() != Some("COUNT") {
+            continue;
+        }
+        return match parts.nth(1) {
+            Some(value) => value.parse::<u64>().unwrap_or(0),
+            None => 0,
+        };
+    }
+    return 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_ticker, StorageMetricsRegistry};
+    use crate::storage::{
+        backup::STORAGE_ROCKSDB_DIR,
+        rocksdb::{RocksDBEngine, DB_COLUMN_FAMILY_CLUSTER},
+    };
+    use common_base::{config::placement_center::PlacementCenterConfig, tools::now_second};
+
+    #[test]
+    fn parse_ticker_test() {
+        let statistics = "rocksdb.block.cache.miss COUNT : 7\nrocksdb.block.cache.hit COUNT : 21\nrocksdb.db.get.micros P50 : 1.000000 P95 : 2.000000";
+        assert_eq!(parse_ticker(statistics, "rocksdb.block.cache.hit"), 21);
+        assert_eq!(parse_ticker(statistics, "rocksdb.block.cache.miss"), 7);
+        assert_eq!(parse_ticker(statistics, "rocksdb.stall.micros"), 0);
+    }
+
+    #[test]
+    fn collect_storage_metrics() {
+        let mut config = PlacementCenterConfig::default();
+        config.data_path = format!("/tmp/tmp_test_metrics/{}", now_second());
+        let engine = RocksDBEngine::new(&config);
+        engine
+            .write_str(engine.cf_cluster(), "/metrics/k1", "v1".to_string())
+            .unwrap();
+
+        let registry = StorageMetricsRegistry::new();
+        registry.register(
+            STORAGE_ROCKSDB_DIR,
+            engine.db.clone(),
+            vec![DB_COLUMN_FAMILY_CLUSTER.to_string()],
+        );
+
+        let metrics = registry.collect();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].db, STORAGE_ROCKSDB_DIR);
+        assert_eq!(metrics[0].column_families.len(), 1);
+        assert!(metrics[0].column_families[0].estimate_num_keys >= 1);
+        assert!(metrics[0].column_families[0].cur_size_all_mem_tables > 0);
+
+        let text = registry.render_prometheus();
+        assert!(text.contains(
+            "placement_center_rocksdb_estimate_num_keys{db=\"_storage_rocksdb\",cf=\"cluster\"}"
+        ));
+    }
+}
@@ -15,8 +15,12 @@
 use common_base::tools::now_second;
 use serde::{Deserialize, Serialize};
 
+pub mod backup;
 pub mod engine;
+pub mod export;
+pub mod inspect;
 pub mod kv;
+pub mod metrics;
 pub mod rocksdb;
 pub mod raft;
 pub mod keys;
@@ -12,32 +12,33 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use common_base::config::placement_center::PlacementCenterConfig;
+use common_base::config::placement_center::{PlacementCenterConfig, RocksDB};
 use common_base::errors::RobustMQError;
 use log::error;
 use rocksdb::SliceTransform;
-use rocksdb::{ColumnFamily, DBCompactionStyle, Options, DB};
+use rocksdb::{BlockBasedOptions, Cache, ColumnFamily, ColumnFamilyDescriptor, Options, DB};
 use serde::{de::DeserializeOwned, Serialize};
 use serde_json;
 use std::collections::HashMap;
 use std::path::Path;
+use std::sync::{Arc, OnceLock};
 
 pub const DB_COLUMN_FAMILY_CLUSTER: &str = "cluster";
 
-fn column_family_list() -> Vec<String> {
+pub fn column_family_list() -> Vec<String> {
     let mut list = Vec::new();
     list.push(DB_COLUMN_FAMILY_CLUSTER.to_string());
     return list;
 }
 
 pub struct RocksDBEngine {
-    pub db: DB,
+    pub db: Arc<DB>,
 }
 
 impl RocksDBEngine {
     /// Create a rocksdb instance
     pub fn new(config: &PlacementCenterConfig) -> Self {
-        let opts: Options = Self::open_db_opts();
+        let opts: Options = open_db_opts(&config.rocksdb);
         let db_path = format!("{}/{}", config.data_path, "_storage_rocksdb");
 
         // init RocksDB
@@ -45,22 +46,39 @@ impl RocksDBEngine {
             DB::open(&opts, db_path.clone()).unwrap();
         }
 
-        // init column family
-        let cf_list = rocksdb::DB::list_cf(&opts, &db_path).unwrap();
-        let mut instance = DB::open_cf(&opts, db_path.clone(), &cf_list).unwrap();
+        // init column family, missing ones are created on open
+        let mut cf_list = rocksdb::DB::list_cf(&opts, &db_path).unwrap();
+        for family in column_family_list() {
+            if !cf_list.contains(&family) {
+                cf_list.push(family);
+            }
+        }
 
-        for family in column_family_list().iter() {
-            if cf_list.iter().find(|cf| cf == &family).is_none() {
-                match instance.create_cf(&family, &opts) {
-                    Ok(()) => {}
-                    Err(e) => {
-                        panic!("{}", e);
-                    }
+        let cache = block_cache(&config.rocksdb);
+        let mut descriptors = Vec::new();
+        for name in cf_list.iter() {
+            let mut cf_opts = match open_cf_opts(&config.rocksdb, name, &cache) {
+                Ok(opts) => opts,
+                Err(e) => {
+                    panic!("{}", e);
                 }
-            }
+            };
+            let transform = SliceTransform::create_fixed_prefix(10);
+            cf_opts.set_prefix_extractor(transform);
+            cf_opts.set_memtable_prefix_bloom_ratio(0.2);
+            descriptors.push(ColumnFamilyDescriptor::new(name, cf_opts));
         }
 
-        return RocksDBEngine { db: instance };
+        let instance = match DB::open_cf_descriptors(&opts, db_path.clone(), descriptors) {
+            Ok(db) => db,
+            Err(e) => {
+                panic!("{}", e);
+            }
+        };
+
+        return RocksDBEngine {
+            db: Arc::new(instance),
+        };
     }
 
     /// Write the data serialization to RocksDB
@@ -195,36 +213,67 @@ impl RocksDBEngine {
         return self.db.cf_handle(&DB_COLUMN_FAMILY_CLUSTER).unwrap();
     }
 
-    fn open_db_opts() -> Options {
-        let mut opts = Options::default();
-        opts.create_if_missing(true);
-        opts.create_missing_column_families(true);
-        opts.set_max_open_files(1000);
-        opts.set_use_fsync(false);
-        opts.set_bytes_per_sync(8388608);
-        opts.optimize_for_point_lookup(1024);
-        opts.set_table_cache_num_shard_bits(6);
-        opts.set_max_write_buffer_number(32);
-        opts.set_write_buffer_size(536870912);
-        opts.set_target_file_size_base(1073741824);
-        opts.set_min_write_buffer_number_to_merge(4);
-        opts.set_level_zero_stop_writes_trigger(2000);
-        opts.set_level_zero_slowdown_writes_trigger(0);
-        opts.set_compaction_style(DBCompactionStyle::Universal);
-        opts.set_disable_auto_compactions(true);
-
-        let transform = SliceTransform::create_fixed_prefix(10);
-        opts.set_prefix_extractor(transform);
-        opts.set_memtable_prefix_bloom_ratio(0.2);
-
-        return opts;
-    }
-
     pub fn get_column_family(&self) -> &ColumnFamily {
         return self.cf_cluster();
     }
 }
 
+/// Database wide options built from the `[rocksdb]` section of the configuration.
+pub fn open_db_opts(config: &RocksDB) -> Options {
+    let conf = config.resolve();
+    let mut opts = Options::default();
+    opts.create_if_missing(true);
+    opts.create_missing_column_families(true);
+    opts.set_max_open_files(conf.max_open_files.unwrap());
+    opts.set_use_fsync(conf.use_fsync.unwrap());
+    opts.set_bytes_per_sync(conf.bytes_per_sync.unwrap());
+    opts.set_table_cache_num_shard_bits(6);
+    // Block cache and stall tickers exported by storage::metrics come from statistics.
+    opts.enable_statistics();
+    return opts;
+}
+
+static BLOCK_CACHE: OnceLock<Cache> = OnceLock::new();
+
+/// The block cache is shared by all column families of every database of the process, so
+/// `block_cache_size` bounds the memory of the state and Raft databases together. The
+/// size of the first database opened wins.
+pub fn block_cache(config: &RocksDB) -> Cache {
+    return BLOCK_CACHE
+        .get_or_init(|| Cache::new_lru_cache(config.resolve().block_cache_size.unwrap()))
+        .clone();
+}
+
+/// Options of the column family `cf_name`, using the per column family overrides
+/// of the configuration when present.
+pub fn open_cf_opts(
+    config: &RocksDB,
+    cf_name: &str,
+    cache: &Cache,
+) -> Result<Options, RobustMQError> {
+    let compression = config.compression().map_err(RobustMQError::CommmonError)?;
+    let compaction_style = config
+        .compaction_style(cf_name)
+        .map_err(RobustMQError::CommmonError)?;
+    let conf = config.column_family(cf_name);
+    let mut opts = Options::default();
+    opts.set_write_buffer_size(conf.write_buffer_size.unwrap());
+    opts.set_max_write_buffer_number(conf.max_write_buffer_number.unwrap());
+    opts.set_min_write_buffer_number_to_merge(conf.min_write_buffer_number_to_merge.unwrap());
+    opts.set_target_file_size_base(conf.target_file_size_base.unwrap());
+    opts.set_level_zero_slowdown_writes_trigger(conf.level_zero_slowdown_writes_trigger.unwrap());
+    opts.set_level_zero_stop_writes_trigger(conf.level_zero_stop_writes_trigger.unwrap());
+    opts.set_compaction_style(compaction_style);
+    opts.set_disable_auto_compactions(conf.disable_auto_compactions.unwrap());
+    opts.set_compression_type(compression);
+
+    let mut block_opts = BlockBasedOptions::default();
+    block_opts.set_block_cache(cache);
+    block_opts.set_bloom_filter(10.0, false);
+    opts.set_block_based_table_factory(&block_opts);
+    return Ok(opts);
+}
+
 #[cfg(test)]
 mod tests {
     use super::RocksDBEngine;
@@ -19,7 +19,7 @@ mod tests {
     use axum::http::request;
     use protocol::kv::{
         kv_service_client::KvServiceClient, DeleteRequest, ExistsReply, ExistsRequest, GetRequest,
-        SetRequest,
+        ScanRequest, SetRequest, StalenessBound,
     };
 
     #[tokio::test]
@@ -40,10 +40,37 @@ mod tests {
         let exist_reply = client.exists(request).await.unwrap().into_inner();
         assert!(exist_reply.flag);
 
-        let request = tonic::Request::new(GetRequest { key: key.clone() });
+        let request = tonic::Request::new(GetRequest {
+            key: key.clone(),
+            staleness: None,
+        });
         let get_reply = client.get(request).await.unwrap().into_inner();
         assert_eq!(get_reply.value, value);
 
+        let request = tonic::Request::new(GetRequest {
+            key: key.clone(),
+            staleness: Some(StalenessBound {
+                max_entries: Some(0),
+                max_ms: None,
+            }),
+        });
+        let get_reply = client.get(request).await.unwrap().into_inner();
+        assert_eq!(get_reply.value, value);
+
+        let request = tonic::Request::new(ScanRequest {
+            prefix: "m".to_string(),
+            limit: 0,
+            staleness: Some(StalenessBound {
+                max_entries: None,
+                max_ms: Some(500),
+            }),
+        });
+        let scan_reply = client.scan(request).await.unwrap().into_inner();
+        assert!(scan_reply
+            .kvs
+            .iter()
+            .any(|kv| kv.key == key && kv.value == value));
+
         let request = tonic::Request::new(DeleteRequest { key: key.clone() });
         let _ = client.delete(request).await.unwrap().into_inner();
 
@@ -1,4 +1,17 @@
 // This file is @generated by prost-build.
+/// How stale a read served by a follower or learner may be. Without a bound the node
+/// answers from its local state, however far behind it is.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StalenessBound {
+    /// Log entries the node may be behind the commit index of the leader. A node that did
+    /// not reach the leader within an election timeout refuses the read
+    #[prost(uint64, optional, tag = "1")]
+    pub max_entries: ::core::option::Option<u64>,
+    /// Milliseconds since the node was last known to be in sync with the leader
+    #[prost(uint64, optional, tag = "2")]
+    pub max_ms: ::core::option::Option<u64>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SetRequest {
@@ -12,6 +25,8 @@ pub struct SetRequest {
 pub struct GetRequest {
     #[prost(string, tag = "1")]
     pub key: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub staleness: ::core::option::Option<StalenessBound>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -39,6 +54,70 @@ pub struct ExistsReply {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScanRequest {
+    #[prost(string, tag = "1")]
+    pub prefix: ::prost::alloc::string::String,
+    /// 0 returns every matching key
+    #[prost(uint64, tag = "2")]
+    pub limit: u64,
+    #[prost(message, optional, tag = "3")]
+    pub staleness: ::core::option::Option<StalenessBound>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct KeyValue {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ScanReply {
+    #[prost(message, repeated, tag = "1")]
+    pub kvs: ::prost::alloc::vec::Vec<KeyValue>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadIndexRequest {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReadIndexReply {
+    /// Unset when nothing has been committed yet
+    #[prost(uint64, optional, tag = "1")]
+    pub read_index: ::core::option::Option<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IncrRequest {
+    #[prost(string, tag = "1")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(int64, tag = "2")]
+    pub delta: i64,
+    /// The increment is refused when the new value would fall below the floor or rise
+    /// above the ceiling
+    #[prost(int64, optional, tag = "3")]
+    pub floor: ::core::option::Option<i64>,
+    #[prost(int64, optional, tag = "4")]
+    pub ceiling: ::core::option::Option<i64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IncrReply {
+    /// The new value, or the current one when the increment was refused
+    #[prost(int64, tag = "1")]
+    pub value: i64,
+    #[prost(bool, tag = "2")]
+    pub applied: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub kvs: ::prost::alloc::vec::Vec<KeyValue>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct CommonReply {}
 /// Generated client implementations.
 pub mod kv_service_client {
@@ -205,6 +284,89 @@ pub mod kv_service_client {
             req.extensions_mut().insert(GrpcMethod::new("kv.KvService", "exists"));
             self.inner.unary(req, path, codec).await
         }
+        /// List the keys with a prefix, ordered by key
+        pub async fn scan(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ScanRequest>,
+        ) -> std::result::Result<tonic::Response<super::ScanReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/kv.KvService/scan");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("kv.KvService", "scan"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Index a read must have applied to observe every write committed before the call,
+        /// answered by the leader
+        pub async fn read_index(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReadIndexRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReadIndexReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/kv.KvService/read_index");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("kv.KvService", "read_index"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Add a delta to an integer-valued key and return the new value, a missing key counts
+        /// as 0
+        pub async fn incr(
+            &mut self,
+            request: impl tonic::IntoRequest<super::IncrRequest>,
+        ) -> std::result::Result<tonic::Response<super::IncrReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/kv.KvService/incr");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("kv.KvService", "incr"));
+            self.inner.unary(req, path, codec).await
+        }
+        /// Write the keys of an export as one log entry. Keys under the internal prefixes are
+        /// rejected, they are restored with a backup or the HTTP import of the node
+        pub async fn import(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ImportRequest>,
+        ) -> std::result::Result<tonic::Response<super::CommonReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/kv.KvService/import");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("kv.KvService", "import"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -234,6 +396,29 @@ pub mod kv_service_server {
             &self,
             request: tonic::Request<super::ExistsRequest>,
         ) -> std::result::Result<tonic::Response<super::ExistsReply>, tonic::Status>;
+        /// List the keys with a prefix, ordered by key
+        async fn scan(
+            &self,
+            request: tonic::Request<super::ScanRequest>,
+        ) -> std::result::Result<tonic::Response<super::ScanReply>, tonic::Status>;
+        /// Index a read must have applied to observe every write committed before the call,
+        /// answered by the leader
+        async fn read_index(
+            &self,
+            request: tonic::Request<super::ReadIndexRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReadIndexReply>, tonic::Status>;
+        /// Add a delta to an integer-valued key and return the new value, a missing key counts
+        /// as 0
+        async fn incr(
+            &self,
+            request: tonic::Request<super::IncrRequest>,
+        ) -> std::result::Result<tonic::Response<super::IncrReply>, tonic::Status>;
+        /// Write the keys of an export as one log entry. Keys under the internal prefixes are
+        /// rejected, they are restored with a backup or the HTTP import of the node
+        async fn import(
+            &self,
+            request: tonic::Request<super::ImportRequest>,
+        ) -> std::result::Result<tonic::Response<super::CommonReply>, tonic::Status>;
     }
     #[derive(Debug)]
     pub struct KvServiceServer<T: KvService> {
@@ -490,6 +675,184 @@ pub mod kv_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/kv.KvService/scan" => {
+                    #[allow(non_camel_case_types)]
+                    struct scanSvc<T: KvService>(pub Arc<T>);
+                    impl<T: KvService> tonic::server::UnaryService<super::ScanRequest>
+                    for scanSvc<T> {
+                        type Response = super::ScanReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ScanRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as KvService>::scan(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = scanSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/kv.KvService/read_index" => {
+                    #[allow(non_camel_case_types)]
+                    struct read_indexSvc<T: KvService>(pub Arc<T>);
+                    impl<
+                        T: KvService,
+                    > tonic::server::UnaryService<super::ReadIndexRequest>
+                    for read_indexSvc<T> {
+                        type Response = super::ReadIndexReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReadIndexRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as KvService>::read_index(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = read_indexSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/kv.KvService/incr" => {
+                    #[allow(non_camel_case_types)]
+                    struct incrSvc<T: KvService>(pub Arc<T>);
+                    impl<T: KvService> tonic::server::UnaryService<super::IncrRequest>
+                    for incrSvc<T> {
+                        type Response = super::IncrReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::IncrRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as KvService>::incr(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = incrSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/kv.KvService/import" => {
+                    #[allow(non_camel_case_types)]
+                    struct importSvc<T: KvService>(pub Arc<T>);
+                    impl<T: KvService> tonic::server::UnaryService<super::ImportRequest>
+                    for importSvc<T> {
+                        type Response = super::CommonReply;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ImportRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as KvService>::import(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = importSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
@@ -15,4 +15,11 @@
 pub mod kv;
 pub mod placement;
 pub mod common;
-pub mod openraft;
\ No newline at end of file
+pub mod openraft;
+pub mod cluster;
+pub mod topic;
+pub mod election;
+pub mod lock;
+pub mod id;
+pub mod mqtt;
+pub mod offset;
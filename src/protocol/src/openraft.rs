@@ -1,39 +1,267 @@
 // This file is @generated by prost-build.
+/// Identity of the sender, checked by the receiver before a request reaches Raft.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RaftHeader {
+    #[prost(string, tag = "1")]
+    pub cluster_name: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "2")]
+    pub from_node_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub to_node_id: u64,
+    /// Persisted id of the cluster the sender belongs to, empty until it knows one.
+    #[prost(string, tag = "4")]
+    pub cluster_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Vote {
+    #[prost(uint64, tag = "1")]
+    pub term: u64,
+    #[prost(uint64, optional, tag = "2")]
+    pub voted_for: ::core::option::Option<u64>,
+    #[prost(bool, tag = "3")]
+    pub committed: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LogId {
+    #[prost(uint64, tag = "1")]
+    pub term: u64,
+    #[prost(uint64, tag = "2")]
+    pub node_id: u64,
+    #[prost(uint64, tag = "3")]
+    pub index: u64,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Node {
+    #[prost(uint64, tag = "1")]
+    pub node_id: u64,
+    #[prost(string, tag = "2")]
+    pub rpc_addr: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct NodeIdSet {
+    #[prost(uint64, repeated, tag = "1")]
+    pub node_ids: ::prost::alloc::vec::Vec<u64>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Membership {
+    /// One set for a uniform config, two while a joint config is in effect.
+    #[prost(message, repeated, tag = "1")]
+    pub configs: ::prost::alloc::vec::Vec<NodeIdSet>,
+    #[prost(map = "uint64, message", tag = "2")]
+    pub nodes: ::std::collections::HashMap<u64, Node>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StoredMembership {
+    #[prost(message, optional, tag = "1")]
+    pub log_id: ::core::option::Option<LogId>,
+    #[prost(message, optional, tag = "2")]
+    pub membership: ::core::option::Option<Membership>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EntryBlank {}
+/// A replicated request with the request version of the node that wrote it. A node that
+/// cannot decode a request of a newer version reports that it needs an upgrade.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AppData {
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+    /// JSON encoded AppRequestData.
+    #[prost(bytes = "vec", tag = "2")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Entry {
+    #[prost(message, optional, tag = "1")]
+    pub log_id: ::core::option::Option<LogId>,
+    #[prost(oneof = "entry::Payload", tags = "2, 5, 4")]
+    pub payload: ::core::option::Option<entry::Payload>,
+}
+/// Nested message and enum types in `Entry`.
+pub mod entry {
+    #[allow(clippy::derive_partial_eq_without_eq)]
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Payload {
+        #[prost(message, tag = "2")]
+        Blank(super::EntryBlank),
+        #[prost(message, tag = "5")]
+        Normal(super::AppData),
+        #[prost(message, tag = "4")]
+        Membership(super::Membership),
+    }
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SnapshotMeta {
+    #[prost(message, optional, tag = "1")]
+    pub last_log_id: ::core::option::Option<LogId>,
+    #[prost(message, optional, tag = "2")]
+    pub last_membership: ::core::option::Option<StoredMembership>,
+    #[prost(string, tag = "3")]
+    pub snapshot_id: ::prost::alloc::string::String,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VoteRequest {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub vote: ::core::option::Option<Vote>,
+    #[prost(message, optional, tag = "3")]
+    pub last_log_id: ::core::option::Option<LogId>,
+    #[prost(message, optional, tag = "4")]
+    pub header: ::core::option::Option<RaftHeader>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VoteReply {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub vote: ::core::option::Option<Vote>,
+    #[prost(bool, tag = "3")]
+    pub vote_granted: bool,
+    #[prost(message, optional, tag = "4")]
+    pub last_log_id: ::core::option::Option<LogId>,
+    #[prost(string, tag = "5")]
+    pub cluster_id: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AppendRequest {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub vote: ::core::option::Option<Vote>,
+    #[prost(message, optional, tag = "3")]
+    pub prev_log_id: ::core::option::Option<LogId>,
+    #[prost(message, repeated, tag = "4")]
+    pub entries: ::prost::alloc::vec::Vec<Entry>,
+    #[prost(message, optional, tag = "5")]
+    pub leader_commit: ::core::option::Option<LogId>,
+    /// Unset on frames of an append stream, the stream message carries it.
+    #[prost(message, optional, tag = "6")]
+    pub header: ::core::option::Option<RaftHeader>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AppendReply {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(enumeration = "AppendResult", tag = "2")]
+    pub result: i32,
+    /// Last log id accepted on PARTIAL_SUCCESS.
+    #[prost(message, optional, tag = "3")]
+    pub matching: ::core::option::Option<LogId>,
+    /// Vote of the follower on HIGHER_VOTE.
+    #[prost(message, optional, tag = "4")]
+    pub higher_vote: ::core::option::Option<Vote>,
+    #[prost(string, tag = "5")]
+    pub cluster_id: ::prost::alloc::string::String,
+}
+/// One message may carry several coalesced append requests.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AppendStreamRequest {
+    #[prost(message, repeated, tag = "1")]
+    pub frames: ::prost::alloc::vec::Vec<AppendFrame>,
+    #[prost(message, optional, tag = "2")]
+    pub header: ::core::option::Option<RaftHeader>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AppendFrame {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub request: ::core::option::Option<AppendRequest>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AppendStreamReply {
+    #[prost(message, repeated, tag = "1")]
+    pub frames: ::prost::alloc::vec::Vec<AppendReplyFrame>,
+    #[prost(string, tag = "2")]
+    pub cluster_id: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AppendReplyFrame {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(message, optional, tag = "2")]
+    pub reply: ::core::option::Option<AppendReply>,
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SnapshotRequest {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub vote: ::core::option::Option<Vote>,
+    #[prost(message, optional, tag = "3")]
+    pub meta: ::core::option::Option<SnapshotMeta>,
+    #[prost(uint64, tag = "4")]
+    pub offset: u64,
+    #[prost(bytes = "vec", tag = "5")]
+    pub data: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bool, tag = "6")]
+    pub done: bool,
+    #[prost(message, optional, tag = "7")]
+    pub header: ::core::option::Option<RaftHeader>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SnapshotReply {
     #[prost(bytes = "vec", tag = "1")]
-    pub value: ::prost::alloc::vec::Vec<u8>,
+    pub legacy_value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(message, optional, tag = "2")]
+    pub vote: ::core::option::Option<Vote>,
+    #[prost(string, tag = "3")]
+    pub cluster_id: ::prost::alloc::string::String,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum AppendResult {
+    Success = 0,
+    PartialSuccess = 1,
+    Conflict = 2,
+    HigherVote = 3,
+}
+impl AppendResult {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            AppendResult::Success => "APPEND_RESULT_SUCCESS",
+            AppendResult::PartialSuccess => "APPEND_RESULT_PARTIAL_SUCCESS",
+            AppendResult::Conflict => "APPEND_RESULT_CONFLICT",
+            AppendResult::HigherVote => "APPEND_RESULT_HIGHER_VOTE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "APPEND_RESULT_SUCCESS" => Some(Self::Success),
+            "APPEND_RESULT_PARTIAL_SUCCESS" => Some(Self::PartialSuccess),
+            "APPEND_RESULT_CONFLICT" => Some(Self::Conflict),
+            "APPEND_RESULT_HIGHER_VOTE" => Some(Self::HigherVote),
+            _ => None,
+        }
+    }
 }
 /// Generated client implementations.
 pub mod open_raft_service_client {
@@ -186,6 +414,33 @@ pub mod open_raft_service_client {
                 .insert(GrpcMethod::new("openraft.OpenRaftService", "snapshot"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn append_stream(
+            &mut self,
+            request: impl tonic::IntoStreamingRequest<
+                Message = super::AppendStreamRequest,
+            >,
+        ) -> std::result::Result<
+            tonic::Response<tonic::codec::Streaming<super::AppendStreamReply>>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/openraft.OpenRaftService/AppendStream",
+            );
+            let mut req = request.into_streaming_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("openraft.OpenRaftService", "AppendStream"));
+            self.inner.streaming(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -207,6 +462,19 @@ pub mod open_raft_service_server {
             &self,
             request: tonic::Request<super::SnapshotRequest>,
         ) -> std::result::Result<tonic::Response<super::SnapshotReply>, tonic::Status>;
+        /// Server streaming response type for the AppendStream method.
+        type AppendStreamStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::AppendStreamReply, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn append_stream(
+            &self,
+            request: tonic::Request<tonic::Streaming<super::AppendStreamRequest>>,
+        ) -> std::result::Result<
+            tonic::Response<Self::AppendStreamStream>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct OpenRaftServiceServer<T: OpenRaftService> {
@@ -424,6 +692,55 @@ pub mod open_raft_service_server {
                     };
                     Box::pin(fut)
                 }
+                "/openraft.OpenRaftService/AppendStream" => {
+                    #[allow(non_camel_case_types)]
+                    struct AppendStreamSvc<T: OpenRaftService>(pub Arc<T>);
+                    impl<
+                        T: OpenRaftService,
+                    > tonic::server::StreamingService<super::AppendStreamRequest>
+                    for AppendStreamSvc<T> {
+                        type Response = super::AppendStreamReply;
+                        type ResponseStream = T::AppendStreamStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                tonic::Streaming<super::AppendStreamRequest>,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as OpenRaftService>::append_stream(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = AppendStreamSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         Ok(
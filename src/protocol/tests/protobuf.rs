@@ -25,6 +25,13 @@ mod tests {
                     "src/kv.proto",
                     "src/placement.proto",
                     "src/openraft.proto",
+                    "src/cluster.proto",
+                    "src/topic.proto",
+                    "src/election.proto",
+                    "src/lock.proto",
+                    "src/id.proto",
+                    "src/mqtt.proto",
+                    "src/offset.proto",
                     ],
                 &["src/"], // specify the root location to search proto dependencies
             )